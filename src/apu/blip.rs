@@ -0,0 +1,113 @@
+use std::f64::consts::PI;
+
+/// Length of the band-limited step, in output samples
+const TAPS: usize = 16;
+/// Sub-sample positions the step is precomputed for
+const PHASES: usize = 64;
+/// Bandwidth of the kernel relative to the output's Nyquist frequency
+const CUTOFF: f64 = 0.9;
+
+/// Windowed sinc impulses for each phase, each sums to 1
+fn kernel() -> Vec<[f32; TAPS]> {
+    (0..PHASES)
+        .map(|phase| {
+            let frac = phase as f64 / PHASES as f64;
+            let mut taps = [0.0; TAPS];
+            let mut weights = [0.0; TAPS];
+            for (k, weight) in weights.iter_mut().enumerate() {
+                // Distance from the center of the impulse, which is TAPS / 2 samples late
+                let x = k as f64 - (TAPS / 2) as f64 - frac;
+                let sinc = if x == 0.0 {
+                    1.0
+                } else {
+                    (PI * CUTOFF * x).sin() / (PI * CUTOFF * x)
+                };
+                let t = 2.0 * PI * x / TAPS as f64;
+                let blackman = 0.42 + 0.5 * t.cos() + 0.08 * (2.0 * t).cos();
+                *weight = sinc * blackman.max(0.0);
+            }
+            let sum: f64 = weights.iter().sum();
+            for (tap, weight) in taps.iter_mut().zip(weights) {
+                *tap = (weight / sum) as f32;
+            }
+            taps
+        })
+        .collect()
+}
+
+/// Band-limited step synthesis, in the style of blip_buf
+/// Changes in amplitude are added as band-limited steps at their exact (fractional) output
+/// sample position, reading integrates them back into samples without aliasing
+/// http://www.slack.net/~ant/bl-synth/
+#[derive(Debug)]
+pub(super) struct BlipBuffer {
+    kernel: Vec<[f32; TAPS]>,
+    /// Output samples per input clock
+    ratio: f64,
+    /// Position of the current clock, in samples from the start of `deltas`
+    time: f64,
+    /// Left and right amplitude changes
+    deltas: [Vec<f32>; 2],
+    /// Running sums of the deltas already read
+    levels: [f32; 2],
+}
+
+impl BlipBuffer {
+    pub fn new(ratio: f64) -> Self {
+        Self {
+            kernel: kernel(),
+            ratio,
+            time: 0.0,
+            deltas: [vec![0.0; TAPS], vec![0.0; TAPS]],
+            levels: [0.0; 2],
+        }
+    }
+
+    pub fn set_ratio(&mut self, ratio: f64) {
+        self.ratio = ratio;
+    }
+
+    /// Adds an amplitude change to `channel` (0 left, 1 right) at the current clock
+    pub fn add_delta(&mut self, channel: usize, delta: f32) {
+        let index = self.time as usize;
+        let phase = (((self.time - index as f64) * PHASES as f64) as usize).min(PHASES - 1);
+        let deltas = &mut self.deltas[channel];
+        if deltas.len() < index + TAPS {
+            deltas.resize(index + TAPS, 0.0);
+        }
+        for (sample, tap) in deltas[index..index + TAPS]
+            .iter_mut()
+            .zip(&self.kernel[phase])
+        {
+            *sample += delta * tap;
+        }
+    }
+
+    pub fn advance(&mut self, clocks: u16) {
+        self.time += clocks as f64 * self.ratio;
+    }
+
+    /// Samples that no later delta can change anymore
+    pub fn available(&self) -> usize {
+        self.time as usize
+    }
+
+    /// Passes every available stereo sample to `sink`
+    pub fn read(&mut self, mut sink: impl FnMut([f32; 2])) {
+        let count = self.available();
+        for deltas in &mut self.deltas {
+            if deltas.len() < count + TAPS {
+                deltas.resize(count + TAPS, 0.0);
+            }
+        }
+        for i in 0..count {
+            self.levels[0] += self.deltas[0][i];
+            self.levels[1] += self.deltas[1][i];
+            sink(self.levels);
+        }
+        for deltas in &mut self.deltas {
+            deltas.drain(..count);
+        }
+        self.time -= count as f64;
+    }
+}
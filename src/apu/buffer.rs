@@ -0,0 +1,67 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+/// Stereo samples shared between the emulator and the host's audio callback
+/// clones share the same buffer, once it's full the oldest samples are dropped
+#[derive(Clone, Debug)]
+pub struct AudioBuffer {
+    samples: Arc<Mutex<VecDeque<[f32; 2]>>>,
+    capacity: usize,
+}
+
+impl AudioBuffer {
+    /// `capacity` is in stereo frames
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+        }
+    }
+
+    pub fn push(&self, frame: [f32; 2]) {
+        let mut samples = self.samples.lock().unwrap();
+        if samples.len() == self.capacity {
+            samples.pop_front();
+        }
+        samples.push_back(frame);
+    }
+
+    /// Pushes several frames at once
+    pub fn extend(&self, frames: impl IntoIterator<Item = [f32; 2]>) {
+        let mut samples = self.samples.lock().unwrap();
+        for frame in frames {
+            if samples.len() == self.capacity {
+                samples.pop_front();
+            }
+            samples.push_back(frame);
+        }
+    }
+
+    /// Fills `out` with interleaved left/right samples, missing samples are silent
+    pub fn fill(&self, out: &mut [f32]) {
+        let mut samples = self.samples.lock().unwrap();
+        for frame in out.chunks_mut(2) {
+            let [left, right] = samples.pop_front().unwrap_or_default();
+            frame[0] = left;
+            if let Some(sample) = frame.get_mut(1) {
+                *sample = right;
+            }
+        }
+    }
+
+    /// Stereo frames waiting to be played
+    pub fn len(&self) -> usize {
+        self.samples.lock().unwrap().len()
+    }
+
+    /// Stereo frames the buffer holds before dropping the oldest
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
@@ -0,0 +1,85 @@
+/// Turns a channel off once it runs out, clocked at 256 Hz by the frame sequencer
+/// https://gbdev.io/pandocs/Audio.html#length-timer
+#[derive(Debug)]
+pub(super) struct LengthCounter {
+    counter: u16,
+    /// 64 for every channel but the wave channel, which has 256
+    max: u16,
+    pub enabled: bool,
+}
+
+impl LengthCounter {
+    pub fn new(max: u16) -> Self {
+        Self {
+            counter: 0,
+            max,
+            enabled: false,
+        }
+    }
+
+    /// NRx1, the counter starts at max - length
+    pub fn load(&mut self, length: u8) {
+        self.counter = self.max - length as u16;
+    }
+
+    /// A trigger with an expired counter reloads it at max
+    pub fn trigger(&mut self) {
+        if self.counter == 0 {
+            self.counter = self.max;
+        }
+    }
+
+    /// Returns true when the counter expires
+    pub fn clock(&mut self) -> bool {
+        if self.enabled && self.counter > 0 {
+            self.counter -= 1;
+            return self.counter == 0;
+        }
+        false
+    }
+}
+
+/// Volume envelope of the pulse and noise channels, clocked at 64 Hz
+/// https://gbdev.io/pandocs/Audio_Registers.html#ff12--nr12-channel-1-volume--envelope
+#[derive(Debug, Default)]
+pub(super) struct Envelope {
+    /// NRx2 as last written
+    register: u8,
+    pub volume: u8,
+    timer: u8,
+}
+
+impl Envelope {
+    pub fn write(&mut self, value: u8) {
+        self.register = value;
+    }
+
+    /// The DAC is on as long as the initial volume or the direction bit is set
+    pub fn dac_enabled(&self) -> bool {
+        self.register & 0xF8 != 0
+    }
+
+    fn period(&self) -> u8 {
+        self.register & 0x7
+    }
+
+    pub fn trigger(&mut self) {
+        self.volume = self.register >> 4;
+        self.timer = self.period();
+    }
+
+    pub fn clock(&mut self) {
+        if self.period() == 0 {
+            return;
+        }
+        self.timer = self.timer.saturating_sub(1);
+        if self.timer == 0 {
+            self.timer = self.period();
+            if self.register & 0x08 != 0 {
+                self.volume = (self.volume + 1).min(15);
+            } else {
+                self.volume = self.volume.saturating_sub(1);
+            }
+        }
+    }
+}
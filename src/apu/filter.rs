@@ -0,0 +1,25 @@
+/// High-pass filter modelling the capacitor on the console's audio output, removes the DC
+/// offset the DACs add
+/// https://gbdev.io/pandocs/Audio_details.html#obscure-behavior
+#[derive(Debug)]
+pub(super) struct HighPass {
+    /// How much charge the capacitor keeps per output sample
+    charge: f32,
+    capacitor: f32,
+}
+
+impl HighPass {
+    /// `clocks_per_sample` is the number of T-Cs between 2 output samples
+    pub fn new(clocks_per_sample: f64) -> Self {
+        Self {
+            charge: 0.999958f64.powf(clocks_per_sample) as f32,
+            capacitor: 0.0,
+        }
+    }
+
+    pub fn filter(&mut self, input: f32) -> f32 {
+        let output = input - self.capacitor;
+        self.capacitor = input - output * self.charge;
+        output
+    }
+}
@@ -0,0 +1,304 @@
+mod blip;
+mod buffer;
+mod channel;
+mod filter;
+mod noise;
+mod pulse;
+mod wave;
+
+pub use buffer::AudioBuffer;
+
+use crate::apu::{blip::BlipBuffer, filter::HighPass, noise::Noise, pulse::Pulse, wave::Wave};
+
+/// First sound register, NR10
+const NR10: u16 = 0xFF10;
+const NR50: u16 = 0xFF24;
+const NR51: u16 = 0xFF25;
+const NR52: u16 = 0xFF26;
+
+/// T-Cs per second, the APU runs in real time in double speed as well
+pub const CLOCK_RATE: u64 = 4_194_304;
+pub const DEFAULT_SAMPLE_RATE: u32 = 48_000;
+/// About 1/6th of a second of audio at the default rate
+const BUFFER_FRAMES: usize = 8192;
+/// Samples gathered before they're filtered and handed to the output buffer
+const FLUSH_FRAMES: usize = 32;
+/// Largest change to the resampling ratio, 0.5% of pitch is inaudible
+const MAX_RATE_DELTA: f64 = 0.005;
+/// Seconds of audio rate control keeps queued on the host side
+pub const TARGET_LATENCY: f64 = 0.05;
+
+/// Bits that read back as 1 for NR10-NR52, write-only bits and unused registers read as 1
+/// https://gbdev.io/pandocs/Audio_Registers.html
+const READ_MASKS: [u8; 0x17] = [
+    0x80, 0x3F, 0x00, 0xFF, 0xBF, // NR10-NR14
+    0xFF, 0x3F, 0x00, 0xFF, 0xBF, // NR20-NR24
+    0x7F, 0xFF, 0x9F, 0xFF, 0xBF, // NR30-NR34
+    0xFF, 0xFF, 0x00, 0x00, 0xBF, // NR40-NR44
+    0x00, 0x00, 0x70, // NR50-NR52
+];
+
+/// Values left by the boot ROM
+/// https://gbdev.io/pandocs/Power_Up_Sequence.html#hardware-registers
+const POST_BOOT: [u8; 0x17] = [
+    0x80, 0xBF, 0xF3, 0xFF, 0xBF, // NR10-NR14
+    0xFF, 0x3F, 0x00, 0xFF, 0xBF, // NR20-NR24
+    0x7F, 0xFF, 0x9F, 0xFF, 0xBF, // NR30-NR34
+    0xFF, 0xFF, 0x00, 0x00, 0xBF, // NR40-NR44
+    0x77, 0xF3, 0xF1, // NR50-NR52
+];
+
+/// Audio processing unit, 2 pulse channels, a wave channel and a noise channel
+/// mixed down to stereo samples at the host's rate
+/// https://gbdev.io/pandocs/Audio.html
+#[derive(Debug)]
+pub struct Apu {
+    /// NR10-NR52 as last written, for reading back
+    registers: [u8; 0x17],
+    wave_ram: [u8; 0x10],
+    pulse1: Pulse,
+    pulse2: Pulse,
+    wave: Wave,
+    noise: Noise,
+    /// Frame sequencer step, 0-7
+    frame_step: u8,
+    /// Last value of the DIV bit that clocks the frame sequencer
+    div_bit: bool,
+    sample_rate: u32,
+    /// Band-limited resampler from T-Cs to `sample_rate`
+    blip: BlipBuffer,
+    /// Last mixed amplitude, changes are added to `blip` as steps
+    level: [f32; 2],
+    high_pass: [HighPass; 2],
+    output: AudioBuffer,
+    /// Stereo frames in the host's audio queue as last reported by the frontend
+    /// rate control is off until the first report
+    host_queued: Option<usize>,
+}
+
+impl Default for Apu {
+    fn default() -> Self {
+        let mut pulse1 = Pulse::new(true);
+        for (reg, value) in POST_BOOT[..5].iter().enumerate() {
+            // NR14's trigger bit would restart the boot sound
+            let value = if reg == 4 { value & 0x7F } else { *value };
+            pulse1.write(reg as u16, value);
+        }
+        // The boot sound leaves channel 1 on, with its envelope run down to 0
+        pulse1.enabled = true;
+        Self {
+            registers: POST_BOOT,
+            wave_ram: [0; 0x10],
+            pulse1,
+            pulse2: Pulse::new(false),
+            wave: Wave::default(),
+            noise: Noise::default(),
+            frame_step: 0,
+            div_bit: false,
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            blip: BlipBuffer::new(DEFAULT_SAMPLE_RATE as f64 / CLOCK_RATE as f64),
+            level: [0.0; 2],
+            high_pass: Self::high_pass(DEFAULT_SAMPLE_RATE),
+            output: AudioBuffer::new(BUFFER_FRAMES),
+            host_queued: None,
+        }
+    }
+}
+
+impl Apu {
+    fn powered(&self) -> bool {
+        self.registers[(NR52 - NR10) as usize] & 0x80 != 0
+    }
+
+    fn register(&self, addr: u16) -> u8 {
+        self.registers[(addr - NR10) as usize]
+    }
+
+    /// A handle to the buffer samples are written to
+    pub fn output(&self) -> AudioBuffer {
+        self.output.clone()
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate;
+        self.blip = BlipBuffer::new(sample_rate as f64 / CLOCK_RATE as f64);
+        self.level = [0.0; 2];
+        self.high_pass = Self::high_pass(sample_rate);
+    }
+
+    /// How much audio the host has left to play, in stereo frames
+    pub fn set_host_queued(&mut self, frames: usize) {
+        self.host_queued = Some(frames);
+    }
+
+    fn high_pass(sample_rate: u32) -> [HighPass; 2] {
+        let clocks_per_sample = CLOCK_RATE as f64 / sample_rate as f64;
+        [
+            HighPass::new(clocks_per_sample),
+            HighPass::new(clocks_per_sample),
+        ]
+    }
+
+    /// Advances the channels by `t_cycles` and clocks the frame sequencer on a falling edge
+    /// of DIV bit 4 (bit 5 in double speed)
+    pub fn tick(&mut self, t_cycles: u16, divider: u16, double_speed: bool) {
+        let mask = if double_speed { 0x2000 } else { 0x1000 };
+        let div_bit = divider & mask != 0;
+        if self.div_bit && !div_bit && self.powered() {
+            self.clock_frame_sequencer();
+        }
+        self.div_bit = div_bit;
+
+        if self.powered() {
+            self.pulse1.tick(t_cycles);
+            self.pulse2.tick(t_cycles);
+            self.wave.tick(t_cycles, &self.wave_ram);
+            self.noise.tick(t_cycles);
+        }
+
+        let mix = self.mix();
+        for (channel, (level, new)) in self.level.iter_mut().zip(mix).enumerate() {
+            if new != *level {
+                self.blip.add_delta(channel, new - *level);
+                *level = new;
+            }
+        }
+        self.blip.advance(t_cycles);
+        if self.blip.available() >= FLUSH_FRAMES {
+            self.flush();
+        }
+    }
+
+    /// Moves the resampled frames through the high-pass filters to the output
+    /// and nudges the resampling ratio to keep the host's queue at `TARGET_LATENCY`,
+    /// the host and the emulator never run at exactly the same rate
+    fn flush(&mut self) {
+        let [left, right] = &mut self.high_pass;
+        let mut frames = Vec::with_capacity(self.blip.available());
+        self.blip
+            .read(|[l, r]| frames.push([left.filter(l), right.filter(r)]));
+        self.output.extend(frames);
+
+        let Some(host_queued) = self.host_queued else {
+            return;
+        };
+        let target = self.sample_rate as f64 * TARGET_LATENCY;
+        // Frames still in the output are on their way to the host queue
+        let fill = (host_queued + self.output.len()) as f64;
+        let adjust = 1.0 + MAX_RATE_DELTA * ((target - fill) / target).clamp(-1.0, 1.0);
+        self.blip
+            .set_ratio(self.sample_rate as f64 * adjust / CLOCK_RATE as f64);
+    }
+
+    /// 512 Hz, length at 256 Hz, sweep at 128 Hz and envelopes at 64 Hz
+    /// https://gbdev.io/pandocs/Audio_details.html#div-apu
+    fn clock_frame_sequencer(&mut self) {
+        if self.frame_step.is_multiple_of(2) {
+            self.pulse1.clock_length();
+            self.pulse2.clock_length();
+            self.wave.clock_length();
+            self.noise.clock_length();
+        }
+        if self.frame_step == 2 || self.frame_step == 6 {
+            self.pulse1.clock_sweep();
+        }
+        if self.frame_step == 7 {
+            self.pulse1.clock_envelope();
+            self.pulse2.clock_envelope();
+            self.noise.clock_envelope();
+        }
+        self.frame_step = (self.frame_step + 1) % 8;
+    }
+
+    /// Converts each channel through its DAC, pans with NR51 and scales with NR50
+    fn mix(&self) -> [f32; 2] {
+        if !self.powered() {
+            return [0.0; 2];
+        }
+        let channels = [
+            (self.pulse1.dac_enabled(), self.pulse1.output()),
+            (self.pulse2.dac_enabled(), self.pulse2.output()),
+            (self.wave.dac_enabled(), self.wave.output()),
+            (self.noise.dac_enabled(), self.noise.output()),
+        ];
+        let panning = self.register(NR51);
+        let volume = self.register(NR50);
+        let mut frame = [0.0; 2];
+        for (i, (dac_enabled, level)) in channels.into_iter().enumerate() {
+            if !dac_enabled {
+                continue;
+            }
+            // The DACs map 0-15 to 1.0 to -1.0
+            let analog = 1.0 - level as f32 / 7.5;
+            if panning & (0x10 << i) != 0 {
+                frame[0] += analog;
+            }
+            if panning & (0x01 << i) != 0 {
+                frame[1] += analog;
+            }
+        }
+        let left = ((volume >> 4) & 0x7) as f32 + 1.0;
+        let right = (volume & 0x7) as f32 + 1.0;
+        [frame[0] * left / 32.0, frame[1] * right / 32.0]
+    }
+
+    pub fn read(&self, addr: u16) -> u8 {
+        match addr {
+            NR52 => {
+                let channels = [
+                    self.pulse1.enabled,
+                    self.pulse2.enabled,
+                    self.wave.enabled,
+                    self.noise.enabled,
+                ];
+                let status = channels
+                    .iter()
+                    .enumerate()
+                    .fold(0, |status, (i, enabled)| status | ((*enabled as u8) << i));
+                (self.register(NR52) & 0x80) | READ_MASKS[(NR52 - NR10) as usize] | status
+            }
+            NR10..NR52 => {
+                let i = (addr - NR10) as usize;
+                self.registers[i] | READ_MASKS[i]
+            }
+            0xFF30..=0xFF3F => self.wave_ram[(addr - 0xFF30) as usize],
+            _ => 0xFF,
+        }
+    }
+
+    pub fn write(&mut self, addr: u16, value: u8) {
+        match addr {
+            // Only the power bit is writable, the channel bits are read-only
+            NR52 => {
+                if value & 0x80 == 0 {
+                    self.power_off();
+                } else if !self.powered() {
+                    self.frame_step = 0;
+                }
+                self.registers[(NR52 - NR10) as usize] = value & 0x80;
+            }
+            // Registers ignore writes while the APU is off
+            NR10..NR52 if self.powered() => {
+                self.registers[(addr - NR10) as usize] = value;
+                match addr {
+                    0xFF10..=0xFF14 => self.pulse1.write(addr - 0xFF10, value),
+                    0xFF15..=0xFF19 => self.pulse2.write(addr - 0xFF15, value),
+                    0xFF1A..=0xFF1E => self.wave.write(addr - 0xFF1A, value),
+                    0xFF1F..=0xFF23 => self.noise.write(addr - 0xFF1F, value),
+                    _ => (),
+                }
+            }
+            0xFF30..=0xFF3F => self.wave_ram[(addr - 0xFF30) as usize] = value,
+            _ => (),
+        }
+    }
+
+    /// Clears every register and silences the channels, wave RAM is kept
+    fn power_off(&mut self) {
+        self.registers = [0; 0x17];
+        self.pulse1 = Pulse::new(true);
+        self.pulse2 = Pulse::new(false);
+        self.wave = Wave::default();
+        self.noise = Noise::default();
+    }
+}
@@ -0,0 +1,96 @@
+use crate::apu::channel::{Envelope, LengthCounter};
+
+const DIVISORS: [u16; 8] = [8, 16, 32, 48, 64, 80, 96, 112];
+
+/// Channel 4, pseudo random noise from a 15 (or 7) bit LFSR
+/// https://gbdev.io/pandocs/Audio_Registers.html#sound-channel-4--noise
+#[derive(Debug)]
+pub(super) struct Noise {
+    pub length: LengthCounter,
+    envelope: Envelope,
+    pub enabled: bool,
+    /// NR43 as last written
+    polynomial: u8,
+    lfsr: u16,
+    timer: u32,
+}
+
+impl Default for Noise {
+    fn default() -> Self {
+        Self {
+            length: LengthCounter::new(64),
+            envelope: Envelope::default(),
+            enabled: false,
+            polynomial: 0,
+            lfsr: 0x7FFF,
+            timer: 0,
+        }
+    }
+}
+
+impl Noise {
+    fn period(&self) -> u32 {
+        (DIVISORS[(self.polynomial & 0x7) as usize] as u32) << (self.polynomial >> 4)
+    }
+
+    /// `reg` is 1-4 for NR41-NR44
+    pub fn write(&mut self, reg: u16, value: u8) {
+        match reg {
+            1 => self.length.load(value & 0x3F),
+            2 => {
+                self.envelope.write(value);
+                if !self.envelope.dac_enabled() {
+                    self.enabled = false;
+                }
+            }
+            3 => self.polynomial = value,
+            4 => {
+                self.length.enabled = value & 0x40 != 0;
+                if value & 0x80 != 0 {
+                    self.enabled = self.envelope.dac_enabled();
+                    self.length.trigger();
+                    self.envelope.trigger();
+                    self.lfsr = 0x7FFF;
+                    self.timer = self.period();
+                }
+            }
+            _ => (),
+        }
+    }
+
+    pub fn tick(&mut self, cycles: u16) {
+        let mut cycles = cycles as u32;
+        while cycles >= self.timer {
+            cycles -= self.timer;
+            self.timer = self.period();
+            let bit = (self.lfsr ^ (self.lfsr >> 1)) & 0x1;
+            self.lfsr = (self.lfsr >> 1) | (bit << 14);
+            // 7 bit mode also feeds bit 6
+            if self.polynomial & 0x08 != 0 {
+                self.lfsr = (self.lfsr & !0x40) | (bit << 6);
+            }
+        }
+        self.timer -= cycles;
+    }
+
+    pub fn clock_length(&mut self) {
+        if self.length.clock() {
+            self.enabled = false;
+        }
+    }
+
+    pub fn clock_envelope(&mut self) {
+        self.envelope.clock();
+    }
+
+    pub fn dac_enabled(&self) -> bool {
+        self.envelope.dac_enabled()
+    }
+
+    pub fn output(&self) -> u8 {
+        if !self.enabled || self.lfsr & 0x1 != 0 {
+            return 0;
+        }
+        self.envelope.volume
+    }
+}
@@ -0,0 +1,188 @@
+use crate::apu::channel::{Envelope, LengthCounter};
+
+/// Waveforms for the 4 duty cycles (12.5%, 25%, 50%, 75%)
+const DUTY_TABLE: [[u8; 8]; 4] = [
+    [0, 0, 0, 0, 0, 0, 0, 1],
+    [1, 0, 0, 0, 0, 0, 0, 1],
+    [1, 0, 0, 0, 0, 1, 1, 1],
+    [0, 1, 1, 1, 1, 1, 1, 0],
+];
+
+/// Frequency sweep of channel 1, clocked at 128 Hz
+/// https://gbdev.io/pandocs/Audio_Registers.html#ff10--nr10-channel-1-sweep
+#[derive(Debug, Default)]
+struct Sweep {
+    /// NR10 as last written
+    register: u8,
+    shadow: u16,
+    timer: u8,
+    enabled: bool,
+}
+
+impl Sweep {
+    fn period(&self) -> u8 {
+        (self.register >> 4) & 0x7
+    }
+
+    fn shift(&self) -> u8 {
+        self.register & 0x7
+    }
+
+    fn reload_timer(&mut self) {
+        // A period of 0 is treated as 8
+        self.timer = match self.period() {
+            0 => 8,
+            period => period,
+        };
+    }
+
+    /// Next frequency, None if it overflows 11 bits
+    fn next(&self) -> Option<u16> {
+        let delta = self.shadow >> self.shift();
+        let frequency = if self.register & 0x08 != 0 {
+            self.shadow - delta
+        } else {
+            self.shadow + delta
+        };
+        (frequency <= 0x7FF).then_some(frequency)
+    }
+}
+
+/// Square wave channels 1 (with sweep) and 2
+/// https://gbdev.io/pandocs/Audio_Registers.html#sound-channel-1--pulse-with-period-sweep
+#[derive(Debug)]
+pub(super) struct Pulse {
+    sweep: Option<Sweep>,
+    pub length: LengthCounter,
+    envelope: Envelope,
+    pub enabled: bool,
+    duty: u8,
+    /// 11 bit period value from NRx3/NRx4
+    frequency: u16,
+    /// T-Cs until the next duty step
+    timer: u16,
+    duty_step: u8,
+}
+
+impl Pulse {
+    pub fn new(sweep: bool) -> Self {
+        Self {
+            sweep: sweep.then(Sweep::default),
+            length: LengthCounter::new(64),
+            envelope: Envelope::default(),
+            enabled: false,
+            duty: 0,
+            frequency: 0,
+            timer: 0,
+            duty_step: 0,
+        }
+    }
+
+    fn period(&self) -> u16 {
+        (2048 - self.frequency) * 4
+    }
+
+    /// `reg` is 0-4 for NRx0-NRx4
+    pub fn write(&mut self, reg: u16, value: u8) {
+        match reg {
+            0 => {
+                if let Some(sweep) = &mut self.sweep {
+                    sweep.register = value;
+                }
+            }
+            1 => {
+                self.duty = value >> 6;
+                self.length.load(value & 0x3F);
+            }
+            2 => {
+                self.envelope.write(value);
+                if !self.envelope.dac_enabled() {
+                    self.enabled = false;
+                }
+            }
+            3 => self.frequency = (self.frequency & 0x700) | value as u16,
+            4 => {
+                self.frequency = (self.frequency & 0xFF) | (((value & 0x7) as u16) << 8);
+                self.length.enabled = value & 0x40 != 0;
+                if value & 0x80 != 0 {
+                    self.trigger();
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn trigger(&mut self) {
+        self.enabled = self.envelope.dac_enabled();
+        self.length.trigger();
+        self.timer = self.period();
+        self.envelope.trigger();
+        if let Some(sweep) = &mut self.sweep {
+            sweep.shadow = self.frequency;
+            sweep.reload_timer();
+            sweep.enabled = sweep.period() != 0 || sweep.shift() != 0;
+            // The overflow check runs right away
+            if sweep.shift() != 0 && sweep.next().is_none() {
+                self.enabled = false;
+            }
+        }
+    }
+
+    pub fn tick(&mut self, cycles: u16) {
+        let mut cycles = cycles;
+        while cycles >= self.timer {
+            cycles -= self.timer;
+            self.timer = self.period();
+            self.duty_step = (self.duty_step + 1) % 8;
+        }
+        self.timer -= cycles;
+    }
+
+    pub fn clock_length(&mut self) {
+        if self.length.clock() {
+            self.enabled = false;
+        }
+    }
+
+    pub fn clock_envelope(&mut self) {
+        self.envelope.clock();
+    }
+
+    pub fn clock_sweep(&mut self) {
+        let Some(sweep) = &mut self.sweep else {
+            return;
+        };
+        sweep.timer = sweep.timer.saturating_sub(1);
+        if sweep.timer > 0 {
+            return;
+        }
+        sweep.reload_timer();
+        if !sweep.enabled || sweep.period() == 0 {
+            return;
+        }
+        match sweep.next() {
+            Some(frequency) if sweep.shift() != 0 => {
+                sweep.shadow = frequency;
+                self.frequency = frequency;
+                // The new frequency is checked for overflow again, without being used
+                if sweep.next().is_none() {
+                    self.enabled = false;
+                }
+            }
+            Some(_) => (),
+            None => self.enabled = false,
+        }
+    }
+
+    pub fn dac_enabled(&self) -> bool {
+        self.envelope.dac_enabled()
+    }
+
+    /// Current output level, 0-15
+    pub fn output(&self) -> u8 {
+        if !self.enabled {
+            return 0;
+        }
+        DUTY_TABLE[self.duty as usize][self.duty_step as usize] * self.envelope.volume
+    }
+}
@@ -0,0 +1,98 @@
+use crate::apu::channel::LengthCounter;
+
+/// Channel 3, plays the 32 4-bit samples of wave RAM
+/// https://gbdev.io/pandocs/Audio_Registers.html#sound-channel-3--wave-output
+#[derive(Debug)]
+pub(super) struct Wave {
+    dac_enabled: bool,
+    pub length: LengthCounter,
+    pub enabled: bool,
+    /// NR32 output level, 0 mutes and 1-3 shift the sample right by 0-2
+    level: u8,
+    frequency: u16,
+    timer: u16,
+    position: u8,
+    /// Last sample read from wave RAM
+    sample: u8,
+}
+
+impl Default for Wave {
+    fn default() -> Self {
+        Self {
+            dac_enabled: false,
+            length: LengthCounter::new(256),
+            enabled: false,
+            level: 0,
+            frequency: 0,
+            timer: 0,
+            position: 0,
+            sample: 0,
+        }
+    }
+}
+
+impl Wave {
+    fn period(&self) -> u16 {
+        (2048 - self.frequency) * 2
+    }
+
+    /// `reg` is 0-4 for NR30-NR34
+    pub fn write(&mut self, reg: u16, value: u8) {
+        match reg {
+            0 => {
+                self.dac_enabled = value & 0x80 != 0;
+                if !self.dac_enabled {
+                    self.enabled = false;
+                }
+            }
+            1 => self.length.load(value),
+            2 => self.level = (value >> 5) & 0x3,
+            3 => self.frequency = (self.frequency & 0x700) | value as u16,
+            4 => {
+                self.frequency = (self.frequency & 0xFF) | (((value & 0x7) as u16) << 8);
+                self.length.enabled = value & 0x40 != 0;
+                if value & 0x80 != 0 {
+                    self.enabled = self.dac_enabled;
+                    self.length.trigger();
+                    self.timer = self.period();
+                    self.position = 0;
+                }
+            }
+            _ => (),
+        }
+    }
+
+    pub fn tick(&mut self, cycles: u16, wave_ram: &[u8; 0x10]) {
+        let mut cycles = cycles;
+        while cycles >= self.timer {
+            cycles -= self.timer;
+            self.timer = self.period();
+            self.position = (self.position + 1) % 32;
+            // High nibble first
+            let byte = wave_ram[(self.position / 2) as usize];
+            self.sample = if self.position.is_multiple_of(2) {
+                byte >> 4
+            } else {
+                byte & 0x0F
+            };
+        }
+        self.timer -= cycles;
+    }
+
+    pub fn clock_length(&mut self) {
+        if self.length.clock() {
+            self.enabled = false;
+        }
+    }
+
+    pub fn dac_enabled(&self) -> bool {
+        self.dac_enabled
+    }
+
+    pub fn output(&self) -> u8 {
+        if !self.enabled || self.level == 0 {
+            return 0;
+        }
+        self.sample >> (self.level - 1)
+    }
+}
@@ -1,14 +1,15 @@
 #[derive(Debug, Default)]
 pub struct Clock {
-    pub m_cycles: u32,
+    pub m_cycles: u64,
+    /// Counts real time, an M-C is 2 T-C in CGB double speed
     pub t_cycles: u64,
 }
 
 // HACK: Incomplete understanding of how clocks work
 
 impl Clock {
-    pub fn tick(&mut self) {
-        self.m_cycles += 1_u32;
-        self.t_cycles += 4_u64;
+    pub fn tick(&mut self, double_speed: bool) {
+        self.m_cycles += 1_u64;
+        self.t_cycles += if double_speed { 2_u64 } else { 4_u64 };
     }
 }
@@ -1,14 +1,39 @@
-use std::slice;
-
 use crate::{
-    cpu::{alu, clock::Clock, handlers::*, reg_file::RegFile},
+    cpu::{
+        clock::Clock,
+        decoder::{CB_OPCODES, Instruction, OPCODES, Op},
+        handlers::*,
+        interrupts,
+        reg_file::RegFile,
+    },
+    error::EmuError,
     mem::map::MemoryMap,
 };
 
+#[derive(Debug, PartialEq, Eq)]
+pub enum RunState {
+    Running,
+    /// Idles until an interrupt is pending
+    Halted,
+    /// Idles until a joypad input
+    Stopped,
+    /// Idles for the remaining M-Cs of a CGB speed switch
+    SwitchingSpeed(u16),
+}
+
 pub struct CpuContext {
     pub registers: RegFile,
     pub memory: MemoryMap,
     pub clock: Clock,
+    /// Set by ei, IME gets enabled after the next instruction
+    pub ime_scheduled: bool,
+    pub state: RunState,
+    /// Set when halt is executed with IME=0 and an interrupt pending
+    /// the next fetch fails to increment PC
+    /// https://gbdev.io/pandocs/halt.html#halt-bug
+    pub halt_bug: bool,
+    /// Prints every executed instruction, off by default since it's far too slow for real time
+    pub trace: bool,
 }
 
 impl CpuContext {
@@ -17,60 +42,142 @@ impl CpuContext {
             registers,
             memory,
             clock,
+            ime_scheduled: false,
+            state: RunState::Running,
+            halt_bug: false,
+            trace: false,
         }
     }
 
-    pub fn fetch(&mut self) -> u8 {
-        let result = match self.memory.read(&mut self.clock, self.registers.pc) {
-            Ok(op) => op,
-            // HACK: Probably improper error handling
-            Err(s) => {
-                println!("{}", s);
-                0x0
-            }
-        };
-        self.registers.pc += 1;
-        result
+    /// Idles for 1 M-C, the rest of the system keeps running
+    pub fn tick(&mut self) {
+        self.memory.tick(&mut self.clock);
+    }
+
+    pub fn fetch(&mut self) -> Result<u8, EmuError> {
+        let result = self.memory.read(&mut self.clock, self.registers.pc)?;
+        if self.halt_bug {
+            self.halt_bug = false;
+        } else {
+            self.registers.pc = self.registers.pc.wrapping_add(1);
+        }
+        Ok(result)
     }
 
-    pub fn start_exec_cycle(&mut self) -> Result<(), String> {
+    pub fn start_exec_cycle(&mut self) -> Result<(), EmuError> {
         loop {
-            let opcode = self.fetch();
-            print!("{:#X}: ", self.registers.pc);
-            print!("{:#X} -> ", opcode);
-            match opcode {
-                0x0 => print!("nop"),                                                 // NOP
-                0xC2 | 0xD2 | 0xCA | 0xDA | 0xC3 => jumps::jmp(self, opcode, false)?, // JP cc, imm16 | JP imm16
-                0x20 | 0x30 | 0x28 | 0x38 | 0x18 => jumps::jmp(self, opcode, true)?, // JR cc, imm8 | JR imm8
-                0xE9 => {
-                    println!("jp [hl]");
-                    self.registers.pc = alu::read_u16(&self.registers.l, &self.registers.h);
-                } // JP hl
-                0x8 => loads::ld_n16_sp(self)?, // LD [imm16] SP
-                0x06 | 0x16 | 0x26 | 0x36 | 0x0E | 0x1E | 0x2E | 0x3E | 0x40..0x80 => {
-                    loads::load8(self, opcode)?
-                } // LD r8, r8 | LD r8, [hl] | LD [hl], r8
-                0x01 | 0x11 | 0x21 | 0x31 => loads::load16(self, opcode)?, // LD r16, imm16
-                0x02 | 0x12 | 0x22 | 0x32 => loads::load_r16mem_a(opcode, self)?, // LD [r16mem] A
-                0x0A | 0x1A | 0x2A | 0x3A => loads::load_a_r16mem(opcode, self)?, // LD A, [r16mem]
-                0x80..0x90 | 0xC6 | 0xCE => arithmetic::add(opcode, self)?, // ADD/ADC A, r8 | ADD/ADC A, [hl] | ADD/ADC A, imm8
-                0x90..0xA0 | 0xD6 | 0xDE => arithmetic::sub(opcode, self)?, // SUB/SBC A, r8 | SUB/SBC A, [hl] | SUB/SBC A, imm8
-                0xA0..0xA8 | 0xE6 => arithmetic::and(opcode, self)?, // AND A, r8 | AND A, [hl] | AND A, imm8
-                0xA8..0xB0 | 0xEE => arithmetic::xor(opcode, self)?, // XOR A, r8 | XOR A, [hl] | XOR A, imm8
-                0xB0..0xB8 | 0xF6 => arithmetic::or(opcode, self)?, // OR A, r8 | OR A, [hl] | OR A, imm8
-                0xB8..0xC0 | 0xFE => arithmetic::cp(opcode, self)?, // CP A, r8 | CP A, [hl] | CP A, imm8
-                0x04 | 0x14 | 0x24 | 0x34 | 0x0C | 0x1C | 0x2C | 0x3C => {
-                    arithmetic::inc_r8(opcode, self, 1)?
-                } // INC r8, INC [hl]
-                0x05 | 0x15 | 0x25 | 0x35 | 0x0D | 0x1D | 0x2D | 0x3D => {
-                    arithmetic::inc_r8(opcode, self, -1)?
-                } // DEC r8, DEC [hl]
-                0xD3 | 0xDB | 0xDD | 0xE3 | 0xE4 | 0xEB..0xEE | 0xF4 | 0xFC | 0xFD => {
-                    return Err(format!("Illegal operation {opcode}"));
+            self.step()?;
+        }
+    }
+
+    /// Executes a single instruction, or idles for 1 M-C while halted/stopped or stalled by HDMA
+    pub fn step(&mut self) -> Result<(), EmuError> {
+        // HDMA runs between instructions so the stall isn't counted against one
+        let idle = matches!(self.state, RunState::Halted | RunState::Stopped);
+        if !idle && self.memory.step_hdma(&mut self.clock) {
+            return Ok(());
+        }
+        match self.state {
+            RunState::Running => (),
+            RunState::Halted => {
+                if self.memory.pending_interrupts() == 0 {
+                    self.tick();
+                    self.memory.skip_hblank_hdma();
+                    return Ok(());
+                }
+                self.state = RunState::Running;
+            }
+            RunState::Stopped => {
+                // The system clock is stopped, time passes but nothing else ticks
+                // until a button press pulls a selected P1 line low
+                if !self.memory.take_joypad_input() {
+                    self.clock.tick(self.memory.double_speed());
+                    return Ok(());
                 }
-                _ => print!("<unsupported>"),
+                self.state = RunState::Running;
+            }
+            RunState::SwitchingSpeed(remaining) => {
+                self.tick();
+                self.state = match remaining {
+                    0 | 1 => RunState::Running,
+                    _ => RunState::SwitchingSpeed(remaining - 1),
+                };
+                return Ok(());
+            }
+        }
+        let enable_ime = self.ime_scheduled;
+        interrupts::service(self)?;
+        if enable_ime {
+            self.registers.ime = true;
+            self.ime_scheduled = false;
+        }
+        let start_pc = self.registers.pc;
+        let instruction = self.decode_next()?;
+        if self.trace {
+            println!("{start_pc:#X}: {:#X} -> {instruction}", instruction.opcode);
+        }
+        self.execute(&instruction)
+    }
+
+    /// Fetches the next opcode and looks it up in the opcode tables
+    /// 0xCB prefixed opcodes are resolved to the instruction that follows the prefix
+    pub fn decode_next(&mut self) -> Result<Instruction, EmuError> {
+        let instruction = OPCODES[self.fetch()? as usize];
+        if instruction.op == Op::Prefix {
+            Ok(CB_OPCODES[self.fetch()? as usize])
+        } else {
+            Ok(instruction)
+        }
+    }
+
+    pub fn execute(&mut self, instruction: &Instruction) -> Result<(), EmuError> {
+        match instruction.op {
+            Op::Nop => (),
+            Op::Ld8 | Op::Ldh => loads::load8(instruction, self)?,
+            Op::Ld16 => loads::load16(instruction, self)?,
+            Op::LdN16Sp => loads::ld_n16_sp(self)?,
+            Op::LdHlSpE8 => loads::ld_hl_sp_e8(self)?,
+            Op::LdSpHl => loads::ld_sp_hl(self)?,
+            Op::Add | Op::Adc => arithmetic::add(instruction, self)?,
+            Op::Sub | Op::Sbc => arithmetic::sub(instruction, self)?,
+            Op::And => arithmetic::and(instruction, self)?,
+            Op::Xor => arithmetic::xor(instruction, self)?,
+            Op::Or => arithmetic::or(instruction, self)?,
+            Op::Cp => arithmetic::cp(instruction, self)?,
+            Op::Inc8 | Op::Dec8 => arithmetic::inc_r8(instruction, self)?,
+            Op::Inc16 | Op::Dec16 => arithmetic::inc_r16(instruction, self)?,
+            Op::AddHl => arithmetic::add_hl_r16(instruction, self)?,
+            Op::AddSp => arithmetic::add_sp_e8(self)?,
+            Op::RotateA(_) => arithmetic::rotate_a(instruction, self)?,
+            Op::Daa => arithmetic::daa(self)?,
+            Op::Cpl => arithmetic::cpl(self)?,
+            Op::Scf | Op::Ccf => arithmetic::set_carry(instruction, self)?,
+            Op::Jp | Op::Jr => jumps::jmp(instruction, self)?,
+            Op::JpHl => jumps::jp_hl(self)?,
+            Op::Call => stack::call(instruction, self)?,
+            Op::Ret | Op::Reti => stack::ret(instruction, self)?,
+            Op::Rst => stack::rst(instruction, self)?,
+            Op::Push => stack::push(instruction, self)?,
+            Op::Pop => stack::pop(instruction, self)?,
+            Op::Di => control::di(self)?,
+            Op::Ei => control::ei(self)?,
+            Op::Halt => control::halt(self)?,
+            Op::Stop => control::stop(self)?,
+            Op::Shift(_) => bitops::shift(instruction, self)?,
+            Op::Bit => bitops::bit(instruction, self)?,
+            Op::Res | Op::Set => bitops::res_set(instruction, self)?,
+            Op::Prefix => {
+                return Err(EmuError::InvalidOperand(
+                    "prefix has to be resolved by decode_next".to_owned(),
+                ));
+            }
+            Op::Illegal => {
+                return Err(EmuError::IllegalOpcode {
+                    pc: self.registers.pc.wrapping_sub(1),
+                    opcode: instruction.opcode,
+                });
             }
-            println!();
         }
+        Ok(())
     }
 }
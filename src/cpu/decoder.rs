@@ -0,0 +1,472 @@
+use std::fmt;
+
+use crate::{
+    cpu::{
+        handlers::bitops::ShiftOp,
+        operands::{R16, R16Type},
+    },
+    error::EmuError,
+};
+
+/// What an instruction does, each variant maps to exactly one handler
+/// https://gbdev.io/pandocs/CPU_Instruction_Set.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op {
+    Nop,
+    /// All 8-bit loads (r8, [hl], [r16mem], [n16] and imm8)
+    Ld8,
+    Ld16,
+    LdN16Sp,
+    LdHlSpE8,
+    LdSpHl,
+    /// 8-bit loads from/to the high page (0xFF00-0xFFFF)
+    Ldh,
+    Add,
+    Adc,
+    Sub,
+    Sbc,
+    And,
+    Xor,
+    Or,
+    Cp,
+    Inc8,
+    Dec8,
+    Inc16,
+    Dec16,
+    AddHl,
+    AddSp,
+    RotateA(ShiftOp),
+    Daa,
+    Cpl,
+    Scf,
+    Ccf,
+    Jp,
+    JpHl,
+    Jr,
+    Call,
+    Ret,
+    Reti,
+    Rst,
+    Push,
+    Pop,
+    Di,
+    Ei,
+    Halt,
+    Stop,
+    /// 0xCB, the actual instruction is decoded from the next byte
+    Prefix,
+    Shift(ShiftOp),
+    Bit,
+    Res,
+    Set,
+    Illegal,
+}
+
+impl Op {
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Op::Nop => "nop",
+            Op::Ld8 | Op::Ld16 | Op::LdN16Sp | Op::LdHlSpE8 | Op::LdSpHl => "ld",
+            Op::Ldh => "ldh",
+            Op::Add | Op::AddHl | Op::AddSp => "add",
+            Op::Adc => "adc",
+            Op::Sub => "sub",
+            Op::Sbc => "sbc",
+            Op::And => "and",
+            Op::Xor => "xor",
+            Op::Or => "or",
+            Op::Cp => "cp",
+            Op::Inc8 | Op::Inc16 => "inc",
+            Op::Dec8 | Op::Dec16 => "dec",
+            Op::RotateA(ShiftOp::Rlc) => "rlca",
+            Op::RotateA(ShiftOp::Rrc) => "rrca",
+            Op::RotateA(ShiftOp::Rl) => "rla",
+            Op::RotateA(_) => "rra",
+            Op::Daa => "daa",
+            Op::Cpl => "cpl",
+            Op::Scf => "scf",
+            Op::Ccf => "ccf",
+            Op::Jp | Op::JpHl => "jp",
+            Op::Jr => "jr",
+            Op::Call => "call",
+            Op::Ret => "ret",
+            Op::Reti => "reti",
+            Op::Rst => "rst",
+            Op::Push => "push",
+            Op::Pop => "pop",
+            Op::Di => "di",
+            Op::Ei => "ei",
+            Op::Halt => "halt",
+            Op::Stop => "stop",
+            Op::Prefix => "prefix",
+            Op::Shift(op) => op.mnemonic(),
+            Op::Bit => "bit",
+            Op::Res => "res",
+            Op::Set => "set",
+            Op::Illegal => "<illegal>",
+        }
+    }
+}
+
+/// Operand kinds as they appear in the opcode tables
+/// immediates are only fetched when the instruction is executed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operand {
+    /// b, c, d, e, h, l, [hl], a
+    R8(u8),
+    /// bc, de, hl, sp or af
+    R16(R16),
+    /// [bc], [de], [hl+], [hl-]
+    R16Mem(u8),
+    /// nz, z, nc, c
+    Cond(u8),
+    N8,
+    N16,
+    E8,
+    /// [n16]
+    MemN16,
+    /// [$FF00 + n8]
+    HighN8,
+    /// [$FF00 + c]
+    HighC,
+    /// sp + e8
+    SpE8,
+    U3(u8),
+    Vec(u8),
+}
+
+impl Operand {
+    pub fn r16(&self) -> Result<R16, EmuError> {
+        match self {
+            Operand::R16(r16) => Ok(*r16),
+            _ => Err(EmuError::InvalidOperand(format!(
+                "Expected r16 operand found {self}"
+            ))),
+        }
+    }
+
+    pub fn u3(&self) -> Result<u8, EmuError> {
+        match self {
+            Operand::U3(bit) => Ok(*bit),
+            _ => Err(EmuError::InvalidOperand(format!(
+                "Expected u3 operand found {self}"
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operand::R8(reg) => write!(
+                f,
+                "{}",
+                ["b", "c", "d", "e", "h", "l", "[hl]", "a"][*reg as usize & 0x7]
+            ),
+            Operand::R16(r16) => write!(f, "{r16}"),
+            Operand::R16Mem(param) => write!(
+                f,
+                "{}",
+                ["[bc]", "[de]", "[hl+]", "[hl-]"][*param as usize & 0x3]
+            ),
+            Operand::Cond(cond) => write!(f, "{}", ["nz", "z", "nc", "c"][*cond as usize & 0x3]),
+            Operand::N8 => write!(f, "n8"),
+            Operand::N16 => write!(f, "n16"),
+            Operand::E8 => write!(f, "e8"),
+            Operand::MemN16 => write!(f, "[n16]"),
+            Operand::HighN8 => write!(f, "[n8]"),
+            Operand::HighC => write!(f, "[c]"),
+            Operand::SpE8 => write!(f, "sp + e8"),
+            Operand::U3(bit) => write!(f, "{bit}"),
+            Operand::Vec(vector) => write!(f, "${vector:02X}"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Instruction {
+    pub opcode: u8,
+    pub prefixed: bool,
+    pub op: Op,
+    pub operands: [Option<Operand>; 2],
+    /// Length in bytes including the prefix
+    pub length: u8,
+    /// M-C taken (when a conditional branch isn't taken)
+    pub cycles: u8,
+    /// M-C taken when a conditional branch is taken
+    pub branch_cycles: Option<u8>,
+}
+
+impl Instruction {
+    const fn new(
+        opcode: u8,
+        op: Op,
+        operands: [Option<Operand>; 2],
+        length: u8,
+        cycles: u8,
+    ) -> Self {
+        Self {
+            opcode,
+            prefixed: false,
+            op,
+            operands,
+            length,
+            cycles,
+            branch_cycles: None,
+        }
+    }
+
+    const fn branch(mut self, cycles: u8) -> Self {
+        self.branch_cycles = Some(cycles);
+        self
+    }
+
+    const fn cb(mut self) -> Self {
+        self.prefixed = true;
+        self
+    }
+
+    pub fn operand(&self, index: usize) -> Result<Operand, EmuError> {
+        self.operands[index].ok_or(EmuError::InvalidOperand(format!(
+            "{self} has no operand {index}"
+        )))
+    }
+
+    /// The condition of conditional jumps, calls and returns
+    pub fn condition(&self) -> Option<u8> {
+        match self.operands[0] {
+            Some(Operand::Cond(cond)) => Some(cond),
+            _ => None,
+        }
+    }
+
+    /// Whether executing the instruction in `m_cycles` matches the opcode table
+    pub fn takes(&self, m_cycles: u64) -> bool {
+        m_cycles == self.cycles as u64 || self.branch_cycles.is_some_and(|c| m_cycles == c as u64)
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.op.mnemonic())?;
+        match self.operands {
+            [Some(dst), Some(src)] => write!(f, " {dst}, {src}"),
+            [Some(operand), None] => write!(f, " {operand}"),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Unprefixed opcode table
+pub static OPCODES: [Instruction; 256] = build_table(false);
+/// 0xCB prefixed opcode table
+pub static CB_OPCODES: [Instruction; 256] = build_table(true);
+
+const fn build_table(prefixed: bool) -> [Instruction; 256] {
+    let mut table = [Instruction::new(0, Op::Illegal, [None, None], 1, 1); 256];
+    let mut opcode = 0;
+    while opcode < 256 {
+        table[opcode] = if prefixed {
+            decode_cb(opcode as u8)
+        } else {
+            decode(opcode as u8)
+        };
+        opcode += 1;
+    }
+    table
+}
+
+const fn r16(param: u8, r16type: R16Type) -> Option<Operand> {
+    Some(Operand::R16(R16::new(param, r16type)))
+}
+
+const fn r8(reg: u8) -> Option<Operand> {
+    Some(Operand::R8(reg))
+}
+
+const A: Option<Operand> = r8(7);
+const HL: u8 = 6;
+
+/// Picks between r8 and [hl] cycle counts
+const fn hl_cycles(regs: [u8; 2], cycles: u8, hl_cycles: u8) -> u8 {
+    if regs[0] == HL || regs[1] == HL {
+        hl_cycles
+    } else {
+        cycles
+    }
+}
+
+const fn alu_op(index: u8) -> Op {
+    match index {
+        0x0 => Op::Add,
+        0x1 => Op::Adc,
+        0x2 => Op::Sub,
+        0x3 => Op::Sbc,
+        0x4 => Op::And,
+        0x5 => Op::Xor,
+        0x6 => Op::Or,
+        _ => Op::Cp,
+    }
+}
+
+// Opcode bits are named after https://gbdev.io/gb-opcodes/optables/
+// y = bits 3-5, z = bits 0-2, p = bits 4-5
+// The match has no wildcard so a missing opcode is a compile error
+const fn decode(opcode: u8) -> Instruction {
+    use Instruction as I;
+    let y = (opcode >> 3) & 0x7;
+    let z = opcode & 0x7;
+    let p = (opcode >> 4) & 0x3;
+    let cond = Some(Operand::Cond(y & 0x3));
+    match opcode {
+        0x00 => I::new(opcode, Op::Nop, [None, None], 1, 1),
+        0x01 | 0x11 | 0x21 | 0x31 => I::new(
+            opcode,
+            Op::Ld16,
+            [r16(p, R16Type::R16), Some(Operand::N16)],
+            3,
+            3,
+        ),
+        0x02 | 0x12 | 0x22 | 0x32 => I::new(opcode, Op::Ld8, [Some(Operand::R16Mem(p)), A], 1, 2),
+        0x0A | 0x1A | 0x2A | 0x3A => I::new(opcode, Op::Ld8, [A, Some(Operand::R16Mem(p))], 1, 2),
+        0x03 | 0x13 | 0x23 | 0x33 => I::new(opcode, Op::Inc16, [r16(p, R16Type::R16), None], 1, 2),
+        0x0B | 0x1B | 0x2B | 0x3B => I::new(opcode, Op::Dec16, [r16(p, R16Type::R16), None], 1, 2),
+        0x09 | 0x19 | 0x29 | 0x39 => I::new(
+            opcode,
+            Op::AddHl,
+            [Some(Operand::R16(R16::HL)), r16(p, R16Type::R16)],
+            1,
+            2,
+        ),
+        0x04 | 0x0C | 0x14 | 0x1C | 0x24 | 0x2C | 0x34 | 0x3C => {
+            I::new(opcode, Op::Inc8, [r8(y), None], 1, hl_cycles([y, y], 1, 3))
+        }
+        0x05 | 0x0D | 0x15 | 0x1D | 0x25 | 0x2D | 0x35 | 0x3D => {
+            I::new(opcode, Op::Dec8, [r8(y), None], 1, hl_cycles([y, y], 1, 3))
+        }
+        0x06 | 0x0E | 0x16 | 0x1E | 0x26 | 0x2E | 0x36 | 0x3E => I::new(
+            opcode,
+            Op::Ld8,
+            [r8(y), Some(Operand::N8)],
+            2,
+            hl_cycles([y, y], 2, 3),
+        ),
+        0x07 | 0x0F | 0x17 | 0x1F => {
+            I::new(opcode, Op::RotateA(ShiftOp::new(y)), [None, None], 1, 1)
+        }
+        0x08 => I::new(
+            opcode,
+            Op::LdN16Sp,
+            [Some(Operand::MemN16), Some(Operand::R16(R16::SP))],
+            3,
+            5,
+        ),
+        0x10 => I::new(opcode, Op::Stop, [None, None], 2, 1),
+        0x18 => I::new(opcode, Op::Jr, [Some(Operand::E8), None], 2, 3),
+        0x20 | 0x28 | 0x30 | 0x38 => {
+            I::new(opcode, Op::Jr, [cond, Some(Operand::E8)], 2, 2).branch(3)
+        }
+        0x27 => I::new(opcode, Op::Daa, [None, None], 1, 1),
+        0x2F => I::new(opcode, Op::Cpl, [None, None], 1, 1),
+        0x37 => I::new(opcode, Op::Scf, [None, None], 1, 1),
+        0x3F => I::new(opcode, Op::Ccf, [None, None], 1, 1),
+        0x76 => I::new(opcode, Op::Halt, [None, None], 1, 1),
+        0x40..=0x75 | 0x77..=0x7F => {
+            I::new(opcode, Op::Ld8, [r8(y), r8(z)], 1, hl_cycles([y, z], 1, 2))
+        }
+        0x80..=0xBF => I::new(opcode, alu_op(y), [A, r8(z)], 1, hl_cycles([z, z], 1, 2)),
+        0xC6 | 0xCE | 0xD6 | 0xDE | 0xE6 | 0xEE | 0xF6 | 0xFE => {
+            I::new(opcode, alu_op(y), [A, Some(Operand::N8)], 2, 2)
+        }
+        0xC0 | 0xC8 | 0xD0 | 0xD8 => I::new(opcode, Op::Ret, [cond, None], 1, 2).branch(5),
+        0xC9 => I::new(opcode, Op::Ret, [None, None], 1, 4),
+        0xD9 => I::new(opcode, Op::Reti, [None, None], 1, 4),
+        0xC1 | 0xD1 | 0xE1 | 0xF1 => I::new(opcode, Op::Pop, [r16(p, R16Type::R16Stk), None], 1, 3),
+        0xC5 | 0xD5 | 0xE5 | 0xF5 => {
+            I::new(opcode, Op::Push, [r16(p, R16Type::R16Stk), None], 1, 4)
+        }
+        0xC2 | 0xCA | 0xD2 | 0xDA => {
+            I::new(opcode, Op::Jp, [cond, Some(Operand::N16)], 3, 3).branch(4)
+        }
+        0xC3 => I::new(opcode, Op::Jp, [Some(Operand::N16), None], 3, 4),
+        0xE9 => I::new(opcode, Op::JpHl, [Some(Operand::R16(R16::HL)), None], 1, 1),
+        0xC4 | 0xCC | 0xD4 | 0xDC => {
+            I::new(opcode, Op::Call, [cond, Some(Operand::N16)], 3, 3).branch(6)
+        }
+        0xCD => I::new(opcode, Op::Call, [Some(Operand::N16), None], 3, 6),
+        0xC7 | 0xCF | 0xD7 | 0xDF | 0xE7 | 0xEF | 0xF7 | 0xFF => {
+            I::new(opcode, Op::Rst, [Some(Operand::Vec(y << 3)), None], 1, 4)
+        }
+        0xCB => I::new(opcode, Op::Prefix, [None, None], 1, 1),
+        0xE0 => I::new(opcode, Op::Ldh, [Some(Operand::HighN8), A], 2, 3),
+        0xF0 => I::new(opcode, Op::Ldh, [A, Some(Operand::HighN8)], 2, 3),
+        0xE2 => I::new(opcode, Op::Ldh, [Some(Operand::HighC), A], 1, 2),
+        0xF2 => I::new(opcode, Op::Ldh, [A, Some(Operand::HighC)], 1, 2),
+        0xEA => I::new(opcode, Op::Ld8, [Some(Operand::MemN16), A], 3, 4),
+        0xFA => I::new(opcode, Op::Ld8, [A, Some(Operand::MemN16)], 3, 4),
+        0xE8 => I::new(
+            opcode,
+            Op::AddSp,
+            [Some(Operand::R16(R16::SP)), Some(Operand::E8)],
+            2,
+            4,
+        ),
+        0xF8 => I::new(
+            opcode,
+            Op::LdHlSpE8,
+            [Some(Operand::R16(R16::HL)), Some(Operand::SpE8)],
+            2,
+            3,
+        ),
+        0xF9 => I::new(
+            opcode,
+            Op::LdSpHl,
+            [Some(Operand::R16(R16::SP)), Some(Operand::R16(R16::HL))],
+            1,
+            2,
+        ),
+        0xF3 => I::new(opcode, Op::Di, [None, None], 1, 1),
+        0xFB => I::new(opcode, Op::Ei, [None, None], 1, 1),
+        0xD3 | 0xDB | 0xDD | 0xE3 | 0xE4 | 0xEB | 0xEC | 0xED | 0xF4 | 0xFC | 0xFD => {
+            I::new(opcode, Op::Illegal, [None, None], 1, 1)
+        }
+    }
+}
+
+/// Cycle counts include fetching the 0xCB prefix
+const fn decode_cb(opcode: u8) -> Instruction {
+    use Instruction as I;
+    let y = (opcode >> 3) & 0x7;
+    let z = opcode & 0x7;
+    let instruction = match opcode >> 6 {
+        0x0 => I::new(
+            opcode,
+            Op::Shift(ShiftOp::new(y)),
+            [r8(z), None],
+            2,
+            hl_cycles([z, z], 2, 4),
+        ),
+        0x1 => I::new(
+            opcode,
+            Op::Bit,
+            [Some(Operand::U3(y)), r8(z)],
+            2,
+            hl_cycles([z, z], 2, 3),
+        ),
+        0x2 => I::new(
+            opcode,
+            Op::Res,
+            [Some(Operand::U3(y)), r8(z)],
+            2,
+            hl_cycles([z, z], 2, 4),
+        ),
+        _ => I::new(
+            opcode,
+            Op::Set,
+            [Some(Operand::U3(y)), r8(z)],
+            2,
+            hl_cycles([z, z], 2, 4),
+        ),
+    };
+    instruction.cb()
+}
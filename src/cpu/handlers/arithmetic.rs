@@ -1,88 +1,59 @@
-use crate::cpu::{alu::*, cpu_context::CpuContext, operands::R8, reg_file::Flag};
-
-pub fn add(opcode: u8, context: &mut CpuContext) -> Result<(), String> {
-    let mut src = read_bits(opcode, 0, 3);
-    let operand_str;
-    if opcode == 0xC6 || opcode == 0xCE {
-        src = context.fetch();
-        operand_str = "imm8";
-    } else if src == 6 {
-        src = context.memory.read(
-            &mut context.clock,
-            read_u16(&context.registers.l, &context.registers.h),
-        )?;
-        operand_str = "[hl]";
-    } else {
-        src = *context.registers.match_r8(src)?;
-        operand_str = "r8";
-    }
-    let addend = if read_bits(opcode, 3, 1) == 1 && context.registers.read_flag(Flag::Carry) {
-        print!("adc ");
-        src + 1
+use crate::{
+    cpu::{
+        cpu_context::CpuContext,
+        decoder::{Instruction, Op},
+        operands::{R8, R16},
+        reg_file::Flag,
+    },
+    error::EmuError,
+};
+
+/// Reads the source operand of an 8-bit alu instruction (r8, [hl] or imm8)
+fn alu_src(instruction: &Instruction, context: &mut CpuContext) -> Result<u8, EmuError> {
+    let r8_param = R8::new(instruction.operand(1)?, context)?;
+    r8_param.read(context)
+}
+
+/// add a, r8 | add a, [hl] | add a, imm8 | adc a, r8 | adc a, [hl] | adc a, imm8
+pub fn add(instruction: &Instruction, context: &mut CpuContext) -> Result<(), EmuError> {
+    let src = alu_src(instruction, context)?;
+    let carry_in = if instruction.op == Op::Adc {
+        context.registers.read_flag(Flag::Carry) as u8
     } else {
-        print!("add ");
-        src
+        0
     };
-    print!("{}", operand_str);
-    let half_carry = (context.registers.a & 0xF) + (addend & 0xF) > 0xF;
-    let (res, carry) = context.registers.a.overflowing_add(addend);
-    let zero = res == 0;
-    //FIX: Use set_all_flags()
+    // The carry in has to be accounted for separately so it can't overflow src
+    let half_carry = (context.registers.a & 0xF) + (src & 0xF) + carry_in > 0xF;
+    let wide_res = context.registers.a as u16 + src as u16 + carry_in as u16;
+    let (res, carry) = (wide_res as u8, wide_res > 0xFF);
     context
         .registers
-        .set_flag(Flag::HalfCarry, Some(half_carry))?;
-    context.registers.set_flag(Flag::Carry, Some(carry))?;
-    context.registers.set_flag(Flag::Zero, Some(zero))?;
-    context.registers.set_flag(Flag::Subtract, Some(false))?;
+        .set_all_flags(&[(res == 0) as u8, 0, half_carry as u8, carry as u8])?;
     context.registers.a = res;
     Ok(())
 }
 
-pub fn sub(opcode: u8, context: &mut CpuContext) -> Result<(), String> {
-    // FIX: This Code block right here is repeated 3 times so far
-    let mut src = read_bits(opcode, 0, 3);
-    let operand_str;
-    if opcode == 0xD6 || opcode == 0xDE {
-        src = context.fetch();
-        operand_str = "imm8";
-    } else if src == 6 {
-        src = context.memory.read(
-            &mut context.clock,
-            read_u16(&context.registers.l, &context.registers.h),
-        )?;
-        operand_str = "[hl]";
+/// sub a, r8 | sub a, [hl] | sub a, imm8 | sbc a, r8 | sbc a, [hl] | sbc a, imm8
+pub fn sub(instruction: &Instruction, context: &mut CpuContext) -> Result<(), EmuError> {
+    let src = alu_src(instruction, context)?;
+    let carry_in = if instruction.op == Op::Sbc {
+        context.registers.read_flag(Flag::Carry) as u8
     } else {
-        src = *context.registers.match_r8(src)?;
-        operand_str = "r8";
-    }
-    let subtrahend = if read_bits(opcode, 3, 1) == 1 && context.registers.read_flag(Flag::Carry) {
-        print!("sbc ");
-        src + 1
-    } else {
-        print!("sub ");
-        src
+        0
     };
-    print!("{}", operand_str);
-    let half_carry = (context.registers.a & 0xF) < (subtrahend & 0xF);
-    let (res, carry) = context.registers.a.overflowing_sub(subtrahend);
-    let zero = res == 0;
-    //FIX: Use set_all_flags()
+    let half_carry = (context.registers.a & 0xF) < (src & 0xF) + carry_in;
+    let carry = (context.registers.a as u16) < src as u16 + carry_in as u16;
+    let res = context.registers.a.wrapping_sub(src).wrapping_sub(carry_in);
     context
         .registers
-        .set_flag(Flag::HalfCarry, Some(half_carry))?;
-    context.registers.set_flag(Flag::Carry, Some(carry))?;
-    context.registers.set_flag(Flag::Zero, Some(zero))?;
-    context.registers.set_flag(Flag::Subtract, Some(true))?;
+        .set_all_flags(&[(res == 0) as u8, 1, half_carry as u8, carry as u8])?;
     context.registers.a = res;
     Ok(())
 }
 
 //NOTE: Untested
-pub fn and(opcode: u8, context: &mut CpuContext) -> Result<(), String> {
-    print!("and ");
-    let r8_param = R8::get_r8_param(opcode == 0xE6, opcode, 0, context)?;
-    let src = r8_param.read(context)?;
-    r8_param.log();
+pub fn and(instruction: &Instruction, context: &mut CpuContext) -> Result<(), EmuError> {
+    let src = alu_src(instruction, context)?;
     context.registers.a &= src;
     context
         .registers
@@ -91,11 +62,8 @@ pub fn and(opcode: u8, context: &mut CpuContext) -> Result<(), String> {
 }
 
 //NOTE: Untested
-pub fn xor(opcode: u8, context: &mut CpuContext) -> Result<(), String> {
-    print!("xor ");
-    let r8_param = R8::get_r8_param(opcode == 0xEE, opcode, 0, context)?;
-    r8_param.log();
-    let src = r8_param.read(context)?;
+pub fn xor(instruction: &Instruction, context: &mut CpuContext) -> Result<(), EmuError> {
+    let src = alu_src(instruction, context)?;
     context.registers.a ^= src;
     context
         .registers
@@ -104,11 +72,8 @@ pub fn xor(opcode: u8, context: &mut CpuContext) -> Result<(), String> {
 }
 
 //NOTE: Untested
-pub fn or(opcode: u8, context: &mut CpuContext) -> Result<(), String> {
-    print!("or ");
-    let r8_param = R8::get_r8_param(opcode == 0xF6, opcode, 0, context)?;
-    let src = r8_param.read(context)?;
-    r8_param.log();
+pub fn or(instruction: &Instruction, context: &mut CpuContext) -> Result<(), EmuError> {
+    let src = alu_src(instruction, context)?;
     context.registers.a |= src;
     context
         .registers
@@ -117,12 +82,8 @@ pub fn or(opcode: u8, context: &mut CpuContext) -> Result<(), String> {
 }
 
 //NOTE: Untested
-pub fn cp(opcode: u8, context: &mut CpuContext) -> Result<(), String> {
-    //NOTE: This code is also valid for sub, probably need to do that as well there
-    print!("cp ");
-    let r8_param = R8::get_r8_param(opcode == 0xFE, opcode, 0, context)?;
-    let subtrahend = r8_param.read(context)?;
-    r8_param.log();
+pub fn cp(instruction: &Instruction, context: &mut CpuContext) -> Result<(), EmuError> {
+    let subtrahend = alu_src(instruction, context)?;
     let half_carry = (context.registers.a & 0xF) < (subtrahend & 0xF);
     let (res, carry) = context.registers.a.overflowing_sub(subtrahend);
     let zero = res == 0;
@@ -133,22 +94,19 @@ pub fn cp(opcode: u8, context: &mut CpuContext) -> Result<(), String> {
 }
 
 /// inc r8 | inc hl | dec r8 | dec hl
-pub fn inc_r8(opcode: u8, context: &mut CpuContext, delta: i8) -> Result<(), String> {
-    let r8_param = R8::get_r8_param(false, opcode, 3, context)?;
+pub fn inc_r8(instruction: &Instruction, context: &mut CpuContext) -> Result<(), EmuError> {
+    let r8_param = R8::new(instruction.operand(0)?, context)?;
     let value = r8_param.read(context)?;
     let (half_carry, zero, sub, res): (bool, bool, bool, u8);
-    if delta < 0 {
-        print!("dec ");
-        res = value.wrapping_sub(delta.unsigned_abs());
-        half_carry = (value & 0xF) < (delta.unsigned_abs() & 0xF);
+    if instruction.op == Op::Dec8 {
+        res = value.wrapping_sub(1);
+        half_carry = (value & 0xF) == 0;
         sub = true
     } else {
-        print!("inc ");
-        res = value.wrapping_add(delta as u8);
-        half_carry = (value & 0xF) + (delta as u8 & 0xF) > 0xF;
+        res = value.wrapping_add(1);
+        half_carry = (value & 0xF) == 0xF;
         sub = false
     }
-    r8_param.log();
     zero = res == 0;
     r8_param.write(context, res)?;
     context.registers.set_all_flags(&[
@@ -159,3 +117,133 @@ pub fn inc_r8(opcode: u8, context: &mut CpuContext, delta: i8) -> Result<(), Str
     ])?;
     Ok(())
 }
+
+/// inc r16 | dec r16
+pub fn inc_r16(instruction: &Instruction, context: &mut CpuContext) -> Result<(), EmuError> {
+    let param = instruction.operand(0)?.r16()?;
+    let value = param.read(&context.registers);
+    let res = if instruction.op == Op::Dec16 {
+        value.wrapping_sub(1)
+    } else {
+        value.wrapping_add(1)
+    };
+    param.write(res, &mut context.registers);
+    // 16-bit increment/decrement takes an extra cycle
+    context.tick();
+    Ok(())
+}
+
+/// add hl, r16
+pub fn add_hl_r16(instruction: &Instruction, context: &mut CpuContext) -> Result<(), EmuError> {
+    let addend = instruction.operand(1)?.r16()?.read(&context.registers);
+    let hl = R16::HL.read(&context.registers);
+    // Half carry and carry come from bits 11 and 15 respectively
+    let half_carry = (hl & 0xFFF) + (addend & 0xFFF) > 0xFFF;
+    let (res, carry) = hl.overflowing_add(addend);
+    R16::HL.write(res, &mut context.registers);
+    context.registers.set_all_flags(&[
+        context.registers.read_flag(Flag::Zero) as u8,
+        0,
+        half_carry as u8,
+        carry as u8,
+    ])?;
+    context.tick();
+    Ok(())
+}
+
+/// Computes SP + e8 (fetched) for add sp, e8 and ld hl, sp + e8
+/// Half carry and carry are computed from the unsigned addition of the low byte
+pub fn sp_plus_e8(context: &mut CpuContext) -> Result<u16, EmuError> {
+    let offset = context.fetch()?;
+    let sp = context.registers.sp;
+    let half_carry = (sp & 0xF) + (offset as u16 & 0xF) > 0xF;
+    let carry = (sp & 0xFF) + offset as u16 > 0xFF;
+    context
+        .registers
+        .set_all_flags(&[0, 0, half_carry as u8, carry as u8])?;
+    Ok(sp.wrapping_add_signed(offset as i8 as i16))
+}
+
+/// add sp, e8
+pub fn add_sp_e8(context: &mut CpuContext) -> Result<(), EmuError> {
+    context.registers.sp = sp_plus_e8(context)?;
+    // Two internal cycles for the 16-bit addition
+    context.tick();
+    context.tick();
+    Ok(())
+}
+
+/// rlca | rrca | rla | rra
+/// Same as their CB prefixed counterparts except Z is always reset
+pub fn rotate_a(instruction: &Instruction, context: &mut CpuContext) -> Result<(), EmuError> {
+    let Op::RotateA(op) = instruction.op else {
+        return Err(EmuError::InvalidOperand(format!(
+            "{instruction} is not an accumulator rotate"
+        )));
+    };
+    let (res, carry) = op.apply(
+        context.registers.a,
+        context.registers.read_flag(Flag::Carry),
+    );
+    context.registers.a = res;
+    context.registers.set_all_flags(&[0, 0, 0, carry as u8])?;
+    Ok(())
+}
+
+/// daa
+/// Adjusts A to a valid BCD number after a BCD addition or subtraction
+/// https://rgbds.gbdev.io/docs/v0.9.1/gbz80.7#DAA
+pub fn daa(context: &mut CpuContext) -> Result<(), EmuError> {
+    let subtract = context.registers.read_flag(Flag::Subtract);
+    let half_carry = context.registers.read_flag(Flag::HalfCarry);
+    let mut carry = context.registers.read_flag(Flag::Carry);
+    let mut adjustment: u8 = 0;
+    let a = context.registers.a;
+    if subtract {
+        if half_carry {
+            adjustment |= 0x06;
+        }
+        if carry {
+            adjustment |= 0x60;
+        }
+        context.registers.a = a.wrapping_sub(adjustment);
+    } else {
+        if half_carry || (a & 0xF) > 0x9 {
+            adjustment |= 0x06;
+        }
+        if carry || a > 0x99 {
+            adjustment |= 0x60;
+            carry = true;
+        }
+        context.registers.a = a.wrapping_add(adjustment);
+    }
+    context.registers.set_all_flags(&[
+        (context.registers.a == 0) as u8,
+        subtract as u8,
+        0,
+        carry as u8,
+    ])?;
+    Ok(())
+}
+
+/// cpl
+pub fn cpl(context: &mut CpuContext) -> Result<(), EmuError> {
+    context.registers.a = !context.registers.a;
+    context.registers.set_flag(Flag::Subtract, Some(true))?;
+    context.registers.set_flag(Flag::HalfCarry, Some(true))?;
+    Ok(())
+}
+
+/// scf | ccf
+pub fn set_carry(instruction: &Instruction, context: &mut CpuContext) -> Result<(), EmuError> {
+    let carry = if instruction.op == Op::Scf {
+        Some(true)
+    } else {
+        // Flips the carry flag
+        None
+    };
+    context.registers.set_flag(Flag::Subtract, Some(false))?;
+    context.registers.set_flag(Flag::HalfCarry, Some(false))?;
+    context.registers.set_flag(Flag::Carry, carry)?;
+    Ok(())
+}
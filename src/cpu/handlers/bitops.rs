@@ -0,0 +1,111 @@
+use crate::{
+    cpu::{
+        alu,
+        cpu_context::CpuContext,
+        decoder::{Instruction, Op},
+        operands::R8,
+        reg_file::Flag,
+    },
+    error::EmuError,
+};
+
+/// The eight shift/rotate operations encoded in bits 3-5 of a 0xCB prefixed opcode
+/// https://gbdev.io/pandocs/CPU_Instruction_Set.html#bit-shift-instructions
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShiftOp {
+    Rlc,
+    Rrc,
+    Rl,
+    Rr,
+    Sla,
+    Sra,
+    Swap,
+    Srl,
+}
+
+impl ShiftOp {
+    /// Decodes the 3 bit operation param of an opcode
+    pub const fn new(param: u8) -> Self {
+        match param & 0x7 {
+            0x0 => Self::Rlc,
+            0x1 => Self::Rrc,
+            0x2 => Self::Rl,
+            0x3 => Self::Rr,
+            0x4 => Self::Sla,
+            0x5 => Self::Sra,
+            0x6 => Self::Swap,
+            _ => Self::Srl,
+        }
+    }
+
+    /// Returns (result, carry out) for `value` given the current carry flag
+    pub fn apply(&self, value: u8, carry: bool) -> (u8, bool) {
+        let bit7 = value & 0x80 != 0;
+        let bit0 = value & 0x01 != 0;
+        match self {
+            Self::Rlc => (value.rotate_left(1), bit7),
+            Self::Rrc => (value.rotate_right(1), bit0),
+            Self::Rl => ((value << 1) | carry as u8, bit7),
+            Self::Rr => ((value >> 1) | ((carry as u8) << 7), bit0),
+            Self::Sla => (value << 1, bit7),
+            Self::Sra => ((value >> 1) | (value & 0x80), bit0),
+            Self::Swap => (value.rotate_left(4), false),
+            Self::Srl => (value >> 1, bit0),
+        }
+    }
+
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Self::Rlc => "rlc",
+            Self::Rrc => "rrc",
+            Self::Rl => "rl",
+            Self::Rr => "rr",
+            Self::Sla => "sla",
+            Self::Sra => "sra",
+            Self::Swap => "swap",
+            Self::Srl => "srl",
+        }
+    }
+}
+
+/// rlc | rrc | rl | rr | sla | sra | swap | srl
+pub fn shift(instruction: &Instruction, context: &mut CpuContext) -> Result<(), EmuError> {
+    let Op::Shift(op) = instruction.op else {
+        return Err(EmuError::InvalidOperand(format!(
+            "{instruction} is not a shift"
+        )));
+    };
+    let r8_param = R8::new(instruction.operand(0)?, context)?;
+    let value = r8_param.read(context)?;
+    let (res, carry) = op.apply(value, context.registers.read_flag(Flag::Carry));
+    r8_param.write(context, res)?;
+    context
+        .registers
+        .set_all_flags(&[(res == 0) as u8, 0, 0, carry as u8])?;
+    Ok(())
+}
+
+/// bit u3, r8 | bit u3, [hl]
+pub fn bit(instruction: &Instruction, context: &mut CpuContext) -> Result<(), EmuError> {
+    let index = instruction.operand(0)?.u3()?;
+    let r8_param = R8::new(instruction.operand(1)?, context)?;
+    let value = r8_param.read(context)?;
+    let zero = alu::read_bits(value, index, 1) == 0;
+    context.registers.set_all_flags(&[
+        zero as u8,
+        0,
+        1,
+        context.registers.read_flag(Flag::Carry) as u8,
+    ])?;
+    Ok(())
+}
+
+/// res u3, r8 | res u3, [hl] | set u3, r8 | set u3, [hl]
+pub fn res_set(instruction: &Instruction, context: &mut CpuContext) -> Result<(), EmuError> {
+    let index = instruction.operand(0)?.u3()?;
+    let r8_param = R8::new(instruction.operand(1)?, context)?;
+    let mut value = r8_param.read(context)?;
+    alu::write_bits(&mut value, index, 1, (instruction.op == Op::Set) as u8)?;
+    r8_param.write(context, value)?;
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use crate::{
+    cpu::cpu_context::{CpuContext, RunState},
+    error::EmuError,
+};
+
+/// M-Cs the CPU is paused for after a speed switch
+const SPEED_SWITCH_CYCLES: u16 = 2050;
+
+/// di
+pub fn di(context: &mut CpuContext) -> Result<(), EmuError> {
+    context.registers.ime = false;
+    context.ime_scheduled = false;
+    Ok(())
+}
+
+/// ei
+/// IME is only set after the instruction following ei
+pub fn ei(context: &mut CpuContext) -> Result<(), EmuError> {
+    context.ime_scheduled = true;
+    Ok(())
+}
+
+/// halt
+/// https://gbdev.io/pandocs/halt.html
+pub fn halt(context: &mut CpuContext) -> Result<(), EmuError> {
+    if !context.registers.ime && context.memory.pending_interrupts() != 0 {
+        // HALT bug: the CPU doesn't halt and the next byte is read twice
+        context.halt_bug = true;
+    } else {
+        context.state = RunState::Halted;
+    }
+    Ok(())
+}
+
+/// stop
+/// Performs a CGB speed switch when armed through KEY1, otherwise stops the CPU
+/// https://gbdev.io/pandocs/Reducing_Power_Consumption.html#using-the-stop-instruction
+/// https://gbdev.io/pandocs/CGB_Registers.html#ff4d--key1-cgb-mode-only-prepare-speed-switch
+pub fn stop(context: &mut CpuContext) -> Result<(), EmuError> {
+    // stop is 2 bytes long, the second byte is ignored
+    context.registers.pc = context.registers.pc.wrapping_add(1);
+    context.memory.reset_divider();
+    if context.memory.speed_switch_armed() {
+        context.memory.switch_speed();
+        context.state = RunState::SwitchingSpeed(SPEED_SWITCH_CYCLES);
+    } else {
+        // Only presses from now on wake it up
+        context.memory.take_joypad_input();
+        context.state = RunState::Stopped;
+    }
+    Ok(())
+}
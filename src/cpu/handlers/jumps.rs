@@ -1,28 +1,35 @@
-use crate::cpu::{alu, cpu_context::CpuContext};
+use crate::{
+    cpu::{
+        alu,
+        cpu_context::CpuContext,
+        decoder::{Instruction, Op},
+        operands::R16,
+    },
+    error::EmuError,
+};
 
-pub fn jmp(context: &mut CpuContext, opcode: u8, is_relative: bool) -> Result<(), String> {
-    let target_address: u16;
-    let is_conditional: bool;
-    if is_relative {
-        print!("jr ");
-        is_conditional = opcode != 0x18;
-        target_address = (context.registers.pc as i16 + context.fetch() as i16) as u16;
+/// jp n16 | jp cc, n16 | jr e8 | jr cc, e8
+pub fn jmp(instruction: &Instruction, context: &mut CpuContext) -> Result<(), EmuError> {
+    let target_address = if instruction.op == Op::Jr {
+        // The offset is relative to the address following the instruction
+        let offset = context.fetch()? as i8;
+        context.registers.pc.wrapping_add_signed(offset as i16)
     } else {
-        print!("jp ");
-        is_conditional = opcode != 0xC3;
-        target_address = alu::read_u16(&context.fetch(), &context.fetch());
-    }
-    if is_conditional {
-        print!("cc ");
-    }
-    print!("n16");
-    if context
-        .registers
-        .match_condition(alu::read_bits(opcode, 3, 2))?
-        || !is_conditional
-    {
+        alu::read_u16(&context.fetch()?, &context.fetch()?)
+    };
+    let taken = match instruction.condition() {
+        Some(cond) => context.registers.match_condition(cond)?,
+        None => true,
+    };
+    if taken {
         context.registers.pc = target_address;
-        context.clock.tick();
+        context.tick();
     }
     Ok(())
 }
+
+/// jp hl
+pub fn jp_hl(context: &mut CpuContext) -> Result<(), EmuError> {
+    context.registers.pc = R16::HL.read(&context.registers);
+    Ok(())
+}
@@ -1,68 +1,58 @@
-use crate::cpu::{
-    alu,
-    cpu_context::CpuContext,
-    operands::{R8, R16, R16Type},
+use crate::{
+    cpu::{
+        alu,
+        cpu_context::CpuContext,
+        decoder::Instruction,
+        handlers::arithmetic,
+        operands::{R8, R16},
+    },
+    error::EmuError,
 };
 
-pub fn load8(context: &mut CpuContext, opcode: u8) -> Result<(), String> {
-    print!("ld ");
-    let src_param = R8::get_r8_param(alu::read_bits(opcode, 6, 1) == 0, opcode, 0, context)?;
+/// All 8-bit loads
+/// ld r8, r8 | ld r8, [hl] | ld [hl], r8 | ld r8, imm8 | ld [r16mem], a | ld a, [r16mem]
+/// ld [n16], a | ld a, [n16] | ldh [n8], a | ldh a, [n8] | ldh [c], a | ldh a, [c]
+pub fn load8(instruction: &Instruction, context: &mut CpuContext) -> Result<(), EmuError> {
+    let src_param = R8::new(instruction.operand(1)?, context)?;
     let src = src_param.read(context)?;
-    let dst_param = R8::get_r8_param(false, opcode, 3, context)?;
-    dst_param.log();
-    src_param.log();
+    let dst_param = R8::new(instruction.operand(0)?, context)?;
     dst_param.write(context, src)?;
     Ok(())
 }
 
-pub fn load16(context: &mut CpuContext, opcode: u8) -> Result<(), String> {
-    let param = R16::new(opcode, 4, R16Type::R16)?;
+/// ld r16, imm16
+pub fn load16(instruction: &Instruction, context: &mut CpuContext) -> Result<(), EmuError> {
+    let param = instruction.operand(0)?.r16()?;
     param.write(
-        alu::read_u16(&context.fetch(), &context.fetch()),
+        alu::read_u16(&context.fetch()?, &context.fetch()?),
         &mut context.registers,
     );
-    print!("ld r16 imm16");
     Ok(())
 }
 
-pub fn load_r16mem_a(opcode: u8, context: &mut CpuContext) -> Result<(), String> {
-    let param = R16::new(opcode, 4, R16Type::R16Mem)?;
-    let addr = param.read(&context.registers);
+// NOTE: Untested
+pub fn ld_n16_sp(context: &mut CpuContext) -> Result<(), EmuError> {
+    let addr = alu::read_u16(&context.fetch()?, &context.fetch()?);
+    let lsb = (context.registers.sp & 0xFF) as u8;
+    let msb = (context.registers.sp >> 8) as u8;
+    context.memory.write(&mut context.clock, addr, lsb)?;
     context
         .memory
-        .write(&mut context.clock, addr, context.registers.a)?;
-    let raw_param = alu::read_bits(opcode, 4, 2);
-    if raw_param == 0x2 {
-        param.write(addr + 1, &mut context.registers);
-    } else if raw_param == 0x3 {
-        param.write(addr - 1, &mut context.registers);
-    }
-    print!("ld [r16mem] a");
+        .write(&mut context.clock, addr.wrapping_add(1), msb)?;
     Ok(())
 }
 
-pub fn load_a_r16mem(opcode: u8, context: &mut CpuContext) -> Result<(), String> {
-    let param = R16::new(opcode, 4, R16Type::R16Mem)?;
-    let addr = param.read(&context.registers);
-    let value = context.memory.read(&mut context.clock, addr)?;
-    context.registers.a = value;
-    let raw_param = alu::read_bits(opcode, 4, 2);
-    if raw_param == 0x2 {
-        param.write(addr + 1, &mut context.registers);
-    } else if raw_param == 0x3 {
-        param.write(addr - 1, &mut context.registers);
-    }
-    print!("ld a [r16mem]");
+/// ld hl, sp + e8
+pub fn ld_hl_sp_e8(context: &mut CpuContext) -> Result<(), EmuError> {
+    let value = arithmetic::sp_plus_e8(context)?;
+    R16::HL.write(value, &mut context.registers);
+    context.tick();
     Ok(())
 }
 
-// NOTE: Untested
-pub fn ld_n16_sp(context: &mut CpuContext) -> Result<(), String> {
-    print!("ld [n16] sp");
-    let addr = alu::read_u16(&context.fetch(), &context.fetch());
-    let lsb = (context.registers.sp & 0xFF) as u8;
-    let msb = (context.registers.sp >> 8) as u8;
-    context.memory.write(&mut context.clock, addr, lsb)?;
-    context.memory.write(&mut context.clock, addr + 1, msb)?;
+/// ld sp, hl
+pub fn ld_sp_hl(context: &mut CpuContext) -> Result<(), EmuError> {
+    context.registers.sp = R16::HL.read(&context.registers);
+    context.tick();
     Ok(())
 }
@@ -1,3 +1,6 @@
 pub mod arithmetic;
+pub mod bitops;
+pub mod control;
 pub mod jumps;
 pub mod loads;
+pub mod stack;
@@ -0,0 +1,102 @@
+use crate::{
+    cpu::{
+        alu,
+        cpu_context::CpuContext,
+        decoder::{Instruction, Op, Operand},
+        operands::R16,
+    },
+    error::EmuError,
+};
+
+/// Pushes a 16-bit value onto the stack (msb first), +2 M-C
+pub fn push_u16(context: &mut CpuContext, value: u16) -> Result<(), EmuError> {
+    let msb = (value >> 8) as u8;
+    let lsb = value as u8;
+    context.registers.sp = context.registers.sp.wrapping_sub(1);
+    context
+        .memory
+        .write(&mut context.clock, context.registers.sp, msb)?;
+    context.registers.sp = context.registers.sp.wrapping_sub(1);
+    context
+        .memory
+        .write(&mut context.clock, context.registers.sp, lsb)?;
+    Ok(())
+}
+
+/// Pops a 16-bit value off the stack (lsb first), +2 M-C
+pub fn pop_u16(context: &mut CpuContext) -> Result<u16, EmuError> {
+    let lsb = context
+        .memory
+        .read(&mut context.clock, context.registers.sp)?;
+    context.registers.sp = context.registers.sp.wrapping_add(1);
+    let msb = context
+        .memory
+        .read(&mut context.clock, context.registers.sp)?;
+    context.registers.sp = context.registers.sp.wrapping_add(1);
+    Ok(alu::read_u16(&lsb, &msb))
+}
+
+/// push r16stk
+pub fn push(instruction: &Instruction, context: &mut CpuContext) -> Result<(), EmuError> {
+    let param = instruction.operand(0)?.r16()?;
+    // Internal delay before the writes
+    context.tick();
+    push_u16(context, param.read(&context.registers))
+}
+
+/// pop r16stk
+pub fn pop(instruction: &Instruction, context: &mut CpuContext) -> Result<(), EmuError> {
+    let param = instruction.operand(0)?.r16()?;
+    let mut value = pop_u16(context)?;
+    if let R16::AF = param {
+        // The lower nibble of F is always 0
+        value &= 0xFFF0;
+    }
+    param.write(value, &mut context.registers);
+    Ok(())
+}
+
+/// call n16 | call cc, n16
+pub fn call(instruction: &Instruction, context: &mut CpuContext) -> Result<(), EmuError> {
+    let target_address = alu::read_u16(&context.fetch()?, &context.fetch()?);
+    let taken = match instruction.condition() {
+        Some(cond) => context.registers.match_condition(cond)?,
+        None => true,
+    };
+    if taken {
+        context.tick();
+        push_u16(context, context.registers.pc)?;
+        context.registers.pc = target_address;
+    }
+    Ok(())
+}
+
+/// ret | ret cc | reti
+pub fn ret(instruction: &Instruction, context: &mut CpuContext) -> Result<(), EmuError> {
+    if instruction.op == Op::Reti {
+        // Unlike ei, reti enables interrupts immediately
+        context.registers.ime = true;
+    } else if let Some(cond) = instruction.condition() {
+        // Condition evaluation takes an extra cycle
+        context.tick();
+        if !context.registers.match_condition(cond)? {
+            return Ok(());
+        }
+    }
+    context.registers.pc = pop_u16(context)?;
+    context.tick();
+    Ok(())
+}
+
+/// rst vec
+pub fn rst(instruction: &Instruction, context: &mut CpuContext) -> Result<(), EmuError> {
+    let Operand::Vec(vector) = instruction.operand(0)? else {
+        return Err(EmuError::InvalidOperand(format!(
+            "{instruction} has no rst vector"
+        )));
+    };
+    context.tick();
+    push_u16(context, context.registers.pc)?;
+    context.registers.pc = vector as u16;
+    Ok(())
+}
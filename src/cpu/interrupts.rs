@@ -0,0 +1,70 @@
+use crate::{
+    cpu::{cpu_context::CpuContext, handlers::stack},
+    error::EmuError,
+};
+
+/// https://gbdev.io/pandocs/Interrupt_Sources.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interrupt {
+    VBlank,
+    Stat,
+    Timer,
+    Serial,
+    Joypad,
+}
+
+impl Interrupt {
+    /// Ordered from highest to lowest priority
+    pub const PRIORITY: [Interrupt; 5] = [
+        Interrupt::VBlank,
+        Interrupt::Stat,
+        Interrupt::Timer,
+        Interrupt::Serial,
+        Interrupt::Joypad,
+    ];
+
+    fn index(&self) -> u8 {
+        match self {
+            Interrupt::VBlank => 0,
+            Interrupt::Stat => 1,
+            Interrupt::Timer => 2,
+            Interrupt::Serial => 3,
+            Interrupt::Joypad => 4,
+        }
+    }
+
+    /// The interrupt's bit in the IE and IF registers
+    pub fn mask(&self) -> u8 {
+        1 << self.index()
+    }
+
+    /// The address the CPU jumps to when servicing the interrupt
+    pub fn vector(&self) -> u16 {
+        0x40 + 8 * self.index() as u16
+    }
+}
+
+/// Dispatches the highest priority pending interrupt if IME is set
+/// Returns whether an interrupt was serviced
+/// +5 M-C (2 wait states, 2 for pushing PC and 1 for setting PC)
+/// https://gbdev.io/pandocs/Interrupts.html#interrupt-handling
+pub fn service(context: &mut CpuContext) -> Result<bool, EmuError> {
+    if !context.registers.ime {
+        return Ok(false);
+    }
+    let pending = context.memory.pending_interrupts();
+    let Some(interrupt) = Interrupt::PRIORITY
+        .into_iter()
+        .find(|interrupt| pending & interrupt.mask() != 0)
+    else {
+        return Ok(false);
+    };
+    context.registers.ime = false;
+    context.memory.clear_interrupt(interrupt);
+    context.tick();
+    context.tick();
+    stack::push_u16(context, context.registers.pc)?;
+    context.registers.pc = interrupt.vector();
+    context.tick();
+    Ok(true)
+}
@@ -1,6 +1,8 @@
 pub mod alu;
 pub mod clock;
 pub mod cpu_context;
+pub mod decoder;
 pub mod handlers;
+pub mod interrupts;
 pub mod operands;
 pub mod reg_file;
@@ -1,4 +1,9 @@
-use crate::cpu::{alu, cpu_context::CpuContext, reg_file::RegFile};
+use std::fmt;
+
+use crate::{
+    cpu::{alu, cpu_context::CpuContext, decoder::Operand, reg_file::RegFile},
+    error::EmuError,
+};
 
 // the r8 param is a 3 bit param in the instruction opcode
 // it represents an 8-bit register
@@ -6,60 +11,62 @@ use crate::cpu::{alu, cpu_context::CpuContext, reg_file::RegFile};
 // from 0-7 in order (b,c,d,e,h,l,[hl],a)
 pub enum R8 {
     Register(u8),
-    Hl(u16),
-    N8(u8), // this is added for convinience some instructions that take r8 have an identical
-            // version that takes imm8 (i.e the next byte on the rom)
+    Mem(u16), // [hl] as well as the other memory operands ([r16mem], [n16], [$FF00 + n8]...)
+    N8(u8),   // this is added for convinience some instructions that take r8 have an identical
+              // version that takes imm8 (i.e the next byte on the rom)
 }
 
 impl R8 {
-    pub fn get_r8_param(
-        n8: bool,
-        opcode: u8,
-        index: u8,
-        context: &mut CpuContext,
-    ) -> Result<Self, String> {
-        if n8 {
-            return Ok(Self::N8(context.fetch()));
-        }
-        let param = alu::read_bits(opcode, index, 3);
-        if param == 6 {
-            let addr = alu::read_u16(&context.registers.l, &context.registers.h);
-            Ok(Self::Hl(addr))
-        } else {
-            Ok(Self::Register(param))
+    /// Resolves a decoded 8-bit operand, fetching any immediates it needs
+    /// [hl+] and [hl-] update hl once the address is resolved
+    pub fn new(operand: Operand, context: &mut CpuContext) -> Result<Self, EmuError> {
+        match operand {
+            Operand::R8(6) => Ok(Self::Mem(R16::HL.read(&context.registers))),
+            Operand::R8(reg) => Ok(Self::Register(reg)),
+            Operand::N8 => Ok(Self::N8(context.fetch()?)),
+            Operand::R16Mem(param) => {
+                let r16 = R16::new(param, R16Type::R16Mem);
+                let addr = r16.read(&context.registers);
+                match param {
+                    0x2 => r16.write(addr.wrapping_add(1), &mut context.registers),
+                    0x3 => r16.write(addr.wrapping_sub(1), &mut context.registers),
+                    _ => (),
+                }
+                Ok(Self::Mem(addr))
+            }
+            Operand::MemN16 => Ok(Self::Mem(alu::read_u16(
+                &context.fetch()?,
+                &context.fetch()?,
+            ))),
+            Operand::HighN8 => Ok(Self::Mem(0xFF00 | context.fetch()? as u16)),
+            Operand::HighC => Ok(Self::Mem(0xFF00 | context.registers.c as u16)),
+            _ => Err(EmuError::InvalidOperand(format!(
+                "Invalid r8 operand {operand}"
+            ))),
         }
     }
 
-    pub fn read(&self, context: &mut CpuContext) -> Result<u8, String> {
+    pub fn read(&self, context: &mut CpuContext) -> Result<u8, EmuError> {
         match self {
             Self::Register(reg) => Ok(*context.registers.match_r8(*reg)?),
-            Self::Hl(addr) => Ok(context.memory.read(&mut context.clock, *addr)?),
+            Self::Mem(addr) => Ok(context.memory.read(&mut context.clock, *addr)?),
             Self::N8(n) => Ok(*n),
         }
     }
 
-    pub fn write(&self, context: &mut CpuContext, value: u8) -> Result<(), String> {
+    pub fn write(&self, context: &mut CpuContext, value: u8) -> Result<(), EmuError> {
         match self {
             Self::Register(reg) => {
                 *context.registers.match_r8(*reg)? = value;
                 Ok(())
             }
-            Self::Hl(addr) => {
+            Self::Mem(addr) => {
                 context.memory.write(&mut context.clock, *addr, value)?;
                 Ok(())
             }
             Self::N8(_) => Ok(()),
         }
     }
-
-    //TODO: implement a better logging system
-    pub fn log(&self) {
-        match self {
-            Self::Register(_) => print!("r8 "),
-            Self::Hl(_) => print!("[hl] "),
-            Self::N8(_) => print!("imm8 "),
-        }
-    }
 }
 
 pub enum R16Type {
@@ -68,7 +75,7 @@ pub enum R16Type {
     R16Mem,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum R16 {
     BC,
     DE,
@@ -78,18 +85,17 @@ pub enum R16 {
 }
 
 impl R16 {
-    pub fn new(opcode: u8, index: u8, r16type: R16Type) -> Result<Self, String> {
-        let param = alu::read_bits(opcode, index, 2);
-        match param {
-            0x0 => Ok(Self::BC),
-            0x1 => Ok(Self::DE),
-            0x2 => Ok(Self::HL),
-            0x3 => match r16type {
-                R16Type::R16 => Ok(Self::SP),
-                R16Type::R16Stk => Ok(Self::AF),
-                R16Type::R16Mem => Ok(Self::HL),
+    /// Decodes the 2 bit r16 param of an opcode
+    pub const fn new(param: u8, r16type: R16Type) -> Self {
+        match param & 0x3 {
+            0x0 => Self::BC,
+            0x1 => Self::DE,
+            0x2 => Self::HL,
+            _ => match r16type {
+                R16Type::R16 => Self::SP,
+                R16Type::R16Stk => Self::AF,
+                R16Type::R16Mem => Self::HL,
             },
-            _ => Err("Invalid paramter R16".to_owned()),
         }
     }
 
@@ -113,3 +119,16 @@ impl R16 {
         }
     }
 }
+
+impl fmt::Display for R16 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let out = match self {
+            R16::BC => "bc",
+            R16::DE => "de",
+            R16::HL => "hl",
+            R16::AF => "af",
+            R16::SP => "sp",
+        };
+        write!(f, "{out}")
+    }
+}
@@ -1,6 +1,7 @@
-use crate::cpu::alu;
+use crate::{cpu::alu, error::EmuError};
 
 // TODO: Use Idiomatic rust names
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Modes {
     DMG,
     MGB,
@@ -43,6 +44,8 @@ pub struct RegFile {
     pub l: u8,
     pub sp: u16,
     pub pc: u16,
+    /// Interrupt master enable
+    pub ime: bool,
 }
 
 impl RegFile {
@@ -64,10 +67,11 @@ impl RegFile {
             l,
             sp: 0xFFFE,
             pc: 0x100,
+            ime: false,
         }
     }
 
-    pub fn match_r8(&mut self, num: u8) -> Result<&mut u8, &str> {
+    pub fn match_r8(&mut self, num: u8) -> Result<&mut u8, EmuError> {
         match num {
             0x0 => Ok(&mut self.b),
             0x1 => Ok(&mut self.c),
@@ -76,34 +80,26 @@ impl RegFile {
             0x4 => Ok(&mut self.h),
             0x5 => Ok(&mut self.l),
             0x7 => Ok(&mut self.a),
-            _ => Err("Invalid r8 index"),
+            _ => Err(EmuError::InvalidOperand(format!("r8 index {num}"))),
         }
     }
 
-    pub fn match_condition(&self, num: u8) -> Result<bool, &str> {
+    pub fn match_condition(&self, num: u8) -> Result<bool, EmuError> {
         match num {
             0x0 => Ok(!self.read_flag(Flag::Zero)),
             0x1 => Ok(self.read_flag(Flag::Zero)),
             0x2 => Ok(!self.read_flag(Flag::Carry)),
             0x3 => Ok(self.read_flag(Flag::Carry)),
-            _ => Err("Invalid condition parameter"),
+            _ => Err(EmuError::InvalidOperand(format!("condition {num}"))),
         }
     }
 
     pub fn read_flag(&self, flag: Flag) -> bool {
         let index = flag.bit_index();
-        let value = alu::read_bits(self.f, index, 1);
-        match value {
-            1 => true,
-            0 => false,
-            _ => {
-                eprintln!("WARNING: alu::read_bits returned {value} for single bit read");
-                true
-            }
-        }
+        alu::read_bits(self.f, index, 1) != 0
     }
 
-    pub fn set_flag(&mut self, flag: Flag, value: Option<bool>) -> Result<(), String> {
+    pub fn set_flag(&mut self, flag: Flag, value: Option<bool>) -> Result<(), EmuError> {
         let index = flag.bit_index();
         match value {
             Some(bit) => alu::write_bits(&mut self.f, index, 1, bit as u8)?,
@@ -118,7 +114,7 @@ impl RegFile {
     /// Takes a &[u8; 4] and sets the four flags accordingly in the following order
     /// [Z, N, H, C]
     /// see https://gbdev.io/gb-opcodes/optables/
-    pub fn set_all_flags(&mut self, flags: &[u8; 4]) -> Result<(), String> {
+    pub fn set_all_flags(&mut self, flags: &[u8; 4]) -> Result<(), EmuError> {
         for (index, &bit) in flags.iter().enumerate() {
             alu::write_bits(&mut self.f, (7 - index) as u8, 1, bit)?;
         }
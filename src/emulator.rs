@@ -1,14 +1,91 @@
 use crate::cpu::clock::Clock;
 use crate::cpu::cpu_context::CpuContext;
 use crate::cpu::reg_file::{Modes, RegFile};
-use crate::mem::map;
-use crate::rom::rom_info::ROMInfo;
+use crate::error::EmuError;
+use crate::mem::map::MemoryMap;
+use crate::mem::mbc;
+use crate::mem::save::SaveFile;
+use crate::ppu::Renderer;
+use crate::rom::rom_info::{CGBMode, ROMInfo};
 
-pub fn init_emulation(rom: Vec<u8>, header_data: ROMInfo) -> Result<(), String> {
-    let registers = RegFile::new(Modes::DMG);
-    let memory = map::MemoryMap::init_rom(rom, header_data);
+/// Battery RAM is written back at most once per emulated second
+const AUTOSAVE_INTERVAL: u64 = 4_194_304;
+/// T-Cs per video frame, the frontend is polled at this rate even with the LCD off
+const FRAME_CYCLES: u64 = 70_224;
+
+/// The host side of the emulation: screen, input and audio
+pub trait Frontend {
+    /// Called once before the first frame
+    fn start(&mut self, _memory: &mut MemoryMap) {}
+    /// Called once per video frame, presents the screen and forwards input and audio
+    /// Returns false once the user asked to quit
+    fn frame(&mut self, memory: &mut MemoryMap) -> bool;
+}
+
+/// Runs the ROM until the frontend quits or the CPU faults
+/// `save` is only used if the cartridge has a battery, `wall_clock` makes the RTC follow
+/// the host's time so it catches up on the time the emulator was closed, `trace` prints
+/// every executed instruction
+pub fn init_emulation(
+    rom: Vec<u8>,
+    header_data: ROMInfo,
+    save: Option<SaveFile>,
+    renderer: Renderer,
+    wall_clock: bool,
+    trace: bool,
+    frontend: &mut impl Frontend,
+) -> Result<(), EmuError> {
+    if !mbc::is_supported(&header_data.cartridge_type) {
+        return Err(EmuError::UnsupportedCartridge(header_data.cartridge_type));
+    }
+    let save = save.filter(|_| header_data.cartridge_type.has_battery());
+    let mode = match header_data.cgb {
+        CGBMode::Color { .. } => Modes::CGB,
+        CGBMode::Monochrome => Modes::DMG,
+    };
+    let registers = RegFile::new(mode);
+    let memory = MemoryMap::with_renderer(rom, header_data, mode, renderer);
     let clock = Clock::default();
     let mut context = CpuContext::init(registers, memory, clock);
-    context.start_exec_cycle()?;
-    Ok(())
+    context.trace = trace;
+    if let Some(rtc) = context.memory.rtc_mut() {
+        rtc.set_wall_clock(wall_clock);
+    }
+    if let Some(save) = &save {
+        save.load(&mut context.memory)?;
+    }
+    frontend.start(&mut context.memory);
+    let result = run(&mut context, save.as_ref(), frontend);
+    // Flush on the way out, even if the game crashed
+    let flushed = match &save {
+        Some(save) if context.memory.take_save_dirty(&context.clock) => save.flush(&context.memory),
+        _ => Ok(()),
+    };
+    result.and(flushed)
+}
+
+fn run(
+    context: &mut CpuContext,
+    save: Option<&SaveFile>,
+    frontend: &mut impl Frontend,
+) -> Result<(), EmuError> {
+    let mut last_autosave = 0;
+    let mut last_frame = 0;
+    loop {
+        context.step()?;
+        if context.clock.t_cycles - last_frame >= FRAME_CYCLES {
+            last_frame += FRAME_CYCLES;
+            if !frontend.frame(&mut context.memory) {
+                return Ok(());
+            }
+        }
+        if let Some(save) = save
+            && context.clock.t_cycles - last_autosave >= AUTOSAVE_INTERVAL
+        {
+            last_autosave = context.clock.t_cycles;
+            if context.memory.take_save_dirty(&context.clock) {
+                save.flush(&context.memory)?;
+            }
+        }
+    }
 }
@@ -0,0 +1,55 @@
+use std::fmt;
+
+use crate::rom::{cartridge_type::CartridgeType, rom_error::RomError};
+
+/// Errors raised while emulating
+/// game faults (illegal opcodes, bad addresses) are kept apart from emulator bugs (invalid operands)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EmuError {
+    /// The CPU fetched one of the unused opcodes, the game most likely crashed
+    IllegalOpcode { pc: u16, opcode: u8 },
+    /// Access to an unmapped or prohibited address
+    InvalidAddress { addr: u16 },
+    /// The cartridge uses a mapper RedGB doesn't implement
+    UnsupportedCartridge(CartridgeType),
+    /// A handler got an operand or parameter it can't take, this is an emulator bug
+    InvalidOperand(String),
+    /// The battery save couldn't be read or written
+    SaveFile(String),
+    /// The ROM file couldn't be parsed
+    RomParse(RomError),
+}
+
+impl fmt::Display for EmuError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EmuError::IllegalOpcode { pc, opcode } => {
+                write!(f, "Error: Illegal opcode {opcode:#04X} at {pc:#06X}")
+            }
+            EmuError::InvalidAddress { addr } => {
+                write!(f, "Error: Out of bounds or invalid address {addr:#06X}")
+            }
+            EmuError::UnsupportedCartridge(cartridge_type) => {
+                write!(f, "Error: Unsupported cartridge type {cartridge_type}")
+            }
+            EmuError::InvalidOperand(s) => write!(f, "Error: Invalid operand ({s})"),
+            EmuError::SaveFile(s) => write!(f, "Error: Save file ({s})"),
+            EmuError::RomParse(e) => write!(f, "Error: Invalid ROM file ({e})"),
+        }
+    }
+}
+
+impl std::error::Error for EmuError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EmuError::RomParse(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<RomError> for EmuError {
+    fn from(e: RomError) -> Self {
+        EmuError::RomParse(e)
+    }
+}
@@ -0,0 +1,195 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use redgb::{
+    apu::{AudioBuffer, DEFAULT_SAMPLE_RATE, TARGET_LATENCY},
+    emulator::Frontend,
+    joypad::Button,
+    mem::map::MemoryMap,
+    ppu::{SCREEN_HEIGHT, SCREEN_WIDTH},
+};
+use sdl2::{
+    EventPump,
+    audio::{AudioQueue, AudioSpecDesired},
+    event::Event,
+    keyboard::Keycode,
+    pixels::PixelFormatEnum,
+    surface::Surface,
+    video::Window,
+};
+
+/// Window pixels per Game Boy pixel
+const SCALE: u32 = 4;
+/// 70224 T-C at 4.194304 MHz, about 59.7 frames per second
+const FRAME_DURATION: Duration = Duration::from_nanos(16_742_706);
+/// Size of the audio device buffer, in stereo frames
+const AUDIO_CHUNK: u16 = 1024;
+
+/// Arrows for the d-pad, X and Z for A and B, Enter and Backspace for Start and Select
+fn button(key: Keycode) -> Option<Button> {
+    match key {
+        Keycode::Right => Some(Button::Right),
+        Keycode::Left => Some(Button::Left),
+        Keycode::Up => Some(Button::Up),
+        Keycode::Down => Some(Button::Down),
+        Keycode::X => Some(Button::A),
+        Keycode::Z => Some(Button::B),
+        Keycode::Backspace => Some(Button::Select),
+        Keycode::Return => Some(Button::Start),
+        _ => None,
+    }
+}
+
+/// Window, input and audio through SDL
+pub struct SdlFrontend {
+    events: EventPump,
+    window: Window,
+    /// The framebuffer as XRGB8888 bytes
+    pixels: Vec<u8>,
+    /// When the next frame is due, the emulation is paced to real time
+    next_frame: Instant,
+    audio: AudioQueue<f32>,
+    /// Where the APU writes its samples, set by `start`
+    samples: Option<AudioBuffer>,
+    /// Interleaved samples on their way to `audio`
+    queued: Vec<f32>,
+}
+
+impl SdlFrontend {
+    pub fn new() -> Result<Self, String> {
+        let sdl = sdl2::init()?;
+        let window = sdl
+            .video()?
+            .window(
+                "RedGB",
+                SCREEN_WIDTH as u32 * SCALE,
+                SCREEN_HEIGHT as u32 * SCALE,
+            )
+            .position_centered()
+            .build()
+            .map_err(|e| e.to_string())?;
+        let audio = sdl.audio()?.open_queue(
+            None,
+            &AudioSpecDesired {
+                freq: Some(DEFAULT_SAMPLE_RATE as i32),
+                channels: Some(2),
+                samples: Some(AUDIO_CHUNK),
+            },
+        )?;
+        audio.resume();
+        Ok(Self {
+            events: sdl.event_pump()?,
+            window,
+            pixels: vec![0; SCREEN_WIDTH * SCREEN_HEIGHT * 4],
+            next_frame: Instant::now(),
+            audio,
+            samples: None,
+            queued: Vec::new(),
+        })
+    }
+
+    /// Forwards the keyboard to the joypad, false on quit
+    fn handle_events(&mut self, memory: &mut MemoryMap) -> bool {
+        for event in self.events.poll_iter() {
+            match event {
+                // SDL turns Ctrl-C into a quit event too
+                Event::Quit { .. }
+                | Event::KeyDown {
+                    keycode: Some(Keycode::Escape),
+                    ..
+                } => return false,
+                Event::KeyDown {
+                    keycode: Some(key),
+                    repeat: false,
+                    ..
+                } => {
+                    if let Some(button) = button(key) {
+                        memory.set_button(button, true);
+                    }
+                }
+                Event::KeyUp {
+                    keycode: Some(key), ..
+                } => {
+                    if let Some(button) = button(key) {
+                        memory.set_button(button, false);
+                    }
+                }
+                _ => (),
+            }
+        }
+        true
+    }
+
+    fn present(&mut self, memory: &MemoryMap) -> Result<(), String> {
+        for (pixel, color) in self.pixels.chunks_mut(4).zip(memory.framebuffer()) {
+            pixel.copy_from_slice(&color.to_le_bytes());
+        }
+        let frame = Surface::from_data(
+            &mut self.pixels,
+            SCREEN_WIDTH as u32,
+            SCREEN_HEIGHT as u32,
+            SCREEN_WIDTH as u32 * 4,
+            PixelFormatEnum::RGB888,
+        )?;
+        let mut screen = self.window.surface(&self.events)?;
+        frame.blit_scaled(None, &mut screen, None)?;
+        screen.update_window()
+    }
+
+    /// Stereo frames SDL has yet to play
+    fn audio_queued(&self) -> usize {
+        self.audio.size() as usize / size_of::<[f32; 2]>()
+    }
+
+    /// Moves everything the APU produced to the SDL queue and reports how full it is,
+    /// the APU adjusts its rate so the queue neither runs dry nor keeps growing
+    fn queue_audio(&mut self, memory: &mut MemoryMap) -> Result<(), String> {
+        let Some(samples) = &self.samples else {
+            return Ok(());
+        };
+        self.queued.resize(samples.len() * 2, 0.0);
+        samples.fill(&mut self.queued);
+        self.audio.queue_audio(&self.queued)?;
+        memory.set_audio_queued(self.audio_queued());
+        Ok(())
+    }
+
+    /// Sleeps until the next frame is due, a host that falls behind doesn't try to catch up
+    fn wait(&mut self) {
+        let now = Instant::now();
+        if self.next_frame > now {
+            thread::sleep(self.next_frame - now);
+        } else if now - self.next_frame > FRAME_DURATION {
+            self.next_frame = now;
+        }
+        self.next_frame += FRAME_DURATION;
+    }
+}
+
+impl Frontend for SdlFrontend {
+    /// The APU resamples to whatever rate the device was opened with
+    /// The queue starts out with `TARGET_LATENCY` of silence so it doesn't run dry right away
+    fn start(&mut self, memory: &mut MemoryMap) {
+        let sample_rate = self.audio.spec().freq as u32;
+        memory.set_sample_rate(sample_rate);
+        self.samples = Some(memory.audio_output());
+        let silence = vec![0.0; (sample_rate as f64 * TARGET_LATENCY) as usize * 2];
+        if let Err(e) = self.audio.queue_audio(&silence) {
+            eprintln!("SDL: {e}");
+        }
+    }
+
+    fn frame(&mut self, memory: &mut MemoryMap) -> bool {
+        if !self.handle_events(memory) {
+            return false;
+        }
+        if let Err(e) = self.present(memory).and_then(|()| self.queue_audio(memory)) {
+            eprintln!("SDL: {e}");
+            return false;
+        }
+        self.wait();
+        true
+    }
+}
@@ -0,0 +1,81 @@
+use crate::cpu::interrupts::Interrupt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Button {
+    Right,
+    Left,
+    Up,
+    Down,
+    A,
+    B,
+    Select,
+    Start,
+}
+
+impl Button {
+    /// Bit in the pressed mask, d-pad in the low nibble and buttons in the high one
+    fn mask(&self) -> u8 {
+        match self {
+            Button::Right => 0x01,
+            Button::Left => 0x02,
+            Button::Up => 0x04,
+            Button::Down => 0x08,
+            Button::A => 0x10,
+            Button::B => 0x20,
+            Button::Select => 0x40,
+            Button::Start => 0x80,
+        }
+    }
+}
+
+/// P1/JOYP, the buttons are read through two selectable 4 bit lines
+/// https://gbdev.io/pandocs/Joypad_Input.html
+#[derive(Debug, Default)]
+pub struct Joypad {
+    /// Bits 4-5 of P1, a line is selected when its bit is 0
+    select: u8,
+    pressed: u8,
+    /// Set when a selected line goes low, wakes the CPU from STOP
+    line_fell: bool,
+}
+
+impl Joypad {
+    /// The lower nibble of P1, 0 means pressed
+    fn lines(&self) -> u8 {
+        let mut lines = 0;
+        if self.select & 0x10 == 0 {
+            lines |= self.pressed & 0xF;
+        }
+        if self.select & 0x20 == 0 {
+            lines |= self.pressed >> 4;
+        }
+        !lines & 0xF
+    }
+
+    pub fn read(&self) -> u8 {
+        0xC0 | self.select | self.lines()
+    }
+
+    pub fn write(&mut self, value: u8) {
+        self.select = value & 0x30;
+    }
+
+    /// Updates a button, a selected line going low requests the joypad interrupt
+    pub fn set_button(&mut self, button: Button, pressed: bool, int_flag: &mut u8) {
+        let before = self.lines();
+        if pressed {
+            self.pressed |= button.mask();
+        } else {
+            self.pressed &= !button.mask();
+        }
+        if before & !self.lines() != 0 {
+            *int_flag |= Interrupt::Joypad.mask();
+            self.line_fell = true;
+        }
+    }
+
+    /// Whether a selected line went low since the last call
+    pub fn take_line_fell(&mut self) -> bool {
+        std::mem::take(&mut self.line_fell)
+    }
+}
@@ -1,4 +1,10 @@
+pub mod apu;
 pub mod cpu;
 pub mod emulator;
+pub mod error;
+pub mod joypad;
 pub mod mem;
+pub mod ppu;
 pub mod rom;
+pub mod serial;
+pub mod timer;
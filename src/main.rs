@@ -1,33 +1,80 @@
+mod frontend;
+
+use frontend::SdlFrontend;
 use redgb::emulator;
-use redgb::rom::{rom_info, rom_parser};
-use std::io::Write;
-use std::{env, fs, io};
+use redgb::error::EmuError;
+use redgb::mem::save::SaveFile;
+use redgb::ppu::Renderer;
+use redgb::rom::rom_parser::{self, ParseMode};
+use redgb::rom::rom_validation::RomValidation;
+use std::io::{self, Write};
+use std::path::Path;
+use std::{env, fs};
+
+fn default_rom_path() -> String {
+    let mut rom_path = String::new();
+    print!("Input ROM:");
+    io::stdout().flush().unwrap();
+    io::stdin()
+        .read_line(&mut rom_path)
+        .expect("Input error occoured");
+    rom_path.trim().to_string()
+}
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    let mut rom_path: String = String::new();
-    #[cfg(not(debug_assertions))]
-    {
-        if args.len() < 2 {
-            print!("Input ROM:");
-            io::stdout().flush().unwrap();
-            io::stdin()
-                .read_line(&mut rom_path)
-                .expect("Input error occoured");
-            rom_path = rom_path.trim().to_string();
-        } else {
-            rom_path = args[1].clone();
+    let args: Vec<String> = env::args().skip(1).collect();
+    // --fifo picks the slower, cycle accurate PPU
+    let renderer = if args.iter().any(|arg| arg == "--fifo") {
+        Renderer::Fifo
+    } else {
+        Renderer::Scanline
+    };
+    // --wall-clock runs the cartridge RTC on the host's time, even while closed
+    let wall_clock = args.iter().any(|arg| arg == "--wall-clock");
+    // --trace prints every executed instruction, too slow to keep up in real time
+    let trace = args.iter().any(|arg| arg == "--trace");
+    let rom_path = match args.iter().find(|arg| !arg.starts_with("--")) {
+        Some(path) => path.clone(),
+        None => default_rom_path(),
+    };
+    println!("Reading input rom: {rom_path}");
+    let rom = fs::read(&rom_path).expect("Failed to read file");
+    let info = match rom_parser::parse_rom_header_with(&rom, ParseMode::Lenient) {
+        Ok(info) => info,
+        Err(e) => {
+            eprintln!("{}", EmuError::from(e));
+            return;
         }
+    };
+    for warning in &info.warnings {
+        eprintln!("Warning: {warning}");
     }
-    #[cfg(debug_assertions)]
-    {
-        rom_path = "/home/felo/dev/rust/RedGB/test_roms/tetris.gb".to_string();
+    for issue in RomValidation::new(&rom, &info).issues {
+        eprintln!("Warning: {issue}");
     }
-    println!("Reading input rom: {rom_path}");
-    let rom = fs::read(rom_path).expect("Failed to read file");
-    let info: rom_info::ROMInfo = rom_parser::parse_rom_header(&rom);
-    match emulator::init_emulation(rom, info) {
-        Ok(()) => (),
-        Err(s) => eprintln!("{}", s),
+    println!(
+        "{} ({}, {})",
+        info.title.trim_end_matches('\0'),
+        info.cgb,
+        info.cartridge_type
+    );
+    let save = SaveFile::for_rom(Path::new(&rom_path));
+    let mut frontend = match SdlFrontend::new() {
+        Ok(frontend) => frontend,
+        Err(e) => {
+            eprintln!("SDL: {e}");
+            return;
+        }
+    };
+    if let Err(e) = emulator::init_emulation(
+        rom,
+        info,
+        Some(save),
+        renderer,
+        wall_clock,
+        trace,
+        &mut frontend,
+    ) {
+        eprintln!("{e}");
     }
 }
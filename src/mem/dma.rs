@@ -0,0 +1,185 @@
+use crate::{cpu::clock::Clock, mem::map::MemoryMap};
+
+/// Bytes copied by an OAM DMA, one per M-C
+const OAM_DMA_LENGTH: u8 = 0xA0;
+
+/// Copies 160 bytes from `XX00` to OAM when XX is written to DMA (0xFF46)
+/// https://gbdev.io/pandocs/OAM_DMA_Transfer.html
+#[derive(Debug)]
+pub(super) struct OamDma {
+    /// Last value written to DMA
+    register: u8,
+    source: u16,
+    /// Next byte to copy, `OAM_DMA_LENGTH` when idle
+    index: u8,
+    /// Source and remaining delay of a transfer that was just requested
+    /// a transfer already running keeps going until the new one starts
+    pending: Option<(u16, u8)>,
+}
+
+impl OamDma {
+    pub fn new(register: u8) -> Self {
+        Self {
+            register,
+            source: 0,
+            index: OAM_DMA_LENGTH,
+            pending: None,
+        }
+    }
+
+    pub fn read(&self) -> u8 {
+        self.register
+    }
+
+    /// Starts a transfer after a 1 M-C delay
+    pub fn write(&mut self, value: u8) {
+        self.register = value;
+        // 0xE000 and up reads from WRAM, like the echo RAM
+        let source = if value >= 0xE0 { value & 0xDF } else { value };
+        self.pending = Some(((source as u16) << 8, 1));
+    }
+
+    pub fn active(&self) -> bool {
+        self.index < OAM_DMA_LENGTH
+    }
+
+    /// While copying the CPU only has access to the IO registers and HRAM
+    pub fn blocks(&self, addr: u16) -> bool {
+        self.active() && addr < 0xFF00
+    }
+}
+
+impl MemoryMap {
+    /// Copies the next OAM DMA byte, called once per M-C
+    pub(super) fn tick_oam_dma(&mut self) {
+        match self.oam_dma.pending {
+            Some((source, 0)) => {
+                self.oam_dma.pending = None;
+                self.oam_dma.source = source;
+                self.oam_dma.index = 0;
+            }
+            Some((source, delay)) => self.oam_dma.pending = Some((source, delay - 1)),
+            None => (),
+        }
+        if self.oam_dma.active() {
+            let index = self.oam_dma.index;
+            self.oam[index as usize] = self.dma_read(self.oam_dma.source + index as u16);
+            self.oam_dma.index += 1;
+        }
+    }
+
+    /// Reads the DMA source bus, which doesn't go through the CPU's access restrictions
+    pub(super) fn dma_read(&self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x7FFF => self.cartridge.read_rom(addr),
+            0x8000..=0x9FFF => self.vram[self.active_vram][(addr - 0x8000) as usize],
+            0xA000..=0xBFFF => self.cartridge.read_ram(addr),
+            0xC000..=0xCFFF => self.wram[0][(addr - 0xC000) as usize],
+            0xD000..=0xDFFF => self.wram[self.active_wram][(addr - 0xD000) as usize],
+            _ => 0xFF,
+        }
+    }
+}
+
+/// Bytes copied per HBlank, and per block of a general purpose transfer
+const HDMA_BLOCK: u16 = 0x10;
+
+/// CGB VRAM DMA (HDMA1-HDMA5), copies blocks of 16 bytes to the active VRAM bank
+/// either all at once (general purpose) or 1 block per HBlank, the CPU is stalled while copying
+/// https://gbdev.io/pandocs/CGB_Registers.html#lcd-vram-dma-transfers
+#[derive(Debug, Default)]
+pub(super) struct Hdma {
+    source: u16,
+    /// Offset in VRAM
+    destination: u16,
+    /// Blocks left to copy
+    remaining: u8,
+    active: bool,
+    hblank: bool,
+    /// Bytes to copy before the CPU resumes
+    due: u16,
+}
+
+impl Hdma {
+    /// HDMA5, bit 7 is clear while a transfer is active
+    pub fn read(&self) -> u8 {
+        let status = if self.active { 0x00 } else { 0x80 };
+        status | (self.remaining.wrapping_sub(1) & 0x7F)
+    }
+
+    pub fn write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0xFF51 => self.source = (self.source & 0x00FF) | ((value as u16) << 8),
+            0xFF52 => self.source = (self.source & 0xFF00) | (value & 0xF0) as u16,
+            0xFF53 => {
+                self.destination = (self.destination & 0x00FF) | (((value & 0x1F) as u16) << 8)
+            }
+            0xFF54 => self.destination = (self.destination & 0xFF00) | (value & 0xF0) as u16,
+            0xFF55 if self.active && self.hblank && value & 0x80 == 0 => {
+                // Cancels the HBlank transfer, the remaining length stays readable
+                self.active = false;
+            }
+            0xFF55 => {
+                self.remaining = (value & 0x7F) + 1;
+                self.active = true;
+                self.hblank = value & 0x80 != 0;
+                if !self.hblank {
+                    self.due = self.remaining as u16 * HDMA_BLOCK;
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// The PPU entered HBlank, an HBlank transfer copies its next block
+    pub fn hblank(&mut self) {
+        if self.active && self.hblank && self.due == 0 {
+            self.due = HDMA_BLOCK;
+        }
+    }
+
+    /// The CPU is halted, the block of the current HBlank isn't copied
+    pub fn skip_hblank(&mut self) {
+        if self.hblank {
+            self.due = 0;
+        }
+    }
+}
+
+impl MemoryMap {
+    /// HBlank HDMA doesn't copy while the CPU is halted, blocks of the skipped HBlanks are dropped
+    pub fn skip_hblank_hdma(&mut self) {
+        self.hdma.skip_hblank();
+    }
+
+    /// Runs 1 M-C of a pending HDMA copy, returns false if there's nothing to copy
+    /// a block takes 8 M-Cs, 16 in double speed
+    pub fn step_hdma(&mut self, clock: &mut Clock) -> bool {
+        if self.hdma.due == 0 {
+            return false;
+        }
+        let bytes = if self.double_speed() { 1 } else { 2 };
+        for _ in 0..bytes {
+            let value = self.dma_read(self.hdma.source);
+            self.vram[self.active_vram][self.hdma.destination as usize] = value;
+            self.hdma.source = self.hdma.source.wrapping_add(1);
+            self.hdma.destination += 1;
+            self.hdma.due -= 1;
+            // The transfer ends at the end of VRAM instead of wrapping around
+            if self.hdma.destination > 0x1FFF {
+                self.hdma.active = false;
+                self.hdma.remaining = 0;
+                self.hdma.due = 0;
+                break;
+            }
+            if self.hdma.due.is_multiple_of(HDMA_BLOCK) {
+                self.hdma.remaining -= 1;
+                if self.hdma.remaining == 0 {
+                    self.hdma.active = false;
+                }
+            }
+        }
+        self.tick(clock);
+        true
+    }
+}
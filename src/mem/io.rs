@@ -0,0 +1,48 @@
+use crate::mem::map::MemoryMap;
+
+/// Memory mapped IO registers (0xFF00-0xFF7F), each one is routed to the subsystem that owns it
+/// Unused bits and write-only registers read as 1, unmapped registers read 0xFF and ignore writes
+/// https://gbdev.io/pandocs/Hardware_Reg_List.html
+impl MemoryMap {
+    pub(super) fn read_io(&self, addr: u16) -> u8 {
+        match addr {
+            0xFF00 => self.joypad.read(),
+            0xFF01..=0xFF02 => self.serial.read(addr),
+            0xFF04..=0xFF07 => self.timer.read(addr),
+            // The upper 3 bits of IF are unused
+            0xFF0F => self.int_flag | 0xE0,
+            0xFF10..=0xFF3F => self.apu.read(addr),
+            0xFF46 => self.oam_dma.read(),
+            0xFF40..=0xFF4B => self.ppu.read(addr),
+            // CGB only registers
+            _ if !self.cgb => 0xFF,
+            0xFF4D => self.key1,
+            0xFF4F => 0xFE | self.active_vram as u8,
+            0xFF55 => self.hdma.read(),
+            0xFF68..=0xFF6C => self.ppu.read(addr),
+            0xFF70 => 0xF8 | self.active_wram as u8,
+            _ => 0xFF,
+        }
+    }
+
+    pub(super) fn write_io(&mut self, addr: u16, value: u8) {
+        match addr {
+            0xFF00 => self.joypad.write(value),
+            0xFF01..=0xFF02 => self.serial.write(addr, value),
+            0xFF04..=0xFF07 => self.timer.write(addr, value),
+            0xFF0F => self.int_flag = value | 0xE0,
+            0xFF10..=0xFF3F => self.apu.write(addr, value),
+            0xFF46 => self.oam_dma.write(value),
+            0xFF40..=0xFF4B => self.ppu.write(addr, value),
+            _ if !self.cgb => (),
+            // Only the speed switch armed bit is writable
+            0xFF4D => self.key1 = (self.key1 & 0x80) | 0x7E | (value & 0x1),
+            0xFF4F => self.active_vram = (value & 0x1) as usize,
+            0xFF51..=0xFF55 => self.hdma.write(addr, value),
+            0xFF68..=0xFF6C => self.ppu.write(addr, value),
+            // Bank 0 can't be mapped at 0xD000, writing 0 selects bank 1
+            0xFF70 => self.active_wram = ((value & 0x7) as usize).max(1),
+            _ => (),
+        }
+    }
+}
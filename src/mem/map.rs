@@ -1,94 +1,167 @@
 use crate::{
-    cpu::{clock::Clock, cpu_context::CpuContext},
-    rom::rom_info::ROMInfo,
+    apu::{Apu, AudioBuffer},
+    cpu::{clock::Clock, interrupts::Interrupt, reg_file::Modes},
+    error::EmuError,
+    joypad::{Button, Joypad},
+    mem::{
+        dma::{Hdma, OamDma},
+        mbc::{self, Mapper, rtc::Rtc},
+    },
+    ppu::{Ppu, Renderer, VideoMemory},
+    rom::rom_info::{CGBMode, ROMInfo},
+    serial::Serial,
+    timer::Timer,
 };
 
 #[derive(Debug)]
 pub struct MemoryMap {
-    rom_banks: Vec<Vec<u8>>,
-    active_rom_bank: usize,
-    vram: Vec<Vec<u8>>,
-    active_vram: usize,
-    eram: Vec<Vec<u8>>,
-    active_eram: usize,
-    wram: Vec<Vec<u8>>,
-    active_wram: usize,
-    oam: Vec<u8>,
-    io: Vec<u8>,
+    pub(super) cartridge: Box<dyn Mapper>,
+    pub(super) vram: Vec<Vec<u8>>,
+    pub(super) active_vram: usize,
+    pub(super) wram: Vec<Vec<u8>>,
+    pub(super) active_wram: usize,
+    pub(super) oam: Vec<u8>,
     hram: Vec<u8>,
+    pub(super) int_flag: u8,
     ie: u8,
+    /// Running in CGB mode, CGB hardware running a DMG game is not
+    pub(super) cgb: bool,
+    pub(super) key1: u8,
+    pub(super) oam_dma: OamDma,
+    pub(super) hdma: Hdma,
+    pub(super) joypad: Joypad,
+    pub(super) serial: Serial,
+    pub(super) timer: Timer,
+    pub(super) apu: Apu,
+    pub(super) ppu: Ppu,
 }
 
 impl MemoryMap {
+    /// Picks the hardware from the header, CGB for color games and DMG otherwise
     pub fn init_rom(rom: Vec<u8>, header_data: ROMInfo) -> Self {
-        let mut rom_banks: Vec<Vec<u8>> = Vec::new();
-        for bank in rom.chunks(0x4000) {
-            rom_banks.push(bank.to_vec());
-        }
+        let mode = match header_data.cgb {
+            CGBMode::Color { .. } => Modes::CGB,
+            CGBMode::Monochrome => Modes::DMG,
+        };
+        Self::new(rom, header_data, mode)
+    }
+
+    /// IO registers start with the values the boot ROM of `mode` leaves behind
+    pub fn new(rom: Vec<u8>, header_data: ROMInfo, mode: Modes) -> Self {
+        Self::with_renderer(rom, header_data, mode, Renderer::default())
+    }
+
+    /// Same as `new`, with a choice of PPU renderer
+    pub fn with_renderer(
+        rom: Vec<u8>,
+        header_data: ROMInfo,
+        mode: Modes,
+        renderer: Renderer,
+    ) -> Self {
         Self {
-            active_rom_bank: 1,
-            rom_banks,
+            cartridge: mbc::from_info(rom, &header_data),
             vram: vec![vec![0; 0x2000]; 2],
             active_vram: 0,
-            eram: vec![vec![0; 0x2000]; header_data.mem_banks as usize],
-            active_eram: 1,
             wram: vec![vec![0; 0x2000]; 8],
             active_wram: 1,
             oam: vec![0; 0x100],
-            io: vec![0; 0x80],
             hram: vec![0; 0x7E],
+            int_flag: 0xE1,
             ie: 0,
+            cgb: mode == Modes::CGB,
+            key1: 0x7E,
+            oam_dma: OamDma::new(match mode {
+                Modes::DMG | Modes::MGB => 0xFF,
+                Modes::CGB | Modes::CGBDMG => 0x00,
+            }),
+            hdma: Hdma::default(),
+            joypad: Joypad::default(),
+            serial: Serial::new(mode),
+            timer: Timer::new(mode),
+            apu: Apu::default(),
+            ppu: Ppu::new(mode, renderer),
+        }
+    }
+    /// Advances the clock and every subsystem by 1 M-C
+    /// In double speed the timer keeps up with the CPU while the PPU gets half the dots
+    pub fn tick(&mut self, clock: &mut Clock) {
+        let double_speed = self.double_speed();
+        clock.tick(double_speed);
+        self.timer.tick(&mut self.int_flag);
+        self.tick_oam_dma();
+        let video = VideoMemory {
+            vram: &self.vram,
+            oam: &self.oam,
+        };
+        let dots = if double_speed { 2 } else { 4 };
+        self.ppu.tick(dots, &video, &mut self.int_flag);
+        self.apu.tick(dots, self.timer.divider(), double_speed);
+        if self.ppu.take_hblank() {
+            self.hdma.hblank();
         }
     }
+
     /// +1 M-C (4 T-C)
-    pub fn read(&self, clock: &mut Clock, addr: u16) -> Result<u8, String> {
-        clock.tick();
+    pub fn read(&mut self, clock: &mut Clock, addr: u16) -> Result<u8, EmuError> {
+        self.tick(clock);
+        if self.oam_dma.blocks(addr) {
+            return Ok(0xFF);
+        }
         let addr = addr as usize;
         match addr {
-            0x0000..=0x3FFF => self.rom_banks[0].get(addr),
-            0x4000..=0x7FFF => self.rom_banks[self.active_rom_bank].get(addr - 0x4000),
+            0x0000..=0x7FFF => return Ok(self.cartridge.read_rom(addr as u16)),
+            0x8000..=0x9FFF if !self.ppu.vram_accessible() => Some(&0xFF),
             0x8000..=0x9FFF => self.vram[self.active_vram].get(addr - 0x8000),
-            0xA000..=0xBFFF => self.eram[self.active_eram].get(addr - 0xA000),
+            0xA000..=0xBFFF => return Ok(self.cartridge.read_ram(addr as u16)),
             0xC000..=0xCFFF => self.wram[0].get(addr - 0xC000),
             0xD000..=0xDFFF => self.wram[self.active_wram].get(addr - 0xD000),
             0xE000..=0xEFFF => self.wram[0].get(addr - 0xE000),
             0xF000..=0xFDFF => self.wram[self.active_wram].get(addr - 0xF000),
+            0xFE00..=0xFE9F if !self.ppu.oam_accessible() => Some(&0xFF),
             0xFE00..=0xFE9F => self.oam.get(addr - 0xFE00),
             0xFEA0..=0xFEFF => Some(&0),
-            0xFF00..=0xFF7F => self.io.get(addr - 0xFF00),
+            0xFF00..=0xFF7F => return Ok(self.read_io(addr as u16)),
             0xFF80..=0xFFFE => self.hram.get(addr - 0xFF80),
             0xFFFF => Some(&self.ie),
             _ => None,
         }
         .copied()
-        .ok_or(format!("Error: Out of bounds address {}", addr))
+        .ok_or(EmuError::InvalidAddress { addr: addr as u16 })
     }
     /// +1 M-C (4 T-C)
-    pub fn write(&mut self, clock: &mut Clock, addr: u16, value: u8) -> Result<(), String> {
-        clock.tick();
+    pub fn write(&mut self, clock: &mut Clock, addr: u16, value: u8) -> Result<(), EmuError> {
+        self.tick(clock);
+        if self.oam_dma.blocks(addr) {
+            return Ok(());
+        }
         let addr = addr as usize;
         let opt_mem_ptr: Option<&mut u8> = match addr {
-            0x0000..=0x3FFF => {
-                return Err(format!("Error: Read-only address {} (ROM bank 0)", addr));
-            }
-            0x4000..=0x7FFF => {
-                return Err(format!(
-                    "Error: Read-only address {} (ROM bank {})",
-                    addr, self.active_rom_bank
-                ));
+            0x0000..=0x7FFF => {
+                self.cartridge.sync(clock.t_cycles);
+                self.cartridge.write_rom(addr as u16, value);
+                return Ok(());
             }
+            // Writes are ignored while the PPU is using VRAM or OAM
+            0x8000..=0x9FFF if !self.ppu.vram_accessible() => return Ok(()),
             0x8000..=0x9FFF => self.vram[self.active_vram].get_mut(addr - 0x8000),
-            0xA000..=0xBFFF => self.eram[self.active_eram].get_mut(addr - 0xA000),
+            0xA000..=0xBFFF => {
+                self.cartridge.sync(clock.t_cycles);
+                self.cartridge.write_ram(addr as u16, value);
+                return Ok(());
+            }
             0xC000..=0xCFFF => self.wram[0].get_mut(addr - 0xC000),
             0xD000..=0xDFFF => self.wram[self.active_wram].get_mut(addr - 0xD000),
             0xE000..=0xEFFF => self.wram[0].get_mut(addr - 0xE000),
             0xF000..=0xFDFF => self.wram[self.active_wram].get_mut(addr - 0xF000),
+            0xFE00..=0xFE9F if !self.ppu.oam_accessible() => return Ok(()),
             0xFE00..=0xFE9F => self.oam.get_mut(addr - 0xFE00),
-            0xFEA0..=0xFEFF => {
-                // https://gbdev.io/pandocs/Memory_Map.html#fea0feff-range
-                return Err(format!("Error: Invalid address {} (Prohibited)", addr));
+            // Prohibited area, writes are ignored
+            // https://gbdev.io/pandocs/Memory_Map.html#fea0feff-range
+            0xFEA0..=0xFEFF => return Ok(()),
+            0xFF00..=0xFF7F => {
+                self.write_io(addr as u16, value);
+                return Ok(());
             }
-            0xFF00..=0xFF7F => self.io.get_mut(addr - 0xFF00),
             0xFF80..=0xFFFE => self.hram.get_mut(addr - 0xFF80),
             0xFFFF => Some(&mut self.ie),
             _ => None,
@@ -97,7 +170,104 @@ impl MemoryMap {
             *mem_ptr = value;
             Ok(())
         } else {
-            Err(format!("Error: Out of bounds or invalid address {}", addr))
+            Err(EmuError::InvalidAddress { addr: addr as u16 })
         }
     }
+
+    pub fn cartridge(&self) -> &dyn Mapper {
+        self.cartridge.as_ref()
+    }
+
+    pub fn cartridge_mut(&mut self) -> &mut dyn Mapper {
+        self.cartridge.as_mut()
+    }
+
+    /// Whether the cartridge RAM or RTC changed since the last call
+    /// the RTC is caught up to `clock` first so the time it counted on its own shows up
+    pub fn take_save_dirty(&mut self, clock: &Clock) -> bool {
+        self.cartridge.sync(clock.t_cycles);
+        self.cartridge.take_dirty()
+    }
+
+    /// The cartridge's real time clock, if it has one
+    pub fn rtc_mut(&mut self) -> Option<&mut Rtc> {
+        self.cartridge.rtc_mut()
+    }
+
+    /// Whether the cartridge's rumble motor is on
+    pub fn rumble(&self) -> bool {
+        self.cartridge.rumble()
+    }
+
+    /// Interrupts that are both requested (IF) and enabled (IE)
+    pub fn pending_interrupts(&self) -> u8 {
+        self.int_flag & self.ie & 0x1F
+    }
+
+    pub fn request_interrupt(&mut self, interrupt: Interrupt) {
+        self.int_flag |= interrupt.mask();
+    }
+
+    pub fn interrupt_requested(&self, interrupt: Interrupt) -> bool {
+        self.int_flag & interrupt.mask() != 0
+    }
+
+    /// https://gbdev.io/pandocs/CGB_Registers.html#ff4d--key1-cgb-mode-only-prepare-speed-switch
+    pub fn speed_switch_armed(&self) -> bool {
+        self.cgb && self.key1 & 0x1 != 0
+    }
+
+    /// KEY1 bit 7, the CPU and timer run at 2x
+    pub fn double_speed(&self) -> bool {
+        self.key1 & 0x80 != 0
+    }
+
+    /// Toggles the current speed bit of KEY1 and disarms the switch
+    pub fn switch_speed(&mut self) {
+        self.key1 = (self.key1 ^ 0x80) & !0x1;
+    }
+
+    /// STOP resets DIV
+    pub fn reset_divider(&mut self) {
+        self.timer.reset_divider();
+    }
+
+    /// The last complete frame, 160x144 0xRRGGBB pixels
+    pub fn framebuffer(&self) -> &[u32] {
+        self.ppu.framebuffer()
+    }
+
+    /// Whether the PPU finished a frame since the last call
+    pub fn take_frame_ready(&mut self) -> bool {
+        self.ppu.take_frame_ready()
+    }
+
+    /// Where the APU writes its samples, clones share the same buffer
+    pub fn audio_output(&self) -> AudioBuffer {
+        self.apu.output()
+    }
+
+    /// Host sample rate of the audio output, in Hz
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.apu.set_sample_rate(sample_rate);
+    }
+
+    /// Stereo frames the host's audio queue still has to play, steers the resampling rate
+    pub fn set_audio_queued(&mut self, frames: usize) {
+        self.apu.set_host_queued(frames);
+    }
+
+    /// Presses or releases a button
+    pub fn set_button(&mut self, button: Button, pressed: bool) {
+        self.joypad.set_button(button, pressed, &mut self.int_flag);
+    }
+
+    /// Whether a selected P1 input line went low since the last call
+    pub fn take_joypad_input(&mut self) -> bool {
+        self.joypad.take_line_fell()
+    }
+
+    pub fn clear_interrupt(&mut self, interrupt: Interrupt) {
+        self.int_flag &= !interrupt.mask();
+    }
 }
@@ -0,0 +1,127 @@
+use crate::{
+    mem::mbc::{Mapper, ROM_BANK_SIZE, ram_index, rom_byte},
+    rom::rom_parser::{NINTENDO, NINTENDO_LOGO_RANGE},
+};
+
+/// Up to 2 MiB of ROM and 32 KiB of RAM
+/// https://gbdev.io/pandocs/MBC1.html
+#[derive(Debug)]
+pub struct Mbc1 {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    /// Set when the RAM changes, cleared by `take_dirty`
+    dirty: bool,
+    ram_enabled: bool,
+    /// 5-bit BANK1 register (0x2000-0x3FFF), never 0
+    bank1: u8,
+    /// 2-bit BANK2 register (0x4000-0x5FFF), upper ROM bank bits or RAM bank
+    bank2: u8,
+    /// Banking mode (0x6000-0x7FFF), when set BANK2 also applies to 0x0000-0x3FFF and RAM
+    mode: bool,
+    /// MBC1M multicarts only wire 4 bits of BANK1 so BANK2 starts at bit 4
+    /// https://gbdev.io/pandocs/MBC1.html#mbc1m-1-mib-multi-game-compilation-carts
+    multicart: bool,
+}
+
+impl Mbc1 {
+    pub fn new(rom: Vec<u8>, ram: Vec<u8>) -> Self {
+        let multicart = is_multicart(&rom);
+        Self {
+            rom,
+            ram,
+            dirty: false,
+            ram_enabled: false,
+            bank1: 1,
+            bank2: 0,
+            mode: false,
+            multicart,
+        }
+    }
+
+    fn bank2_shift(&self) -> u8 {
+        if self.multicart { 4 } else { 5 }
+    }
+
+    /// The bank mapped at 0x0000-0x3FFF
+    fn low_bank(&self) -> usize {
+        if self.mode {
+            (self.bank2 << self.bank2_shift()) as usize
+        } else {
+            0
+        }
+    }
+
+    /// The bank mapped at 0x4000-0x7FFF
+    fn high_bank(&self) -> usize {
+        let bank1 = if self.multicart {
+            self.bank1 & 0xF
+        } else {
+            self.bank1
+        };
+        ((self.bank2 << self.bank2_shift()) | bank1) as usize
+    }
+
+    fn ram_bank(&self) -> usize {
+        if self.mode { self.bank2 as usize } else { 0 }
+    }
+}
+
+impl Mapper for Mbc1 {
+    fn read_rom(&self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x3FFF => rom_byte(&self.rom, self.low_bank(), addr),
+            _ => rom_byte(&self.rom, self.high_bank(), addr),
+        }
+    }
+
+    fn write_rom(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x0000..=0x1FFF => self.ram_enabled = value & 0xF == 0xA,
+            0x2000..=0x3FFF => {
+                // The zero check happens on all 5 bits, so bank 0x20 can't be mapped
+                // at 0x4000 and selects 0x21 instead
+                self.bank1 = (value & 0x1F).max(1);
+            }
+            0x4000..=0x5FFF => self.bank2 = value & 0x3,
+            _ => self.mode = value & 0x1 != 0,
+        }
+    }
+
+    fn read_ram(&self, addr: u16) -> u8 {
+        if !self.ram_enabled {
+            return 0xFF;
+        }
+        ram_index(&self.ram, self.ram_bank(), addr).map_or(0xFF, |i| self.ram[i])
+    }
+
+    fn write_ram(&mut self, addr: u16, value: u8) {
+        if !self.ram_enabled {
+            return;
+        }
+        if let Some(i) = ram_index(&self.ram, self.ram_bank(), addr) {
+            self.dirty |= self.ram[i] != value;
+            self.ram[i] = value;
+        }
+    }
+
+    fn take_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+
+    fn ram(&self) -> &[u8] {
+        &self.ram
+    }
+
+    fn ram_mut(&mut self) -> &mut [u8] {
+        &mut self.ram
+    }
+}
+
+/// MBC1M carts are 1 MiB and every game has its own header, so the Nintendo logo
+/// shows up again at the start of bank 0x10
+fn is_multicart(rom: &[u8]) -> bool {
+    let second = 0x10 * ROM_BANK_SIZE;
+    rom.len() == 0x40 * ROM_BANK_SIZE
+        && rom[second + NINTENDO_LOGO_RANGE.start()..=second + NINTENDO_LOGO_RANGE.end()]
+            == NINTENDO
+}
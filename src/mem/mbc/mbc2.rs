@@ -0,0 +1,79 @@
+use crate::mem::mbc::{Mapper, rom_byte};
+
+/// 512 half-bytes of RAM built into the mapper
+const RAM_SIZE: usize = 0x200;
+
+/// Up to 256 KiB of ROM and 512x4 bits of built-in RAM
+/// https://gbdev.io/pandocs/MBC2.html
+#[derive(Debug)]
+pub struct Mbc2 {
+    rom: Vec<u8>,
+    /// Only the lower nibble of each byte is stored
+    ram: Vec<u8>,
+    /// Set when the RAM changes, cleared by `take_dirty`
+    dirty: bool,
+    ram_enabled: bool,
+    /// 4-bit ROM bank, never 0
+    rom_bank: u8,
+}
+
+impl Mbc2 {
+    pub fn new(rom: Vec<u8>) -> Self {
+        Self {
+            rom,
+            ram: vec![0; RAM_SIZE],
+            dirty: false,
+            ram_enabled: false,
+            rom_bank: 1,
+        }
+    }
+}
+
+impl Mapper for Mbc2 {
+    fn read_rom(&self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x3FFF => rom_byte(&self.rom, 0, addr),
+            _ => rom_byte(&self.rom, self.rom_bank as usize, addr),
+        }
+    }
+
+    /// Both registers live in 0x0000-0x3FFF, address bit 8 picks which one is written
+    fn write_rom(&mut self, addr: u16, value: u8) {
+        if addr > 0x3FFF {
+            return;
+        }
+        if addr & 0x100 == 0 {
+            self.ram_enabled = value & 0xF == 0xA;
+        } else {
+            self.rom_bank = (value & 0xF).max(1);
+        }
+    }
+
+    /// The 512 bytes are echoed through 0xA000-0xBFFF, the upper nibble is open bus
+    fn read_ram(&self, addr: u16) -> u8 {
+        if !self.ram_enabled {
+            return 0xFF;
+        }
+        self.ram[addr as usize % RAM_SIZE] | 0xF0
+    }
+
+    fn write_ram(&mut self, addr: u16, value: u8) {
+        if self.ram_enabled {
+            let i = addr as usize % RAM_SIZE;
+            self.dirty |= self.ram[i] != value & 0xF;
+            self.ram[i] = value & 0xF;
+        }
+    }
+
+    fn take_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+
+    fn ram(&self) -> &[u8] {
+        &self.ram
+    }
+
+    fn ram_mut(&mut self) -> &mut [u8] {
+        &mut self.ram
+    }
+}
@@ -0,0 +1,116 @@
+use crate::mem::mbc::{Mapper, ram_index, rom_byte, rtc::Rtc};
+
+/// Up to 2 MiB of ROM, 32 KiB of RAM and an optional real time clock
+/// https://gbdev.io/pandocs/MBC3.html
+#[derive(Debug)]
+pub struct Mbc3 {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    /// Set when the RAM changes, cleared by `take_dirty`
+    dirty: bool,
+    /// Enables both RAM and the RTC registers
+    ram_enabled: bool,
+    /// 7-bit ROM bank, never 0
+    rom_bank: u8,
+    /// 0x00-0x03 selects a RAM bank, 0x08-0x0C an RTC register
+    ram_select: u8,
+    rtc: Option<Rtc>,
+}
+
+impl Mbc3 {
+    pub fn new(rom: Vec<u8>, ram: Vec<u8>, timer: bool) -> Self {
+        Self {
+            rom,
+            ram,
+            dirty: false,
+            ram_enabled: false,
+            rom_bank: 1,
+            ram_select: 0,
+            rtc: timer.then(Rtc::default),
+        }
+    }
+
+    /// The RTC register mapped at 0xA000-0xBFFF, if any
+    fn rtc_select(&self) -> Option<u8> {
+        match self.ram_select {
+            0x08..=0x0C if self.rtc.is_some() => Some(self.ram_select),
+            _ => None,
+        }
+    }
+}
+
+impl Mapper for Mbc3 {
+    fn read_rom(&self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x3FFF => rom_byte(&self.rom, 0, addr),
+            _ => rom_byte(&self.rom, self.rom_bank as usize, addr),
+        }
+    }
+
+    fn write_rom(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x0000..=0x1FFF => self.ram_enabled = value & 0xF == 0xA,
+            0x2000..=0x3FFF => self.rom_bank = (value & 0x7F).max(1),
+            0x4000..=0x5FFF => self.ram_select = value & 0xF,
+            _ => {
+                if let Some(rtc) = &mut self.rtc {
+                    rtc.write_latch(value);
+                }
+            }
+        }
+    }
+
+    fn read_ram(&self, addr: u16) -> u8 {
+        if !self.ram_enabled {
+            return 0xFF;
+        }
+        match (self.rtc_select(), &self.rtc) {
+            (Some(select), Some(rtc)) => rtc.read(select),
+            _ if self.ram_select <= 0x3 => {
+                ram_index(&self.ram, self.ram_select as usize, addr).map_or(0xFF, |i| self.ram[i])
+            }
+            _ => 0xFF,
+        }
+    }
+
+    fn write_ram(&mut self, addr: u16, value: u8) {
+        if !self.ram_enabled {
+            return;
+        }
+        if let (Some(select), Some(rtc)) = (self.rtc_select(), &mut self.rtc) {
+            rtc.write(select, value);
+        } else if self.ram_select <= 0x3
+            && let Some(i) = ram_index(&self.ram, self.ram_select as usize, addr)
+        {
+            self.dirty |= self.ram[i] != value;
+            self.ram[i] = value;
+        }
+    }
+
+    fn take_dirty(&mut self) -> bool {
+        let rtc_changed = self.rtc.as_mut().is_some_and(Rtc::take_changed);
+        std::mem::take(&mut self.dirty) | rtc_changed
+    }
+
+    fn ram(&self) -> &[u8] {
+        &self.ram
+    }
+
+    fn ram_mut(&mut self) -> &mut [u8] {
+        &mut self.ram
+    }
+
+    fn sync(&mut self, t_cycles: u64) {
+        if let Some(rtc) = &mut self.rtc {
+            rtc.sync(t_cycles);
+        }
+    }
+
+    fn rtc(&self) -> Option<&Rtc> {
+        self.rtc.as_ref()
+    }
+
+    fn rtc_mut(&mut self) -> Option<&mut Rtc> {
+        self.rtc.as_mut()
+    }
+}
@@ -0,0 +1,93 @@
+use crate::mem::mbc::{Mapper, ram_index, rom_byte};
+
+/// Up to 8 MiB of ROM and 128 KiB of RAM, the mapper of most CGB games
+/// https://gbdev.io/pandocs/MBC5.html
+#[derive(Debug)]
+pub struct Mbc5 {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    /// Set when the RAM changes, cleared by `take_dirty`
+    dirty: bool,
+    ram_enabled: bool,
+    /// 9-bit ROM bank, unlike MBC1 and MBC3 bank 0 can be mapped at 0x4000
+    rom_bank: u16,
+    ram_bank: u8,
+    /// Rumble carts wire bit 3 of the RAM bank register to the motor
+    has_rumble: bool,
+    rumble: bool,
+}
+
+impl Mbc5 {
+    pub fn new(rom: Vec<u8>, ram: Vec<u8>, has_rumble: bool) -> Self {
+        Self {
+            rom,
+            ram,
+            dirty: false,
+            ram_enabled: false,
+            rom_bank: 1,
+            ram_bank: 0,
+            has_rumble,
+            rumble: false,
+        }
+    }
+}
+
+impl Mapper for Mbc5 {
+    fn read_rom(&self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x3FFF => rom_byte(&self.rom, 0, addr),
+            _ => rom_byte(&self.rom, self.rom_bank as usize, addr),
+        }
+    }
+
+    fn write_rom(&mut self, addr: u16, value: u8) {
+        match addr {
+            // MBC5 compares the whole byte
+            0x0000..=0x1FFF => self.ram_enabled = value == 0x0A,
+            0x2000..=0x2FFF => self.rom_bank = (self.rom_bank & 0x100) | value as u16,
+            0x3000..=0x3FFF => self.rom_bank = (self.rom_bank & 0xFF) | ((value as u16 & 0x1) << 8),
+            0x4000..=0x5FFF => {
+                if self.has_rumble {
+                    self.rumble = value & 0x8 != 0;
+                    self.ram_bank = value & 0x7;
+                } else {
+                    self.ram_bank = value & 0xF;
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn read_ram(&self, addr: u16) -> u8 {
+        if !self.ram_enabled {
+            return 0xFF;
+        }
+        ram_index(&self.ram, self.ram_bank as usize, addr).map_or(0xFF, |i| self.ram[i])
+    }
+
+    fn write_ram(&mut self, addr: u16, value: u8) {
+        if !self.ram_enabled {
+            return;
+        }
+        if let Some(i) = ram_index(&self.ram, self.ram_bank as usize, addr) {
+            self.dirty |= self.ram[i] != value;
+            self.ram[i] = value;
+        }
+    }
+
+    fn take_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+
+    fn ram(&self) -> &[u8] {
+        &self.ram
+    }
+
+    fn ram_mut(&mut self) -> &mut [u8] {
+        &mut self.ram
+    }
+
+    fn rumble(&self) -> bool {
+        self.rumble
+    }
+}
@@ -0,0 +1,136 @@
+pub mod mbc1;
+pub mod mbc2;
+pub mod mbc3;
+pub mod mbc5;
+pub mod rtc;
+
+use std::fmt;
+
+use crate::{
+    mem::mbc::rtc::Rtc,
+    rom::{cartridge_type::CartridgeType, rom_info::ROMInfo},
+};
+
+pub const ROM_BANK_SIZE: usize = 0x4000;
+pub const RAM_BANK_SIZE: usize = 0x2000;
+
+/// A memory bank controller, owns the cartridge ROM and external RAM
+/// Writes to 0x0000-0x7FFF never reach the ROM, they set the mapper's registers instead
+/// https://gbdev.io/pandocs/MBCs.html
+pub trait Mapper: fmt::Debug {
+    /// 0x0000-0x7FFF, reads past the end of the ROM file see an open bus (0xFF)
+    fn read_rom(&self, addr: u16) -> u8;
+    /// 0x0000-0x7FFF
+    fn write_rom(&mut self, addr: u16, value: u8);
+    /// 0xA000-0xBFFF, disabled or missing RAM reads as 0xFF
+    fn read_ram(&self, addr: u16) -> u8;
+    /// 0xA000-0xBFFF
+    fn write_ram(&mut self, addr: u16, value: u8);
+    /// The whole external RAM, in bank order
+    fn ram(&self) -> &[u8];
+    fn ram_mut(&mut self) -> &mut [u8];
+    /// Whether the RAM or the RTC changed since the last call, the save file is out of date
+    fn take_dirty(&mut self) -> bool;
+    /// Catches up anything counting on its own (the RTC) to `t_cycles`
+    /// called before every write to the cartridge, reads only see latched state
+    fn sync(&mut self, _t_cycles: u64) {}
+    /// Whether the rumble motor is on
+    fn rumble(&self) -> bool {
+        false
+    }
+    fn rtc(&self) -> Option<&Rtc> {
+        None
+    }
+    fn rtc_mut(&mut self) -> Option<&mut Rtc> {
+        None
+    }
+}
+
+/// Whether RedGB implements the mapper of a cartridge type
+pub fn is_supported(cartridge_type: &CartridgeType) -> bool {
+    matches!(
+        cartridge_type,
+        CartridgeType::RomOnly { .. }
+            | CartridgeType::Mbc1 { .. }
+            | CartridgeType::Mbc2 { .. }
+            | CartridgeType::Mbc3 { .. }
+            | CartridgeType::Mbc5 { .. }
+    )
+}
+
+/// Builds the mapper for the cartridge type in the header
+/// Unsupported mappers fall back to a plain 32 KiB ROM, see `is_supported`
+pub fn from_info(rom: Vec<u8>, info: &ROMInfo) -> Box<dyn Mapper> {
+    let ram = vec![0; info.mem_banks as usize * RAM_BANK_SIZE];
+    match info.cartridge_type {
+        CartridgeType::Mbc1 { .. } => Box::new(mbc1::Mbc1::new(rom, ram)),
+        CartridgeType::Mbc2 { .. } => Box::new(mbc2::Mbc2::new(rom)),
+        CartridgeType::Mbc3 { timer, .. } => Box::new(mbc3::Mbc3::new(rom, ram, timer)),
+        CartridgeType::Mbc5 { rumble, .. } => Box::new(mbc5::Mbc5::new(rom, ram, rumble)),
+        _ => Box::new(RomOnly {
+            rom,
+            ram,
+            dirty: false,
+        }),
+    }
+}
+
+/// Reads from `bank` of the ROM, the bank number wraps around the ROM size like the
+/// unconnected bank lines on a real cartridge, past the end of a short bank reads 0xFF
+pub fn rom_byte(rom: &[u8], bank: usize, addr: u16) -> u8 {
+    let banks = rom.len().div_ceil(ROM_BANK_SIZE).max(1);
+    rom.get((bank % banks) * ROM_BANK_SIZE + (addr as usize & 0x3FFF))
+        .copied()
+        .unwrap_or(0xFF)
+}
+
+/// Index of `addr` in `bank` of the external RAM, None without RAM
+pub fn ram_index(ram: &[u8], bank: usize, addr: u16) -> Option<usize> {
+    if ram.is_empty() {
+        return None;
+    }
+    // Carts with 2 KiB of RAM mirror it across the whole bank
+    let bank_size = ram.len().min(RAM_BANK_SIZE);
+    let banks = ram.len() / bank_size;
+    Some((bank % banks) * bank_size + (addr as usize & 0x1FFF) % bank_size)
+}
+
+/// 32 KiB of ROM and optionally up to 8 KiB of RAM without any banking
+/// https://gbdev.io/pandocs/nombc.html
+#[derive(Debug)]
+pub struct RomOnly {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    dirty: bool,
+}
+
+impl Mapper for RomOnly {
+    fn read_rom(&self, addr: u16) -> u8 {
+        self.rom.get(addr as usize).copied().unwrap_or(0xFF)
+    }
+
+    fn write_rom(&mut self, _addr: u16, _value: u8) {}
+
+    fn ram(&self) -> &[u8] {
+        &self.ram
+    }
+
+    fn ram_mut(&mut self) -> &mut [u8] {
+        &mut self.ram
+    }
+
+    fn read_ram(&self, addr: u16) -> u8 {
+        ram_index(&self.ram, 0, addr).map_or(0xFF, |i| self.ram[i])
+    }
+
+    fn write_ram(&mut self, addr: u16, value: u8) {
+        if let Some(i) = ram_index(&self.ram, 0, addr) {
+            self.dirty |= self.ram[i] != value;
+            self.ram[i] = value;
+        }
+    }
+
+    fn take_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+}
@@ -0,0 +1,267 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// T-cycles per RTC second, the RTC has its own 32768 Hz crystal
+const CYCLES_PER_SECOND: u64 = 4_194_304;
+/// Size of the RTC footer appended to save files
+pub const RTC_SAVE_SIZE: usize = 48;
+
+const HALT: u8 = 0x40;
+const DAY_CARRY: u8 = 0x80;
+
+/// The RTC counter registers, in register select order (0x08-0x0C)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RtcRegisters {
+    pub seconds: u8,
+    pub minutes: u8,
+    pub hours: u8,
+    /// Lower 8 bits of the day counter
+    pub days_low: u8,
+    /// Bit 0: day counter bit 8, bit 6: halt, bit 7: day counter carry
+    pub days_high: u8,
+}
+
+impl RtcRegisters {
+    fn get(&self, select: u8) -> u8 {
+        match select {
+            0x08 => self.seconds & 0x3F,
+            0x09 => self.minutes & 0x3F,
+            0x0A => self.hours & 0x1F,
+            0x0B => self.days_low,
+            _ => self.days_high & 0xC1,
+        }
+    }
+
+    fn set(&mut self, select: u8, value: u8) {
+        match select {
+            0x08 => self.seconds = value & 0x3F,
+            0x09 => self.minutes = value & 0x3F,
+            0x0A => self.hours = value & 0x1F,
+            0x0B => self.days_low = value,
+            _ => self.days_high = value & 0xC1,
+        }
+    }
+
+    fn days(&self) -> u16 {
+        ((self.days_high as u16 & 0x1) << 8) | self.days_low as u16
+    }
+
+    fn set_days(&mut self, days: u16) {
+        self.days_low = days as u8;
+        self.days_high = (self.days_high & !0x1) | ((days >> 8) as u8 & 0x1);
+    }
+
+    /// Counts one second, out of range values keep counting up to their bit width
+    /// and wrap to 0 without carrying
+    fn tick_second(&mut self) {
+        let (seconds, carry) = count(self.seconds, 59, 0x3F);
+        self.seconds = seconds;
+        if !carry {
+            return;
+        }
+        let (minutes, carry) = count(self.minutes, 59, 0x3F);
+        self.minutes = minutes;
+        if !carry {
+            return;
+        }
+        let (hours, carry) = count(self.hours, 23, 0x1F);
+        self.hours = hours;
+        if !carry {
+            return;
+        }
+        if self.days() == 0x1FF {
+            self.set_days(0);
+            self.days_high |= DAY_CARRY;
+        } else {
+            self.set_days(self.days() + 1);
+        }
+    }
+
+    fn advance(&mut self, mut seconds: u64) {
+        // Step through out of range values one second at a time until they're valid again
+        while seconds > 0 && (self.seconds > 59 || self.minutes > 59 || self.hours > 23) {
+            self.tick_second();
+            seconds -= 1;
+        }
+        if seconds == 0 {
+            return;
+        }
+        let total = seconds
+            + self.seconds as u64
+            + 60 * (self.minutes as u64 + 60 * (self.hours as u64 + 24 * self.days() as u64));
+        self.seconds = (total % 60) as u8;
+        self.minutes = (total / 60 % 60) as u8;
+        self.hours = (total / 3600 % 24) as u8;
+        let days = total / 86400;
+        if days > 0x1FF {
+            self.days_high |= DAY_CARRY;
+        }
+        self.set_days((days % 0x200) as u16);
+    }
+}
+
+/// Returns (value + 1, carry) for a counter that rolls over after `max`
+fn count(value: u8, max: u8, mask: u8) -> (u8, bool) {
+    if value == max {
+        (0, true)
+    } else {
+        ((value + 1) & mask, false)
+    }
+}
+
+/// MBC3 real time clock
+/// https://gbdev.io/pandocs/MBC3.html#the-clock-counter-registers
+#[derive(Debug)]
+pub struct Rtc {
+    registers: RtcRegisters,
+    latched: RtcRegisters,
+    /// Last value written to the latch register, 0x00 then 0x01 latches
+    latch_write: u8,
+    /// T-cycles into the current second
+    sub_second: u64,
+    /// Clock T-cycles at the last sync
+    last_sync: u64,
+    /// Counts host time instead of emulated cycles, so the clock keeps running
+    /// while the emulator is closed or paused
+    wall_clock: bool,
+    last_wall_sync: SystemTime,
+    /// Set when the counters or the latched registers change, cleared by `take_changed`
+    changed: bool,
+}
+
+impl Default for Rtc {
+    fn default() -> Self {
+        Self {
+            registers: RtcRegisters::default(),
+            latched: RtcRegisters::default(),
+            latch_write: 0xFF,
+            sub_second: 0,
+            last_sync: 0,
+            wall_clock: false,
+            last_wall_sync: SystemTime::now(),
+            changed: false,
+        }
+    }
+}
+
+impl Rtc {
+    pub fn set_wall_clock(&mut self, wall_clock: bool) {
+        self.wall_clock = wall_clock;
+        self.last_wall_sync = SystemTime::now();
+    }
+
+    pub fn halted(&self) -> bool {
+        self.registers.days_high & HALT != 0
+    }
+
+    /// Catches the clock up to `t_cycles` (or the host time)
+    pub fn sync(&mut self, t_cycles: u64) {
+        let elapsed = t_cycles.saturating_sub(self.last_sync);
+        self.last_sync = t_cycles;
+        if self.wall_clock {
+            let now = SystemTime::now();
+            let elapsed = now
+                .duration_since(self.last_wall_sync)
+                .unwrap_or_default()
+                .as_nanos() as u64;
+            // Keep the fraction of a second for the next sync
+            let whole = elapsed / 1_000_000_000;
+            self.last_wall_sync += std::time::Duration::from_secs(whole);
+            if !self.halted() && whole > 0 {
+                self.registers.advance(whole);
+                self.changed = true;
+            }
+            return;
+        }
+        if self.halted() {
+            return;
+        }
+        self.sub_second += elapsed;
+        if self.sub_second >= CYCLES_PER_SECOND {
+            self.registers.advance(self.sub_second / CYCLES_PER_SECOND);
+            self.sub_second %= CYCLES_PER_SECOND;
+            self.changed = true;
+        }
+    }
+
+    /// Writing 0x00 then 0x01 copies the counters into the readable registers
+    pub fn write_latch(&mut self, value: u8) {
+        if self.latch_write == 0x00 && value == 0x01 {
+            self.changed |= self.latched != self.registers;
+            self.latched = self.registers;
+        }
+        self.latch_write = value;
+    }
+
+    /// Reads the latched copy of register `select` (0x08-0x0C)
+    pub fn read(&self, select: u8) -> u8 {
+        self.latched.get(select)
+    }
+
+    /// Writes register `select` (0x08-0x0C)
+    pub fn write(&mut self, select: u8, value: u8) {
+        // Writing the seconds resets the sub-second divider
+        if select == 0x08 {
+            self.sub_second = 0;
+        }
+        self.registers.set(select, value);
+        self.latched.set(select, value);
+        self.changed = true;
+    }
+
+    /// Whether the clock changed since the last call
+    pub fn take_changed(&mut self) -> bool {
+        std::mem::take(&mut self.changed)
+    }
+
+    pub fn registers(&self) -> RtcRegisters {
+        self.registers
+    }
+
+    /// The footer other emulators (BGB, VBA-M, SameBoy, mGBA) append to the save file:
+    /// current and latched registers as 32-bit little endian values, then a 64-bit UNIX timestamp
+    pub fn save(&self) -> [u8; RTC_SAVE_SIZE] {
+        let mut out = [0; RTC_SAVE_SIZE];
+        let values = [self.registers, self.latched]
+            .into_iter()
+            .flat_map(|r| [r.seconds, r.minutes, r.hours, r.days_low, r.days_high]);
+        for (i, value) in values.enumerate() {
+            out[i * 4..i * 4 + 4].copy_from_slice(&(value as u32).to_le_bytes());
+        }
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        out[40..48].copy_from_slice(&timestamp.to_le_bytes());
+        out
+    }
+
+    /// Restores a footer written by `save`, the 44 byte variant with a 32-bit timestamp is
+    /// accepted too. With the wall clock enabled the time spent closed is added back.
+    /// Returns false if the footer is malformed
+    pub fn load(&mut self, footer: &[u8]) -> bool {
+        let timestamp = match footer.len() {
+            44 => u32::from_le_bytes(footer[40..44].try_into().unwrap()) as u64,
+            48 => u64::from_le_bytes(footer[40..48].try_into().unwrap()),
+            _ => return false,
+        };
+        let value = |i: usize| footer[i * 4];
+        let registers = |offset: usize| RtcRegisters {
+            seconds: value(offset),
+            minutes: value(offset + 1),
+            hours: value(offset + 2),
+            days_low: value(offset + 3),
+            days_high: value(offset + 4),
+        };
+        self.registers = registers(0);
+        self.latched = registers(5);
+        if self.wall_clock && !self.halted() {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            self.registers.advance(now.saturating_sub(timestamp));
+            self.changed = true;
+        }
+        true
+    }
+}
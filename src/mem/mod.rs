@@ -1 +1,5 @@
+mod dma;
+mod io;
 pub mod map;
+pub mod mbc;
+pub mod save;
@@ -0,0 +1,72 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{error::EmuError, mem::map::MemoryMap};
+
+/// Battery backed cartridge RAM on disk
+/// The layout is the one BGB, VBA-M, SameBoy and mGBA use: the raw RAM in bank order,
+/// followed by the 48 byte RTC footer for carts with a clock
+#[derive(Debug)]
+pub struct SaveFile {
+    path: PathBuf,
+}
+
+impl SaveFile {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// `game.gb` saves to `game.sav` in the same directory
+    pub fn for_rom(rom_path: &Path) -> Self {
+        Self::new(rom_path.with_extension("sav"))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Restores the RAM and RTC from disk, a missing file leaves them untouched
+    pub fn load(&self, memory: &mut MemoryMap) -> Result<(), EmuError> {
+        let data = match fs::read(&self.path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(self.error(e)),
+        };
+        let cartridge = memory.cartridge_mut();
+        let ram = cartridge.ram_mut();
+        let len = ram.len().min(data.len());
+        ram[..len].copy_from_slice(&data[..len]);
+        let footer = &data[len..];
+        if let Some(rtc) = cartridge.rtc_mut()
+            && !footer.is_empty()
+            && !rtc.load(footer)
+        {
+            return Err(EmuError::SaveFile(format!(
+                "{}: unknown RTC footer of {} bytes",
+                self.path.display(),
+                footer.len()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Writes the RAM and RTC to disk
+    /// The data goes to a temporary file first so a crash can't leave a half written save
+    pub fn flush(&self, memory: &MemoryMap) -> Result<(), EmuError> {
+        let cartridge = memory.cartridge();
+        let mut data = cartridge.ram().to_vec();
+        if let Some(rtc) = cartridge.rtc() {
+            data.extend_from_slice(&rtc.save());
+        }
+        let tmp = self.path.with_extension("sav.tmp");
+        fs::write(&tmp, &data)
+            .and_then(|()| fs::rename(&tmp, &self.path))
+            .map_err(|e| self.error(e))
+    }
+
+    fn error(&self, e: io::Error) -> EmuError {
+        EmuError::SaveFile(format!("{}: {e}", self.path.display()))
+    }
+}
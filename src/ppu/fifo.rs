@@ -0,0 +1,235 @@
+use std::collections::VecDeque;
+
+use crate::ppu::{
+    OBJ_ENABLE, Ppu, SCREEN_WIDTH, VideoMemory, WINDOW_ENABLE,
+    scanline::{BgAttributes, BgPixel, ObjPixel, Sprite, tile_pixel},
+};
+
+/// Dots of the discarded tile fetch at the start of mode 3
+const STARTUP_DOTS: u8 = 6;
+/// Dots it takes to fetch a sprite's tile row once the BG fetch is done
+const SPRITE_FETCH_DOTS: u8 = 6;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum FetchStep {
+    #[default]
+    Tile,
+    DataLow,
+    DataHigh,
+    Push,
+}
+
+/// The background/window fetcher, each step but Push takes 2 dots
+/// https://gbdev.io/pandocs/pixel_fifo.html#get-tile
+#[derive(Debug, Default)]
+struct Fetcher {
+    step: FetchStep,
+    /// Set on the first dot of a 2 dot step
+    half: bool,
+    /// Tile column, relative to the start of the line or the window
+    x: u8,
+    /// Pixel row within the map
+    y: u8,
+    tile: u8,
+    attributes: BgAttributes,
+    low: u8,
+    high: u8,
+}
+
+/// State of mode 3 when running the pixel FIFO renderer
+/// https://gbdev.io/pandocs/pixel_fifo.html
+#[derive(Debug, Default)]
+pub(super) struct Pipeline {
+    bg_fifo: VecDeque<BgPixel>,
+    obj_fifo: VecDeque<ObjPixel>,
+    fetcher: Fetcher,
+    /// Screen x of the next pixel
+    lx: u8,
+    /// Pixels still to drop for the SCX fine scroll
+    discard: u8,
+    startup: u8,
+    in_window: bool,
+    /// Sprites found by the OAM scan and whether they were fetched already
+    sprites: Vec<(Sprite, bool)>,
+    /// Index in `sprites` and remaining dots of the sprite fetch in progress
+    sprite_fetch: Option<(usize, u8)>,
+}
+
+impl Pipeline {
+    pub fn start_line(&mut self, sprites: Vec<Sprite>, scx: u8) {
+        *self = Self {
+            discard: scx & 0x7,
+            startup: STARTUP_DOTS,
+            sprites: sprites.into_iter().map(|sprite| (sprite, false)).collect(),
+            ..Self::default()
+        };
+    }
+}
+
+impl Ppu {
+    /// Index in `sprites` of the next sprite to fetch at the current X, if any is due
+    /// outside of CGB mode the lowest X goes first so it wins where sprites overlap
+    fn next_sprite(&self) -> Option<usize> {
+        let lx = self.pipeline.lx as u16;
+        let mut due = self
+            .pipeline
+            .sprites
+            .iter()
+            .enumerate()
+            .filter(|(_, (sprite, fetched))| !fetched && sprite.x as u16 <= lx + 8);
+        if self.oam_priority() {
+            due.next().map(|(index, _)| index)
+        } else {
+            due.min_by_key(|(_, (sprite, _))| (sprite.x, sprite.index))
+                .map(|(index, _)| index)
+        }
+    }
+
+    /// Runs mode 3 for 1 dot, returns true once the last pixel of the line is out
+    pub(super) fn fifo_dot(&mut self, video: &VideoMemory) -> bool {
+        if self.pipeline.startup > 0 {
+            self.pipeline.startup -= 1;
+            return false;
+        }
+
+        if self.pipeline.sprite_fetch.is_none()
+            && self.lcdc & OBJ_ENABLE != 0
+            && let Some(index) = self.next_sprite()
+        {
+            // The BG fetch in progress finishes first, up to 5 dots depending on alignment
+            // https://gbdev.io/pandocs/Rendering.html#obj-penalty-algorithm
+            let wait = 5u8.saturating_sub(self.pipeline.lx.wrapping_add(self.scx) % 8);
+            self.pipeline.sprite_fetch = Some((index, wait + SPRITE_FETCH_DOTS));
+        }
+        // The pixel output is paused during a sprite fetch
+        if let Some((index, dots)) = self.pipeline.sprite_fetch {
+            if dots > SPRITE_FETCH_DOTS {
+                self.fetcher_dot(video);
+            }
+            if dots > 1 {
+                self.pipeline.sprite_fetch = Some((index, dots - 1));
+            } else {
+                self.pipeline.sprite_fetch = None;
+                self.merge_sprite(video, index);
+            }
+            return false;
+        }
+
+        if !self.pipeline.in_window
+            && self.pipeline.discard == 0
+            && self.lcdc & WINDOW_ENABLE != 0
+            && self.wy_triggered
+            && self.wx <= 166
+            && self.pipeline.lx as u16 + 7 >= self.wx as u16
+        {
+            // The fetcher restarts on the window, its pixels replace the BG
+            self.pipeline.in_window = true;
+            self.pipeline.bg_fifo.clear();
+            self.pipeline.fetcher = Fetcher::default();
+        }
+
+        self.fetcher_dot(video);
+        self.shift_pixel();
+        if self.pipeline.lx as usize == SCREEN_WIDTH {
+            if self.pipeline.in_window {
+                self.window_line += 1;
+            }
+            return true;
+        }
+        false
+    }
+
+    /// Advances the BG fetcher, registers are read when each step happens
+    fn fetcher_dot(&mut self, video: &VideoMemory) {
+        let step = self.pipeline.fetcher.step;
+        if step != FetchStep::Push {
+            let fetcher = &mut self.pipeline.fetcher;
+            fetcher.half = !fetcher.half;
+            if fetcher.half {
+                return;
+            }
+        }
+        let fetcher = &self.pipeline.fetcher;
+        let (bank, row_addr) = self.bg_row_addr(fetcher.tile, fetcher.attributes, fetcher.y);
+        match step {
+            FetchStep::Tile => {
+                let in_window = self.pipeline.in_window;
+                let (x, y) = if in_window {
+                    (fetcher.x, self.window_line)
+                } else {
+                    (
+                        (self.scx >> 3).wrapping_add(fetcher.x),
+                        self.ly.wrapping_add(self.scy),
+                    )
+                };
+                let (tile, attributes) = self.bg_tile(video, self.bg_map(in_window), x, y);
+                let fetcher = &mut self.pipeline.fetcher;
+                fetcher.y = y;
+                fetcher.tile = tile;
+                fetcher.attributes = attributes;
+                fetcher.step = FetchStep::DataLow;
+            }
+            FetchStep::DataLow => {
+                self.pipeline.fetcher.low = video.vram(bank, row_addr);
+                self.pipeline.fetcher.step = FetchStep::DataHigh;
+            }
+            FetchStep::DataHigh => {
+                self.pipeline.fetcher.high = video.vram(bank, row_addr + 1);
+                self.pipeline.fetcher.step = FetchStep::Push;
+            }
+            FetchStep::Push => {
+                // Only pushes into an empty FIFO, otherwise retries on the next dot
+                let pipeline = &mut self.pipeline;
+                if pipeline.bg_fifo.is_empty() {
+                    let fetcher = &mut pipeline.fetcher;
+                    let attributes = fetcher.attributes;
+                    let (low, high) = (fetcher.low, fetcher.high);
+                    pipeline.bg_fifo.extend((0..8).map(|x| {
+                        let x = if attributes.x_flip() { 7 - x } else { x };
+                        BgPixel {
+                            color: tile_pixel(low, high, x),
+                            palette: attributes.palette(),
+                            priority: attributes.priority(),
+                        }
+                    }));
+                    fetcher.x = fetcher.x.wrapping_add(1);
+                    fetcher.step = FetchStep::Tile;
+                }
+            }
+        }
+    }
+
+    /// Mixes the sprite's row into the sprite FIFO
+    fn merge_sprite(&mut self, video: &VideoMemory, index: usize) {
+        let (sprite, fetched) = &mut self.pipeline.sprites[index];
+        *fetched = true;
+        let sprite = *sprite;
+        let (low, high) = self.sprite_row(video, &sprite);
+        // Pixels left of the current position are cut off
+        let skip = (self.pipeline.lx as u16 + 8).saturating_sub(sprite.x as u16) as u8;
+        for col in skip..8 {
+            let pixel = self.sprite_pixel(&sprite, low, high, col);
+            let pos = (col - skip) as usize;
+            if pos >= self.pipeline.obj_fifo.len() {
+                self.pipeline.obj_fifo.push_back(pixel);
+            } else if self.obj_wins(&pixel, &self.pipeline.obj_fifo[pos]) {
+                self.pipeline.obj_fifo[pos] = pixel;
+            }
+        }
+    }
+
+    /// Pops a pixel from both FIFOs and draws it, palettes are applied at this point
+    fn shift_pixel(&mut self) {
+        let Some(bg) = self.pipeline.bg_fifo.pop_front() else {
+            return;
+        };
+        if self.pipeline.discard > 0 {
+            self.pipeline.discard -= 1;
+            return;
+        }
+        let obj = self.pipeline.obj_fifo.pop_front().unwrap_or_default();
+        let x = self.pipeline.lx as usize;
+        self.framebuffer[self.ly as usize * SCREEN_WIDTH + x] = self.mix(bg, obj);
+        self.pipeline.lx += 1;
+    }
+}
@@ -0,0 +1,357 @@
+mod fifo;
+mod palette;
+mod scanline;
+
+use crate::{
+    cpu::{interrupts::Interrupt, reg_file::Modes},
+    ppu::{fifo::Pipeline, palette::PaletteRam},
+};
+
+pub const SCREEN_WIDTH: usize = 160;
+pub const SCREEN_HEIGHT: usize = 144;
+
+const DOTS_PER_LINE: u16 = 456;
+const LINES_PER_FRAME: u8 = 154;
+const OAM_SCAN_DOTS: u16 = 80;
+/// Shortest mode 3, without any fine scroll or sprite penalties
+const DRAWING_DOTS: u16 = 172;
+
+const LCD_ENABLE: u8 = 0x80;
+const WINDOW_MAP: u8 = 0x40;
+const WINDOW_ENABLE: u8 = 0x20;
+const TILE_DATA: u8 = 0x10;
+const BG_MAP: u8 = 0x08;
+const OBJ_SIZE: u8 = 0x04;
+const OBJ_ENABLE: u8 = 0x02;
+const BG_ENABLE: u8 = 0x01;
+
+/// DMG shades as 0xRRGGBB, from lightest to darkest
+const SHADES: [u32; 4] = [0xFFFFFF, 0xAAAAAA, 0x555555, 0x000000];
+
+/// The value of the STAT mode bits
+/// https://gbdev.io/pandocs/Rendering.html#ppu-modes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    HBlank,
+    VBlank,
+    OamScan,
+    Drawing,
+}
+
+impl Mode {
+    fn bits(&self) -> u8 {
+        match self {
+            Mode::HBlank => 0,
+            Mode::VBlank => 1,
+            Mode::OamScan => 2,
+            Mode::Drawing => 3,
+        }
+    }
+
+    /// The STAT bit that selects this mode as an interrupt source
+    fn stat_source(&self) -> u8 {
+        match self {
+            Mode::HBlank => 0x08,
+            Mode::VBlank => 0x10,
+            Mode::OamScan => 0x20,
+            Mode::Drawing => 0x00,
+        }
+    }
+}
+
+/// How mode 3 is emulated, picked when the PPU is built
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Renderer {
+    /// Draws a whole line at the end of a fixed length mode 3
+    #[default]
+    Scanline,
+    /// Runs the pixel fetchers and FIFOs dot by dot, mode 3 length varies and register
+    /// writes take effect mid-line
+    Fifo,
+}
+
+/// The PPU's view of VRAM and OAM, both are owned by `MemoryMap`
+pub struct VideoMemory<'a> {
+    pub vram: &'a [Vec<u8>],
+    pub oam: &'a [u8],
+}
+
+impl VideoMemory<'_> {
+    fn vram(&self, bank: usize, addr: u16) -> u8 {
+        self.vram[bank][(addr - 0x8000) as usize]
+    }
+}
+
+/// Picture processing unit, draws into a 160x144 framebuffer
+/// https://gbdev.io/pandocs/Rendering.html
+#[derive(Debug)]
+pub struct Ppu {
+    renderer: Renderer,
+    pipeline: Pipeline,
+    /// Running in CGB mode, enables BG attributes, color palettes and OAM order priority
+    cgb: bool,
+    lcdc: u8,
+    /// Bits 0-2 (mode and LY=LYC) are driven by the PPU, only 3-6 are writable
+    stat: u8,
+    scy: u8,
+    scx: u8,
+    ly: u8,
+    lyc: u8,
+    bgp: u8,
+    obp0: u8,
+    obp1: u8,
+    wy: u8,
+    wx: u8,
+    bg_palettes: PaletteRam,
+    obj_palettes: PaletteRam,
+    /// Object priority mode, bit 0 set orders sprites by X like the DMG
+    opri: u8,
+    mode: Mode,
+    /// Dot within the current line, 0-455
+    dot: u16,
+    /// Dot at which mode 3 ends on the current line, scanline renderer only
+    drawing_end: u16,
+    /// Line of the window to draw next, only advances on lines where the window is visible
+    window_line: u8,
+    /// WY matched LY at some point this frame
+    wy_triggered: bool,
+    /// STAT interrupts fire on the rising edge of the OR of every enabled source
+    stat_line: bool,
+    framebuffer: Vec<u32>,
+    frame_ready: bool,
+    /// Set when mode 0 starts on a visible line, drives HBlank HDMA
+    hblank_started: bool,
+}
+
+impl Default for Ppu {
+    fn default() -> Self {
+        Self::new(Modes::DMG, Renderer::default())
+    }
+}
+
+impl Ppu {
+    /// Starts at the top of a frame with the registers the boot ROM leaves behind
+    /// https://gbdev.io/pandocs/Power_Up_Sequence.html#hardware-registers
+    pub fn new(mode: Modes, renderer: Renderer) -> Self {
+        Self {
+            renderer,
+            pipeline: Pipeline::default(),
+            cgb: mode == Modes::CGB,
+            lcdc: 0x91,
+            stat: 0,
+            scy: 0,
+            scx: 0,
+            ly: 0,
+            lyc: 0,
+            bgp: 0xFC,
+            obp0: 0xFF,
+            obp1: 0xFF,
+            wy: 0,
+            wx: 0,
+            bg_palettes: PaletteRam::default(),
+            obj_palettes: PaletteRam::default(),
+            opri: 0,
+            mode: Mode::OamScan,
+            dot: 0,
+            drawing_end: OAM_SCAN_DOTS + DRAWING_DOTS,
+            window_line: 0,
+            wy_triggered: true,
+            stat_line: false,
+            framebuffer: vec![SHADES[0]; SCREEN_WIDTH * SCREEN_HEIGHT],
+            frame_ready: false,
+            hblank_started: false,
+        }
+    }
+
+    fn lcd_enabled(&self) -> bool {
+        self.lcdc & LCD_ENABLE != 0
+    }
+
+    /// The CPU can't access VRAM while it's being read for drawing
+    pub fn vram_accessible(&self) -> bool {
+        !self.lcd_enabled() || self.mode != Mode::Drawing
+    }
+
+    /// The CPU can't access OAM during OAM scan and drawing
+    pub fn oam_accessible(&self) -> bool {
+        !self.lcd_enabled() || matches!(self.mode, Mode::HBlank | Mode::VBlank)
+    }
+
+    /// Palette RAM can't be accessed while drawing either
+    fn palettes_locked(&self) -> bool {
+        !self.vram_accessible()
+    }
+
+    /// The last complete frame as 0xRRGGBB pixels, row by row
+    pub fn framebuffer(&self) -> &[u32] {
+        &self.framebuffer
+    }
+
+    /// Whether a new frame was completed since the last call
+    pub fn take_frame_ready(&mut self) -> bool {
+        std::mem::take(&mut self.frame_ready)
+    }
+
+    /// Whether an HBlank started since the last call
+    pub fn take_hblank(&mut self) -> bool {
+        std::mem::take(&mut self.hblank_started)
+    }
+
+    /// Advances the PPU by `dots`, 4 per M-C in single speed
+    pub fn tick(&mut self, dots: u16, video: &VideoMemory, int_flag: &mut u8) {
+        if !self.lcd_enabled() {
+            return;
+        }
+        for _ in 0..dots {
+            self.step(video, int_flag);
+        }
+    }
+
+    fn step(&mut self, video: &VideoMemory, int_flag: &mut u8) {
+        self.dot += 1;
+        if self.dot == DOTS_PER_LINE {
+            self.dot = 0;
+            self.ly += 1;
+            if self.ly == LINES_PER_FRAME {
+                self.ly = 0;
+                self.window_line = 0;
+                self.wy_triggered = false;
+            }
+        }
+        if (self.ly as usize) < SCREEN_HEIGHT {
+            match self.dot {
+                0 => self.start_line(),
+                OAM_SCAN_DOTS => self.start_drawing(video),
+                _ if self.mode == Mode::Drawing => {
+                    let done = match self.renderer {
+                        Renderer::Scanline => {
+                            let done = self.dot == self.drawing_end;
+                            if done {
+                                self.render_scanline(video);
+                            }
+                            done
+                        }
+                        Renderer::Fifo => self.fifo_dot(video),
+                    };
+                    if done {
+                        self.mode = Mode::HBlank;
+                        self.hblank_started = true;
+                    }
+                }
+                _ => (),
+            }
+        } else if self.ly as usize == SCREEN_HEIGHT && self.dot == 0 {
+            self.mode = Mode::VBlank;
+            self.frame_ready = true;
+            *int_flag |= Interrupt::VBlank.mask();
+        }
+        self.update_stat(int_flag);
+    }
+
+    fn start_line(&mut self) {
+        self.mode = Mode::OamScan;
+        if self.ly == self.wy {
+            self.wy_triggered = true;
+        }
+    }
+
+    fn start_drawing(&mut self, video: &VideoMemory) {
+        self.mode = Mode::Drawing;
+        match self.renderer {
+            Renderer::Scanline => {
+                self.drawing_end = OAM_SCAN_DOTS + DRAWING_DOTS + (self.scx & 0x7) as u16;
+            }
+            Renderer::Fifo => {
+                let sprites = self.scan_oam(video.oam);
+                self.pipeline.start_line(sprites, self.scx);
+            }
+        }
+    }
+
+    /// Updates the LY=LYC flag and requests a STAT interrupt on a rising edge of the STAT line
+    fn update_stat(&mut self, int_flag: &mut u8) {
+        let coincidence = self.ly == self.lyc;
+        self.stat = (self.stat & !0x04) | ((coincidence as u8) << 2);
+        let line =
+            self.stat & self.mode.stat_source() != 0 || (coincidence && self.stat & 0x40 != 0);
+        if line && !self.stat_line {
+            *int_flag |= Interrupt::Stat.mask();
+        }
+        self.stat_line = line;
+    }
+
+    fn set_lcdc(&mut self, value: u8) {
+        let was_enabled = self.lcd_enabled();
+        self.lcdc = value;
+        if was_enabled && !self.lcd_enabled() {
+            // The screen goes blank and LY resets
+            self.ly = 0;
+            self.dot = 0;
+            self.mode = Mode::HBlank;
+            self.window_line = 0;
+            self.framebuffer.fill(SHADES[0]);
+            self.frame_ready = true;
+        } else if !was_enabled && self.lcd_enabled() {
+            self.wy_triggered = false;
+            self.start_line();
+        }
+    }
+
+    pub fn read(&self, addr: u16) -> u8 {
+        match addr {
+            0xFF40 => self.lcdc,
+            0xFF41 => {
+                let mode = if self.lcd_enabled() {
+                    self.mode.bits()
+                } else {
+                    0
+                };
+                0x80 | (self.stat & 0x7C) | mode
+            }
+            0xFF42 => self.scy,
+            0xFF43 => self.scx,
+            0xFF44 => self.ly,
+            0xFF45 => self.lyc,
+            0xFF47 => self.bgp,
+            0xFF48 => self.obp0,
+            0xFF49 => self.obp1,
+            0xFF4A => self.wy,
+            0xFF4B => self.wx,
+            0xFF68 => self.bg_palettes.read_index(),
+            0xFF69 => self.bg_palettes.read_data(self.palettes_locked()),
+            0xFF6A => self.obj_palettes.read_index(),
+            0xFF6B => self.obj_palettes.read_data(self.palettes_locked()),
+            0xFF6C => 0xFE | self.opri,
+            _ => 0xFF,
+        }
+    }
+
+    pub fn write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0xFF40 => self.set_lcdc(value),
+            0xFF41 => self.stat = (self.stat & 0x07) | (value & 0x78),
+            0xFF42 => self.scy = value,
+            0xFF43 => self.scx = value,
+            // LY is read-only
+            0xFF44 => (),
+            0xFF45 => self.lyc = value,
+            0xFF47 => self.bgp = value,
+            0xFF48 => self.obp0 = value,
+            0xFF49 => self.obp1 = value,
+            0xFF4A => self.wy = value,
+            0xFF4B => self.wx = value,
+            0xFF68 => self.bg_palettes.write_index(value),
+            0xFF69 => {
+                let locked = self.palettes_locked();
+                self.bg_palettes.write_data(value, locked);
+            }
+            0xFF6A => self.obj_palettes.write_index(value),
+            0xFF6B => {
+                let locked = self.palettes_locked();
+                self.obj_palettes.write_data(value, locked);
+            }
+            0xFF6C => self.opri = value & 0x1,
+            _ => (),
+        }
+    }
+}
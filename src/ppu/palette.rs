@@ -0,0 +1,60 @@
+/// 8 palettes of 4 RGB555 colors, accessed through an index register and a data register
+/// https://gbdev.io/pandocs/Palettes.html#lcd-color-palettes-cgb-only
+#[derive(Debug)]
+pub(super) struct PaletteRam {
+    data: [u8; 64],
+    /// Bits 0-5 are the byte address, bit 7 increments it after each data write
+    index: u8,
+}
+
+impl Default for PaletteRam {
+    fn default() -> Self {
+        // Every color starts out white
+        Self {
+            data: [0xFF; 64],
+            index: 0,
+        }
+    }
+}
+
+impl PaletteRam {
+    /// BCPS/OCPS, bit 6 is unused
+    pub fn read_index(&self) -> u8 {
+        self.index | 0x40
+    }
+
+    pub fn write_index(&mut self, value: u8) {
+        self.index = value & 0xBF;
+    }
+
+    /// BCPD/OCPD, `locked` while the PPU is drawing
+    pub fn read_data(&self, locked: bool) -> u8 {
+        if locked {
+            0xFF
+        } else {
+            self.data[(self.index & 0x3F) as usize]
+        }
+    }
+
+    /// The address still increments when the write is dropped
+    pub fn write_data(&mut self, value: u8, locked: bool) {
+        if !locked {
+            self.data[(self.index & 0x3F) as usize] = value;
+        }
+        if self.index & 0x80 != 0 {
+            self.index = 0x80 | ((self.index + 1) & 0x3F);
+        }
+    }
+
+    /// Color `color` of palette `palette` as 0xRRGGBB
+    pub fn rgb(&self, palette: u8, color: u8) -> u32 {
+        let addr = (palette as usize & 0x7) * 8 + color as usize * 2;
+        let rgb555 = u16::from_le_bytes([self.data[addr], self.data[addr + 1]]);
+        // Scales each 5 bit channel to 8 bits
+        let channel = |shift: u16| {
+            let c = ((rgb555 >> shift) & 0x1F) as u32;
+            (c << 3) | (c >> 2)
+        };
+        (channel(0) << 16) | (channel(5) << 8) | channel(10)
+    }
+}
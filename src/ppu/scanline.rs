@@ -0,0 +1,313 @@
+use crate::ppu::{
+    BG_ENABLE, BG_MAP, OBJ_ENABLE, OBJ_SIZE, Ppu, SCREEN_WIDTH, SHADES, TILE_DATA, VideoMemory,
+    WINDOW_ENABLE, WINDOW_MAP,
+};
+
+/// At most 10 sprites are drawn on a line
+const SPRITES_PER_LINE: usize = 10;
+
+/// An OAM entry
+/// https://gbdev.io/pandocs/OAM.html
+#[derive(Clone, Copy, Debug)]
+pub(super) struct Sprite {
+    pub index: u8,
+    pub y: u8,
+    pub x: u8,
+    pub tile: u8,
+    pub attributes: u8,
+}
+
+impl Sprite {
+    fn read(oam: &[u8], index: usize) -> Self {
+        let entry = &oam[index * 4..index * 4 + 4];
+        Self {
+            index: index as u8,
+            y: entry[0],
+            x: entry[1],
+            tile: entry[2],
+            attributes: entry[3],
+        }
+    }
+
+    /// Sprite is drawn behind BG colors 1-3
+    pub fn behind_bg(&self) -> bool {
+        self.attributes & 0x80 != 0
+    }
+
+    pub fn y_flip(&self) -> bool {
+        self.attributes & 0x40 != 0
+    }
+
+    pub fn x_flip(&self) -> bool {
+        self.attributes & 0x20 != 0
+    }
+
+    /// OBP1 instead of OBP0
+    pub fn dmg_palette(&self) -> bool {
+        self.attributes & 0x10 != 0
+    }
+
+    /// CGB only
+    pub fn bank(&self) -> usize {
+        ((self.attributes >> 3) & 0x1) as usize
+    }
+
+    /// CGB only
+    pub fn cgb_palette(&self) -> u8 {
+        self.attributes & 0x7
+    }
+}
+
+/// Attributes of a BG map entry, stored in VRAM bank 1 on CGB
+/// https://gbdev.io/pandocs/Tile_Maps.html#bg-map-attributes-cgb-mode-only
+#[derive(Clone, Copy, Debug, Default)]
+pub(super) struct BgAttributes(pub u8);
+
+impl BgAttributes {
+    pub fn palette(&self) -> u8 {
+        self.0 & 0x7
+    }
+
+    pub fn bank(&self) -> usize {
+        ((self.0 >> 3) & 0x1) as usize
+    }
+
+    pub fn x_flip(&self) -> bool {
+        self.0 & 0x20 != 0
+    }
+
+    pub fn y_flip(&self) -> bool {
+        self.0 & 0x40 != 0
+    }
+
+    /// BG colors 1-3 are drawn over sprites
+    pub fn priority(&self) -> bool {
+        self.0 & 0x80 != 0
+    }
+}
+
+/// A BG or window pixel, palettes are applied when it's mixed
+#[derive(Clone, Copy, Debug, Default)]
+pub(super) struct BgPixel {
+    pub color: u8,
+    /// CGB only
+    pub palette: u8,
+    /// CGB only
+    pub priority: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub(super) struct ObjPixel {
+    pub color: u8,
+    /// 0 for OBP0 and 1 for OBP1 on DMG, palette 0-7 on CGB
+    pub palette: u8,
+    pub behind_bg: bool,
+    pub oam_index: u8,
+}
+
+/// Color index (0-3) of pixel `x` (0 is leftmost) from the 2 bytes of a tile row
+pub(super) fn tile_pixel(low: u8, high: u8, x: u8) -> u8 {
+    let bit = 7 - x;
+    (((high >> bit) & 0x1) << 1) | ((low >> bit) & 0x1)
+}
+
+/// Maps a color index through a DMG palette register
+pub(super) fn dmg_shade(palette: u8, color: u8) -> u32 {
+    SHADES[((palette >> (color * 2)) & 0x3) as usize]
+}
+
+impl Ppu {
+    /// Address of the first byte of `tile`, 0x8000 unsigned or 0x8800 signed addressing
+    /// https://gbdev.io/pandocs/Tile_Data.html
+    pub(super) fn tile_addr(&self, tile: u8) -> u16 {
+        if self.lcdc & TILE_DATA != 0 {
+            0x8000 + tile as u16 * 16
+        } else {
+            0x9000u16.wrapping_add_signed(tile as i8 as i16 * 16)
+        }
+    }
+
+    pub(super) fn bg_map(&self, window: bool) -> u16 {
+        let bit = if window { WINDOW_MAP } else { BG_MAP };
+        if self.lcdc & bit != 0 { 0x9C00 } else { 0x9800 }
+    }
+
+    /// Tile index and attributes of the map entry for tile column `x` and pixel row `y`
+    pub(super) fn bg_tile(
+        &self,
+        video: &VideoMemory,
+        map: u16,
+        x: u8,
+        y: u8,
+    ) -> (u8, BgAttributes) {
+        let addr = map + (y as u16 / 8) * 32 + (x & 0x1F) as u16;
+        let attributes = if self.cgb {
+            BgAttributes(video.vram(1, addr))
+        } else {
+            BgAttributes::default()
+        };
+        (video.vram(0, addr), attributes)
+    }
+
+    /// VRAM bank and address of the low byte of pixel row `y` of a BG tile
+    pub(super) fn bg_row_addr(&self, tile: u8, attributes: BgAttributes, y: u8) -> (usize, u16) {
+        let row = if attributes.y_flip() {
+            7 - y % 8
+        } else {
+            y % 8
+        };
+        (attributes.bank(), self.tile_addr(tile) + row as u16 * 2)
+    }
+
+    /// Picks the first 10 sprites (in OAM order) that overlap line `ly`
+    pub(super) fn scan_oam(&self, oam: &[u8]) -> Vec<Sprite> {
+        let height = self.sprite_height();
+        (0..40)
+            .map(|index| Sprite::read(oam, index))
+            .filter(|sprite| {
+                let top = sprite.y as i16 - 16;
+                (top..top + height as i16).contains(&(self.ly as i16))
+            })
+            .take(SPRITES_PER_LINE)
+            .collect()
+    }
+
+    pub(super) fn sprite_height(&self) -> u8 {
+        if self.lcdc & OBJ_SIZE != 0 { 16 } else { 8 }
+    }
+
+    /// The 2 bytes of the row of `sprite` that's on line `ly`
+    pub(super) fn sprite_row(&self, video: &VideoMemory, sprite: &Sprite) -> (u8, u8) {
+        let height = self.sprite_height();
+        let mut row = self.ly.wrapping_sub(sprite.y.wrapping_sub(16)) % height;
+        if sprite.y_flip() {
+            row = height - 1 - row;
+        }
+        // 8x16 sprites ignore bit 0 of the tile index
+        let tile = if height == 16 {
+            sprite.tile & 0xFE
+        } else {
+            sprite.tile
+        };
+        let bank = if self.cgb { sprite.bank() } else { 0 };
+        let addr = 0x8000 + tile as u16 * 16 + row as u16 * 2;
+        (video.vram(bank, addr), video.vram(bank, addr + 1))
+    }
+
+    /// Pixel `col` (0 is leftmost on screen) of a sprite row
+    pub(super) fn sprite_pixel(&self, sprite: &Sprite, low: u8, high: u8, col: u8) -> ObjPixel {
+        let x = if sprite.x_flip() { 7 - col } else { col };
+        ObjPixel {
+            color: tile_pixel(low, high, x),
+            palette: if self.cgb {
+                sprite.cgb_palette()
+            } else {
+                sprite.dmg_palette() as u8
+            },
+            behind_bg: sprite.behind_bg(),
+            oam_index: sprite.index,
+        }
+    }
+
+    /// Sprites are ordered by OAM index in CGB mode, by X then OAM index otherwise
+    pub(super) fn oam_priority(&self) -> bool {
+        self.cgb && self.opri & 0x1 == 0
+    }
+
+    /// Whether `new` replaces `old` where 2 sprites overlap, assumes sprites come in X order
+    pub(super) fn obj_wins(&self, new: &ObjPixel, old: &ObjPixel) -> bool {
+        new.color != 0 && (old.color == 0 || (self.oam_priority() && new.oam_index < old.oam_index))
+    }
+
+    /// Resolves the final color of a pixel from the BG pixel and the winning sprite pixel
+    /// https://gbdev.io/pandocs/Tile_Maps.html#bg-to-obj-priority-in-cgb-mode
+    pub(super) fn mix(&self, bg: BgPixel, obj: ObjPixel) -> u32 {
+        let bg_enabled = self.lcdc & BG_ENABLE != 0;
+        let obj_visible = obj.color != 0 && self.lcdc & OBJ_ENABLE != 0;
+        if self.cgb {
+            // LCDC bit 0 takes the priority away from the BG instead of hiding it
+            let bg_over = bg_enabled && bg.color != 0 && (bg.priority || obj.behind_bg);
+            if obj_visible && !bg_over {
+                self.obj_palettes.rgb(obj.palette, obj.color)
+            } else {
+                self.bg_palettes.rgb(bg.palette, bg.color)
+            }
+        } else {
+            let bg_color = if bg_enabled { bg.color } else { 0 };
+            if obj_visible && !(obj.behind_bg && bg_color != 0) {
+                let palette = if obj.palette != 0 {
+                    self.obp1
+                } else {
+                    self.obp0
+                };
+                dmg_shade(palette, obj.color)
+            } else if bg_enabled {
+                dmg_shade(self.bgp, bg_color)
+            } else {
+                SHADES[0]
+            }
+        }
+    }
+
+    /// Draws line LY into the framebuffer
+    pub(super) fn render_scanline(&mut self, video: &VideoMemory) {
+        let mut bg = [BgPixel::default(); SCREEN_WIDTH];
+        // On DMG LCDC bit 0 blanks the BG and window
+        if self.cgb || self.lcdc & BG_ENABLE != 0 {
+            let window_visible =
+                self.lcdc & WINDOW_ENABLE != 0 && self.wy_triggered && self.wx <= 166;
+            for x in 0..SCREEN_WIDTH as u8 {
+                let in_window = window_visible && x as u16 + 7 >= self.wx as u16;
+                let (map_x, map_y) = if in_window {
+                    (x + 7 - self.wx, self.window_line)
+                } else {
+                    (x.wrapping_add(self.scx), self.ly.wrapping_add(self.scy))
+                };
+                let (tile, attributes) =
+                    self.bg_tile(video, self.bg_map(in_window), map_x / 8, map_y);
+                let (bank, addr) = self.bg_row_addr(tile, attributes, map_y);
+                let col = if attributes.x_flip() {
+                    7 - map_x % 8
+                } else {
+                    map_x % 8
+                };
+                bg[x as usize] = BgPixel {
+                    color: tile_pixel(video.vram(bank, addr), video.vram(bank, addr + 1), col),
+                    palette: attributes.palette(),
+                    priority: attributes.priority(),
+                };
+            }
+            if window_visible {
+                self.window_line += 1;
+            }
+        }
+
+        let mut objs = [ObjPixel::default(); SCREEN_WIDTH];
+        if self.lcdc & OBJ_ENABLE != 0 {
+            let mut sprites = self.scan_oam(video.oam);
+            if !self.oam_priority() {
+                // The sort is stable, OAM order breaks ties
+                sprites.sort_by_key(|sprite| sprite.x);
+            }
+            for sprite in &sprites {
+                let (low, high) = self.sprite_row(video, sprite);
+                for col in 0..8u8 {
+                    let x = sprite.x as i16 - 8 + col as i16;
+                    if !(0..SCREEN_WIDTH as i16).contains(&x) {
+                        continue;
+                    }
+                    // The highest priority opaque sprite pixel is kept, even if BG hides it
+                    let pixel = self.sprite_pixel(sprite, low, high, col);
+                    if self.obj_wins(&pixel, &objs[x as usize]) {
+                        objs[x as usize] = pixel;
+                    }
+                }
+            }
+        }
+
+        let start = self.ly as usize * SCREEN_WIDTH;
+        for x in 0..SCREEN_WIDTH {
+            self.framebuffer[start + x] = self.mix(bg[x], objs[x]);
+        }
+    }
+}
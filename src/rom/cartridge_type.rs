@@ -0,0 +1,227 @@
+/// Mapper and extra hardware on the cartridge, decoded from the byte at 0x147
+/// https://gbdev.io/pandocs/The_Cartridge_Header.html#0147--cartridge-type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CartridgeType {
+    RomOnly {
+        ram: bool,
+        battery: bool,
+    },
+    Mbc1 {
+        ram: bool,
+        battery: bool,
+    },
+    /// MBC2 has 512x4 bits of RAM built into the mapper
+    Mbc2 {
+        battery: bool,
+    },
+    Mmm01 {
+        ram: bool,
+        battery: bool,
+    },
+    Mbc3 {
+        ram: bool,
+        battery: bool,
+        timer: bool,
+    },
+    Mbc5 {
+        ram: bool,
+        battery: bool,
+        rumble: bool,
+    },
+    Mbc6,
+    Mbc7,
+    PocketCamera,
+    Tama5,
+    HuC3,
+    HuC1,
+    Unknown(u8),
+}
+
+impl CartridgeType {
+    pub fn from_code(code: u8) -> Self {
+        match code {
+            0x00 => Self::RomOnly {
+                ram: false,
+                battery: false,
+            },
+            0x08 => Self::RomOnly {
+                ram: true,
+                battery: false,
+            },
+            0x09 => Self::RomOnly {
+                ram: true,
+                battery: true,
+            },
+            0x01 => Self::Mbc1 {
+                ram: false,
+                battery: false,
+            },
+            0x02 => Self::Mbc1 {
+                ram: true,
+                battery: false,
+            },
+            0x03 => Self::Mbc1 {
+                ram: true,
+                battery: true,
+            },
+            0x05 => Self::Mbc2 { battery: false },
+            0x06 => Self::Mbc2 { battery: true },
+            0x0B => Self::Mmm01 {
+                ram: false,
+                battery: false,
+            },
+            0x0C => Self::Mmm01 {
+                ram: true,
+                battery: false,
+            },
+            0x0D => Self::Mmm01 {
+                ram: true,
+                battery: true,
+            },
+            0x0F => Self::Mbc3 {
+                ram: false,
+                battery: true,
+                timer: true,
+            },
+            0x10 => Self::Mbc3 {
+                ram: true,
+                battery: true,
+                timer: true,
+            },
+            0x11 => Self::Mbc3 {
+                ram: false,
+                battery: false,
+                timer: false,
+            },
+            0x12 => Self::Mbc3 {
+                ram: true,
+                battery: false,
+                timer: false,
+            },
+            0x13 => Self::Mbc3 {
+                ram: true,
+                battery: true,
+                timer: false,
+            },
+            0x19 => Self::Mbc5 {
+                ram: false,
+                battery: false,
+                rumble: false,
+            },
+            0x1A => Self::Mbc5 {
+                ram: true,
+                battery: false,
+                rumble: false,
+            },
+            0x1B => Self::Mbc5 {
+                ram: true,
+                battery: true,
+                rumble: false,
+            },
+            0x1C => Self::Mbc5 {
+                ram: false,
+                battery: false,
+                rumble: true,
+            },
+            0x1D => Self::Mbc5 {
+                ram: true,
+                battery: false,
+                rumble: true,
+            },
+            0x1E => Self::Mbc5 {
+                ram: true,
+                battery: true,
+                rumble: true,
+            },
+            0x20 => Self::Mbc6,
+            0x22 => Self::Mbc7,
+            0xFC => Self::PocketCamera,
+            0xFD => Self::Tama5,
+            0xFE => Self::HuC3,
+            0xFF => Self::HuC1,
+            _ => Self::Unknown(code),
+        }
+    }
+
+    /// Whether the cartridge has external RAM, MBC2's built-in RAM counts
+    pub fn has_ram(&self) -> bool {
+        match self {
+            Self::RomOnly { ram, .. }
+            | Self::Mbc1 { ram, .. }
+            | Self::Mmm01 { ram, .. }
+            | Self::Mbc3 { ram, .. }
+            | Self::Mbc5 { ram, .. } => *ram,
+            Self::Mbc2 { .. }
+            | Self::Mbc6
+            | Self::Mbc7
+            | Self::PocketCamera
+            | Self::HuC3
+            | Self::HuC1 => true,
+            Self::Tama5 | Self::Unknown(_) => false,
+        }
+    }
+
+    pub fn has_battery(&self) -> bool {
+        match self {
+            Self::RomOnly { battery, .. }
+            | Self::Mbc1 { battery, .. }
+            | Self::Mbc2 { battery }
+            | Self::Mmm01 { battery, .. }
+            | Self::Mbc3 { battery, .. }
+            | Self::Mbc5 { battery, .. } => *battery,
+            Self::Mbc6 | Self::Mbc7 | Self::PocketCamera | Self::HuC3 | Self::HuC1 => true,
+            Self::Tama5 | Self::Unknown(_) => false,
+        }
+    }
+
+    pub fn has_timer(&self) -> bool {
+        match self {
+            Self::Mbc3 { timer, .. } => *timer,
+            Self::HuC3 | Self::Tama5 => true,
+            _ => false,
+        }
+    }
+
+    pub fn has_rumble(&self) -> bool {
+        match self {
+            Self::Mbc5 { rumble, .. } => *rumble,
+            Self::Mbc7 => true,
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for CartridgeType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mapper = match self {
+            Self::RomOnly { .. } => "ROM",
+            Self::Mbc1 { .. } => "MBC1",
+            Self::Mbc2 { .. } => "MBC2",
+            Self::Mmm01 { .. } => "MMM01",
+            Self::Mbc3 { .. } => "MBC3",
+            Self::Mbc5 { .. } => "MBC5",
+            Self::Mbc6 => "MBC6",
+            Self::Mbc7 => "MBC7",
+            Self::PocketCamera => "POCKET CAMERA",
+            Self::Tama5 => "BANDAI TAMA5",
+            Self::HuC3 => "HuC3",
+            Self::HuC1 => "HuC1",
+            Self::Unknown(code) => return write!(f, "Unknown ({code:#04X})"),
+        };
+        write!(f, "{mapper}")?;
+        // MBC2's RAM is part of the mapper so the header doesn't list it
+        if self.has_ram() && !matches!(self, Self::Mbc2 { .. }) {
+            write!(f, "+RAM")?;
+        }
+        if self.has_battery() {
+            write!(f, "+BATTERY")?;
+        }
+        if self.has_timer() {
+            write!(f, "+TIMER")?;
+        }
+        if self.has_rumble() {
+            write!(f, "+RUMBLE")?;
+        }
+        Ok(())
+    }
+}
@@ -1,2 +1,6 @@
+pub mod cartridge_type;
+pub mod licensee;
+pub mod rom_error;
 pub mod rom_info;
 pub mod rom_parser;
+pub mod rom_validation;
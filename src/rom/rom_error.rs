@@ -0,0 +1,41 @@
+use std::fmt;
+
+/// Problems found while parsing a ROM header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RomError {
+    /// The file ends before the header does
+    TooShort { len: usize },
+    /// The Nintendo logo at 0x104 doesn't match, a real DMG would lock up at boot
+    MissingLogo,
+    /// The header checksum at 0x14D doesn't match the header bytes
+    HeaderChecksum { expected: u8, calculated: u8 },
+    /// The ROM size code at 0x148 is not a known size
+    UnknownRomSize { code: u8 },
+    /// The RAM size code at 0x149 is not a known size
+    UnknownRamSize { code: u8 },
+}
+
+impl fmt::Display for RomError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RomError::TooShort { len } => {
+                write!(
+                    f,
+                    "File too short ({len:#X} bytes, the header ends at 0x150)"
+                )
+            }
+            RomError::MissingLogo => write!(f, "No Nintendo Logo found"),
+            RomError::HeaderChecksum {
+                expected,
+                calculated,
+            } => write!(
+                f,
+                "Invalid Header checksum (expected {expected:#04X}, calculated {calculated:#04X})"
+            ),
+            RomError::UnknownRomSize { code } => write!(f, "Unknown ROM size code {code:#04X}"),
+            RomError::UnknownRamSize { code } => write!(f, "Unknown RAM size code {code:#04X}"),
+        }
+    }
+}
+
+impl std::error::Error for RomError {}
@@ -1,9 +1,11 @@
+use crate::rom::{cartridge_type::CartridgeType, licensee::Licensee, rom_error::RomError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CGBMode {
     Monochrome,
     Color { exclusive: bool },
 }
 
-// TODO: Just derive debug lil bro
 impl std::fmt::Display for CGBMode {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let out = match self {
@@ -21,15 +23,31 @@ impl Default for CGBMode {
     }
 }
 
+/// https://gbdev.io/pandocs/The_Cartridge_Header.html#014a--destination-code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Destination {
+    Japan,
+    #[default]
+    Overseas,
+}
+
+#[derive(Debug)]
 pub struct ROMInfo {
     pub title: String,
     pub cgb: CGBMode,
     pub sgb: bool,
-    pub cartridge_type: u8,
+    pub cartridge_type: CartridgeType,
     pub rom_banks: u16,
+    /// Number of 8 KiB external RAM banks declared by the header
     pub mem_banks: u16,
+    pub destination: Destination,
+    pub licensee: Licensee,
+    /// Mask ROM version, usually 0
+    pub version: u8,
     pub header_checksum: u8,
     pub rom_checksum: u16,
+    /// Problems that were tolerated because the header was parsed leniently
+    pub warnings: Vec<RomError>,
 }
 
 impl Default for ROMInfo {
@@ -38,11 +56,15 @@ impl Default for ROMInfo {
             title: String::default(),
             cgb: CGBMode::default(),
             sgb: true,
-            cartridge_type: 0x10,
+            cartridge_type: CartridgeType::from_code(0x10),
             rom_banks: 1,
             mem_banks: 0x3,
+            destination: Destination::default(),
+            licensee: Licensee::default(),
+            version: 0,
             header_checksum: u8::default(),
             rom_checksum: u16::default(),
+            warnings: Vec::new(),
         }
     }
 }
@@ -1,35 +1,66 @@
-use crate::rom::rom_info::{CGBMode, ROMInfo};
-use std::fs;
+use crate::rom::cartridge_type::CartridgeType;
+use crate::rom::licensee::Licensee;
+use crate::rom::rom_error::RomError;
+use crate::rom::rom_info::{CGBMode, Destination, ROMInfo};
 use std::ops::Range;
 use std::ops::RangeInclusive;
-use std::str;
 
 // https://gbdev.io/pandocs/The_Cartridge_Header.html#0104-0133--nintendo-logo
-const NINTENDO: [u8; 48] = [
+pub const NINTENDO: [u8; 48] = [
     0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D,
     0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E, 0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99,
     0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC, 0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E,
 ];
 
-const NINTENDO_LOGO_RANGE: RangeInclusive<usize> = 0x104..=0x133;
+pub const NINTENDO_LOGO_RANGE: RangeInclusive<usize> = 0x104..=0x133;
 const HEADER_SIZE: usize = 0x150;
 const HEADER_RANGE: RangeInclusive<usize> = 0x134..=0x14C;
 const TITLE_RANGE: Range<usize> = 0x134..0x143;
 const CGB_FLAG_ADDR: usize = 0x143;
+const NEW_LICENSEE_RANGE: RangeInclusive<usize> = 0x144..=0x145;
 const SGB_FLAG_ADDR: usize = 0x146;
 const CARTRIDGE_TYPE_ADDR: usize = 0x147;
 const ROM_BANKS_ADDR: usize = 0x148;
 const MEM_BANKS_ADDR: usize = 0x149;
+const DESTINATION_ADDR: usize = 0x14A;
+const OLD_LICENSEE_ADDR: usize = 0x14B;
+const VERSION_ADDR: usize = 0x14C;
 const HEADER_CHECKSUM_ADDR: usize = 0x14D;
 const ROM_CHECKSUM_RANGE: RangeInclusive<usize> = 0x14E..=0x14F;
 
+/// How header problems that don't prevent parsing are handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseMode {
+    /// Any problem is an error, this is what the boot ROM enforces
+    Strict,
+    /// Bad logos and checksums are collected in `ROMInfo::warnings`
+    /// homebrew and test ROMs often don't bother fixing them
+    Lenient,
+}
+
 /// Extracts important ROM data from ROM header and preforms validation
-pub fn parse_rom_header(rom: &Vec<u8>) -> ROMInfo {
-    assert!(rom.len() > HEADER_SIZE, "Invalid ROM File (File too short)");
-    assert!(
-        validate_rom(rom),
-        "Invalid ROM File (No Nintendo Logo found)"
-    );
+pub fn parse_rom_header(rom: &[u8]) -> Result<ROMInfo, RomError> {
+    parse_rom_header_with(rom, ParseMode::Strict)
+}
+
+/// Same as `parse_rom_header` but lets the caller tolerate a damaged header
+/// A file too short to hold a header is always an error
+pub fn parse_rom_header_with(rom: &[u8], mode: ParseMode) -> Result<ROMInfo, RomError> {
+    if rom.len() < HEADER_SIZE {
+        return Err(RomError::TooShort { len: rom.len() });
+    }
+    let mut warnings = Vec::new();
+    let mut report = |error: RomError| match mode {
+        ParseMode::Strict => Err(error),
+        ParseMode::Lenient => {
+            warnings.push(error);
+            Ok(())
+        }
+    };
+
+    if rom[NINTENDO_LOGO_RANGE] != NINTENDO {
+        report(RomError::MissingLogo)?;
+    }
 
     // https://gbdev.io/pandocs/The_Cartridge_Header.html#0134-0143--title
     let game_title = String::from_utf8_lossy(&rom[TITLE_RANGE]).to_string();
@@ -46,27 +77,62 @@ pub fn parse_rom_header(rom: &Vec<u8>) -> ROMInfo {
     let sgb = rom[SGB_FLAG_ADDR] == 0x3;
 
     // https://gbdev.io/pandocs/The_Cartridge_Header.html#0147--cartridge-type
-    let cartridge_type = rom[CARTRIDGE_TYPE_ADDR];
+    let cartridge_type = CartridgeType::from_code(rom[CARTRIDGE_TYPE_ADDR]);
 
     // https://gbdev.io/pandocs/The_Cartridge_Header.html#0148--rom-size
-    let rom_banks = 2 * (1 << rom[ROM_BANKS_ADDR]);
+    // 0x52-0x54 are listed by unofficial sources but no known ROM uses them
+    let rom_banks = match rom[ROM_BANKS_ADDR] {
+        code @ 0x0..=0x8 => 2 << code,
+        0x52 => 72,
+        0x53 => 80,
+        0x54 => 96,
+        code => {
+            report(RomError::UnknownRomSize { code })?;
+            rom.len().div_ceil(0x4000) as u16
+        }
+    };
 
     // https://gbdev.io/pandocs/The_Cartridge_Header.html#0149--ram-size
+    // 0x1 was 2 KiB on a few early carts, it's rounded up to a full bank
     let mem_banks = match rom[MEM_BANKS_ADDR] {
         0x0 => 0,
-        0x1 => 1,
+        0x1 | 0x2 => 1,
         0x3 => 4,
         0x4 => 16,
         0x5 => 8,
-        _ => 0,
+        code => {
+            report(RomError::UnknownRamSize { code })?;
+            0
+        }
     };
 
+    // https://gbdev.io/pandocs/The_Cartridge_Header.html#014a--destination-code
+    let destination = match rom[DESTINATION_ADDR] {
+        0x0 => Destination::Japan,
+        _ => Destination::Overseas,
+    };
+
+    // https://gbdev.io/pandocs/The_Cartridge_Header.html#01440145--new-licensee-code
+    let licensee = Licensee::new(
+        rom[OLD_LICENSEE_ADDR],
+        [
+            rom[*NEW_LICENSEE_RANGE.start()],
+            rom[*NEW_LICENSEE_RANGE.end()],
+        ],
+    );
+
+    // https://gbdev.io/pandocs/The_Cartridge_Header.html#014c--mask-rom-version-number
+    let version = rom[VERSION_ADDR];
+
     // https://gbdev.io/pandocs/The_Cartridge_Header.html#014d--header-checksum
     let header_checksum = rom[HEADER_CHECKSUM_ADDR];
-    assert!(
-        validate_header_checksum(&rom[HEADER_RANGE], header_checksum),
-        "Invalid Header checksum"
-    );
+    let calculated = header_checksum_of(&rom[HEADER_RANGE]);
+    if calculated != header_checksum {
+        report(RomError::HeaderChecksum {
+            expected: header_checksum,
+            calculated,
+        })?;
+    }
 
     // These two bytes form one 16-bit big endian number for the rom (global) checksum
     // https://gbdev.io/pandocs/The_Cartridge_Header.html#014e-014f--global-checksum
@@ -75,31 +141,25 @@ pub fn parse_rom_header(rom: &Vec<u8>) -> ROMInfo {
         ((bytes[0] as u16) << 8) | bytes[1] as u16
     };
 
-    let info = ROMInfo {
+    Ok(ROMInfo {
         title: game_title,
         cgb: cgb_mode,
         sgb,
         cartridge_type,
         rom_banks,
         mem_banks,
+        destination,
+        licensee,
+        version,
         header_checksum,
         rom_checksum,
-    };
-    println!("{}", info.title);
-    println!("{}", info.cgb);
-    info
-}
-
-fn validate_header_checksum(header: &[u8], checksum: u8) -> bool {
-    let mut calculated_checksum: u8 = 0;
-    for byte in header.iter() {
-        calculated_checksum = calculated_checksum.wrapping_sub(*byte).wrapping_sub(1);
-    }
-    println!("Calculated checksum: {calculated_checksum:#x}");
-    println!("Header checksum: {checksum:#x}");
-    calculated_checksum == checksum
+        warnings,
+    })
 }
 
-fn validate_rom(rom: &[u8]) -> bool {
-    rom[NINTENDO_LOGO_RANGE] == NINTENDO
+/// https://gbdev.io/pandocs/The_Cartridge_Header.html#014d--header-checksum
+fn header_checksum_of(header: &[u8]) -> u8 {
+    header.iter().fold(0u8, |checksum, byte| {
+        checksum.wrapping_sub(*byte).wrapping_sub(1)
+    })
 }
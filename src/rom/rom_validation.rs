@@ -0,0 +1,112 @@
+use std::fmt;
+
+use crate::rom::rom_info::ROMInfo;
+
+const BANK_SIZE: usize = 0x4000;
+const ROM_CHECKSUM_ADDR: usize = 0x14E;
+
+/// A problem with the ROM dump itself, none of them stop the ROM from running
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RomIssue {
+    /// The global checksum at 0x14E doesn't match the sum of the ROM bytes
+    GlobalChecksum { expected: u16, calculated: u16 },
+    /// The file is smaller than the size declared at 0x148
+    Truncated { declared: usize, actual: usize },
+    /// The file is bigger than the size declared at 0x148
+    Oversized { declared: usize, actual: usize },
+    /// The file doesn't end on a bank boundary
+    PartialBank { len: usize },
+    /// Mappers wrap the bank number with a mask so the bank count should be a power of two
+    BankCount { banks: usize },
+}
+
+impl fmt::Display for RomIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RomIssue::GlobalChecksum {
+                expected,
+                calculated,
+            } => write!(
+                f,
+                "Global checksum mismatch (expected {expected:#06X}, calculated {calculated:#06X})"
+            ),
+            RomIssue::Truncated { declared, actual } => write!(
+                f,
+                "Truncated dump ({actual:#X} bytes, header declares {declared:#X})"
+            ),
+            RomIssue::Oversized { declared, actual } => write!(
+                f,
+                "Oversized dump ({actual:#X} bytes, header declares {declared:#X})"
+            ),
+            RomIssue::PartialBank { len } => {
+                write!(f, "File size {len:#X} is not a multiple of the bank size")
+            }
+            RomIssue::BankCount { banks } => write!(f, "{banks} ROM banks is not a power of two"),
+        }
+    }
+}
+
+/// Report on how well the ROM file matches its header
+/// https://gbdev.io/pandocs/The_Cartridge_Header.html#014e-014f--global-checksum
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RomValidation {
+    pub calculated_checksum: u16,
+    /// Number of banks in the file, a trailing partial bank counts as one
+    pub file_banks: usize,
+    pub issues: Vec<RomIssue>,
+}
+
+impl RomValidation {
+    /// Verifies the global checksum and the ROM size against a parsed header
+    pub fn new(rom: &[u8], info: &ROMInfo) -> Self {
+        let calculated_checksum = global_checksum_of(rom);
+        let file_banks = rom.len().div_ceil(BANK_SIZE);
+        let declared = info.rom_banks as usize * BANK_SIZE;
+        let mut issues = Vec::new();
+
+        if calculated_checksum != info.rom_checksum {
+            issues.push(RomIssue::GlobalChecksum {
+                expected: info.rom_checksum,
+                calculated: calculated_checksum,
+            });
+        }
+        if rom.len() < declared {
+            issues.push(RomIssue::Truncated {
+                declared,
+                actual: rom.len(),
+            });
+        } else if rom.len() > declared {
+            issues.push(RomIssue::Oversized {
+                declared,
+                actual: rom.len(),
+            });
+        }
+        if !rom.len().is_multiple_of(BANK_SIZE) {
+            issues.push(RomIssue::PartialBank { len: rom.len() });
+        }
+        // The 72, 80 and 96 bank sizes are odd but declared by the header
+        if !file_banks.is_power_of_two() && file_banks != info.rom_banks as usize {
+            issues.push(RomIssue::BankCount { banks: file_banks });
+        }
+
+        Self {
+            calculated_checksum,
+            file_banks,
+            issues,
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Sum of every byte in the ROM except the two checksum bytes
+fn global_checksum_of(rom: &[u8]) -> u16 {
+    rom.iter()
+        .enumerate()
+        .filter(|(addr, _)| !(ROM_CHECKSUM_ADDR..=ROM_CHECKSUM_ADDR + 1).contains(addr))
+        .fold(0u16, |checksum, (_, byte)| {
+            checksum.wrapping_add(*byte as u16)
+        })
+}
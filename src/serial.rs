@@ -0,0 +1,41 @@
+use crate::cpu::reg_file::Modes;
+
+/// SB and SC, there is no link cable so nothing is ever transferred
+/// https://gbdev.io/pandocs/Serial_Data_Transfer_(Link_Cable).html
+#[derive(Debug)]
+pub struct Serial {
+    sb: u8,
+    sc: u8,
+    /// Bits of SC that read as 1, the clock speed bit only exists on CGB
+    sc_mask: u8,
+}
+
+impl Serial {
+    pub fn new(mode: Modes) -> Self {
+        let sc_mask = match mode {
+            Modes::CGB => 0x7C,
+            _ => 0x7E,
+        };
+        Self {
+            sb: 0,
+            sc: 0,
+            sc_mask,
+        }
+    }
+
+    pub fn read(&self, addr: u16) -> u8 {
+        match addr {
+            0xFF01 => self.sb,
+            0xFF02 => self.sc | self.sc_mask,
+            _ => 0xFF,
+        }
+    }
+
+    pub fn write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0xFF01 => self.sb = value,
+            0xFF02 => self.sc = value & !self.sc_mask,
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use crate::cpu::{interrupts::Interrupt, reg_file::Modes};
+
+/// DIV, TIMA, TMA and TAC
+/// https://gbdev.io/pandocs/Timer_and_Divider_Registers.html
+#[derive(Debug)]
+pub struct Timer {
+    /// Internal 16-bit counter incremented every T-cycle, DIV is its upper byte
+    divider: u16,
+    tima: u8,
+    tma: u8,
+    tac: u8,
+    /// TIMA overflowed during the last M-cycle and reads 0x00, TMA is loaded on the next one
+    overflow: bool,
+    /// TMA is being loaded into TIMA this M-cycle, TIMA writes are ignored and TMA writes
+    /// go through to TIMA
+    reloading: bool,
+}
+
+impl Timer {
+    pub fn new(mode: Modes) -> Self {
+        // https://gbdev.io/pandocs/Power_Up_Sequence.html#hardware-registers
+        let divider = match mode {
+            Modes::DMG | Modes::MGB => 0xABCC,
+            // Depends on how long the CGB boot ROM ran
+            Modes::CGB | Modes::CGBDMG => 0x1EA0,
+        };
+        Self {
+            divider,
+            tima: 0,
+            tma: 0,
+            tac: 0,
+            overflow: false,
+            reloading: false,
+        }
+    }
+
+    /// The internal counter, the APU frame sequencer is clocked from it too
+    pub fn divider(&self) -> u16 {
+        self.divider
+    }
+
+    /// +1 M-C (4 T-C)
+    pub fn tick(&mut self, int_flag: &mut u8) {
+        self.reloading = false;
+        if self.overflow {
+            self.overflow = false;
+            self.reloading = true;
+            self.tima = self.tma;
+            *int_flag |= Interrupt::Timer.mask();
+        }
+        self.set_divider(self.divider.wrapping_add(4));
+    }
+
+    /// The input of TIMA's falling edge detector: the divider bit selected by TAC,
+    /// ANDed with the enable bit
+    /// https://gbdev.io/pandocs/Timer_Obscure_Behaviour.html#relation-between-timer-and-divider-register
+    fn signal(&self) -> bool {
+        let bit = match self.tac & 0x3 {
+            0x0 => 9,
+            0x1 => 3,
+            0x2 => 5,
+            _ => 7,
+        };
+        self.tac & 0x4 != 0 && self.divider & (1 << bit) != 0
+    }
+
+    /// Updates the divider, incrementing TIMA on a falling edge of the signal
+    fn set_divider(&mut self, divider: u16) {
+        let before = self.signal();
+        self.divider = divider;
+        if before && !self.signal() {
+            self.increment_tima();
+        }
+    }
+
+    fn increment_tima(&mut self) {
+        let (tima, overflow) = self.tima.overflowing_add(1);
+        self.tima = tima;
+        self.overflow = overflow;
+    }
+
+    /// STOP resets the divider like a write to DIV
+    pub fn reset_divider(&mut self) {
+        self.set_divider(0);
+    }
+
+    pub fn read(&self, addr: u16) -> u8 {
+        match addr {
+            0xFF04 => (self.divider >> 8) as u8,
+            0xFF05 => self.tima,
+            0xFF06 => self.tma,
+            0xFF07 => self.tac | 0xF8,
+            _ => 0xFF,
+        }
+    }
+
+    pub fn write(&mut self, addr: u16, value: u8) {
+        match addr {
+            // Any write resets the whole divider, which can clock TIMA
+            0xFF04 => self.set_divider(0),
+            // Writing during the overflow cycle cancels the reload and the interrupt
+            0xFF05 if !self.reloading => {
+                self.overflow = false;
+                self.tima = value;
+            }
+            0xFF06 => {
+                self.tma = value;
+                if self.reloading {
+                    self.tima = value;
+                }
+            }
+            0xFF07 => {
+                // Disabling the timer or switching to a cleared bit is a falling edge too
+                let before = self.signal();
+                self.tac = value & 0x7;
+                if before && !self.signal() {
+                    self.increment_tima();
+                }
+            }
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,227 @@
+use redgb::{
+    apu::TARGET_LATENCY,
+    cpu::{clock::Clock, reg_file::Modes},
+    error::EmuError,
+    mem::map::MemoryMap,
+    rom::rom_info::ROMInfo,
+};
+
+/// Map with the APU freshly powered on (frame sequencer at step 0) and DIV reset
+fn get_mock_map() -> Result<(MemoryMap, Clock), EmuError> {
+    let mut clock = Clock::default();
+    let mut memory = MemoryMap::new(vec![0; 0x8000], ROMInfo::default(), Modes::DMG);
+    memory.write(&mut clock, 0xFF26, 0x00)?;
+    memory.write(&mut clock, 0xFF26, 0x80)?;
+    memory.write(&mut clock, 0xFF24, 0x77)?;
+    memory.write(&mut clock, 0xFF25, 0xFF)?;
+    memory.write(&mut clock, 0xFF04, 0x00)?;
+    Ok((memory, clock))
+}
+
+fn idle(memory: &mut MemoryMap, clock: &mut Clock, m_cycles: u32) {
+    for _ in 0..m_cycles {
+        memory.tick(clock);
+    }
+}
+
+#[test]
+fn trigger_and_dac() -> Result<(), EmuError> {
+    let (mut memory, mut clock) = get_mock_map()?;
+    assert_eq!(memory.read(&mut clock, 0xFF26)?, 0xF0);
+    // A trigger with the DAC off doesn't enable the channel
+    memory.write(&mut clock, 0xFF19, 0x80)?;
+    assert_eq!(memory.read(&mut clock, 0xFF26)?, 0xF0);
+    memory.write(&mut clock, 0xFF17, 0xF0)?;
+    memory.write(&mut clock, 0xFF19, 0x80)?;
+    assert_eq!(memory.read(&mut clock, 0xFF26)?, 0xF2);
+    // Turning the DAC off disables it right away
+    memory.write(&mut clock, 0xFF17, 0x00)?;
+    assert_eq!(memory.read(&mut clock, 0xFF26)?, 0xF0);
+
+    memory.write(&mut clock, 0xFF1A, 0x80)?;
+    memory.write(&mut clock, 0xFF1E, 0x80)?;
+    memory.write(&mut clock, 0xFF21, 0x08)?;
+    memory.write(&mut clock, 0xFF23, 0x80)?;
+    assert_eq!(memory.read(&mut clock, 0xFF26)?, 0xFC);
+    memory.write(&mut clock, 0xFF26, 0x00)?;
+    assert_eq!(memory.read(&mut clock, 0xFF26)?, 0x70);
+    Ok(())
+}
+
+#[test]
+fn post_boot_channel_1() -> Result<(), EmuError> {
+    let mut clock = Clock::default();
+    let mut memory = MemoryMap::new(vec![0; 0x8000], ROMInfo::default(), Modes::DMG);
+    assert_eq!(memory.read(&mut clock, 0xFF26)?, 0xF1);
+    // The envelope left by the boot ROM keeps the DAC on for a bare retrigger
+    memory.write(&mut clock, 0xFF14, 0x80)?;
+    assert_eq!(memory.read(&mut clock, 0xFF26)?, 0xF1);
+    Ok(())
+}
+
+#[test]
+fn length_counter() -> Result<(), EmuError> {
+    let (mut memory, mut clock) = get_mock_map()?;
+    // 1 step of length left
+    memory.write(&mut clock, 0xFF16, 0x3F)?;
+    memory.write(&mut clock, 0xFF17, 0xF0)?;
+    memory.write(&mut clock, 0xFF19, 0xC0)?;
+    // The frame sequencer is clocked when DIV bit 4 falls, every 2048 M-C
+    idle(&mut memory, &mut clock, 2040);
+    assert_eq!(memory.read(&mut clock, 0xFF26)? & 0x2, 0x2);
+    idle(&mut memory, &mut clock, 10);
+    assert_eq!(memory.read(&mut clock, 0xFF26)? & 0x2, 0x0);
+
+    // Without length enabled the channel keeps playing
+    memory.write(&mut clock, 0xFF16, 0x3F)?;
+    memory.write(&mut clock, 0xFF19, 0x80)?;
+    idle(&mut memory, &mut clock, 4096);
+    assert_eq!(memory.read(&mut clock, 0xFF26)? & 0x2, 0x2);
+    Ok(())
+}
+
+#[test]
+fn sweep_overflow() -> Result<(), EmuError> {
+    let (mut memory, mut clock) = get_mock_map()?;
+    memory.write(&mut clock, 0xFF12, 0xF0)?;
+    // Period 1, increasing, shift 1: 0x600 + 0x300 overflows as soon as it's triggered
+    memory.write(&mut clock, 0xFF10, 0x11)?;
+    memory.write(&mut clock, 0xFF13, 0x00)?;
+    memory.write(&mut clock, 0xFF14, 0x86)?;
+    assert_eq!(memory.read(&mut clock, 0xFF26)? & 0x1, 0x0);
+    // 0x400 + 0x200 is fine, 0x600 + 0x300 overflows on the first sweep step (frame step 2)
+    memory.write(&mut clock, 0xFF14, 0x84)?;
+    assert_eq!(memory.read(&mut clock, 0xFF26)? & 0x1, 0x1);
+    idle(&mut memory, &mut clock, 2048 * 3);
+    assert_eq!(memory.read(&mut clock, 0xFF26)? & 0x1, 0x0);
+    Ok(())
+}
+
+#[test]
+fn samples() -> Result<(), EmuError> {
+    let (mut memory, mut clock) = get_mock_map()?;
+    let output = memory.audio_output();
+    memory.set_sample_rate(48_000);
+    let mut silence = vec![1.0; 8];
+    output.fill(&mut silence);
+    assert!(silence.iter().all(|sample| *sample == 0.0));
+
+    // 50% duty at 1 kHz, left only
+    memory.write(&mut clock, 0xFF25, 0x20)?;
+    memory.write(&mut clock, 0xFF16, 0x80)?;
+    memory.write(&mut clock, 0xFF17, 0xF0)?;
+    memory.write(&mut clock, 0xFF18, 0x83)?;
+    memory.write(&mut clock, 0xFF19, 0x87)?;
+    while !output.is_empty() {
+        output.fill(&mut [0.0; 2]);
+    }
+    // 1 frame is 70224 T-C, the empty output speeds the resampler up a little
+    idle(&mut memory, &mut clock, 17556);
+    let len = output.len();
+    assert!((770..=810).contains(&len), "{len} frames");
+    let mut samples = vec![0.0; len * 2];
+    output.fill(&mut samples);
+    let (left, right): (Vec<f32>, Vec<f32>) =
+        samples.chunks(2).map(|frame| (frame[0], frame[1])).unzip();
+    assert!(right.iter().all(|sample| *sample == 0.0));
+    // Full volume through the DAC and NR50 at max, the steps ring a little
+    let high = left.iter().cloned().fold(f32::MIN, f32::max);
+    let low = left.iter().cloned().fold(f32::MAX, f32::min);
+    assert!((0.24..0.35).contains(&high), "high {high}");
+    assert!((-0.35..-0.2).contains(&low), "low {low}");
+    // About 16 periods in the frame, ignoring the ringing before the first step
+    let levels: Vec<bool> = left
+        .iter()
+        .filter(|sample| sample.abs() > 0.1)
+        .map(|sample| *sample > 0.0)
+        .collect();
+    let crossings = levels.windows(2).filter(|w| w[0] != w[1]).count();
+    assert!((30..=34).contains(&crossings), "{crossings} crossings");
+    Ok(())
+}
+
+/// Drains the output, runs for `m_cycles` and returns what was produced
+fn run(memory: &mut MemoryMap, clock: &mut Clock, m_cycles: u32) -> Vec<f32> {
+    let output = memory.audio_output();
+    while !output.is_empty() {
+        output.fill(&mut [0.0; 2]);
+    }
+    idle(memory, clock, m_cycles);
+    let mut samples = vec![0.0; output.len() * 2];
+    output.fill(&mut samples);
+    samples
+}
+
+#[test]
+fn dc_blocking() -> Result<(), EmuError> {
+    let (mut memory, mut clock) = get_mock_map()?;
+    // DAC on at volume 0, a constant 1.0 out of the DAC
+    memory.write(&mut clock, 0xFF17, 0x08)?;
+    let start = run(&mut memory, &mut clock, 1000);
+    assert!(start.iter().any(|sample| *sample > 0.1));
+    // The capacitor charges up and the offset disappears
+    run(&mut memory, &mut clock, 17556 * 3);
+    let settled = run(&mut memory, &mut clock, 17556);
+    assert!(settled.iter().all(|sample| sample.abs() < 0.01));
+    Ok(())
+}
+
+/// Frames produced over 10 video frames, reporting `queued` frames on the host side
+/// after each of them (nothing is reported with None)
+fn produced(queued: Option<usize>) -> Result<usize, EmuError> {
+    let (mut memory, mut clock) = get_mock_map()?;
+    let output = memory.audio_output();
+    let mut total = 0;
+    for _ in 0..10 {
+        idle(&mut memory, &mut clock, 17556);
+        let new = output.len();
+        output.fill(&mut vec![0.0; new * 2]);
+        total += new;
+        if let Some(queued) = queued {
+            memory.set_audio_queued(queued);
+        }
+    }
+    Ok(total)
+}
+
+#[test]
+fn rate_control() -> Result<(), EmuError> {
+    // 8037 frames at exactly 48 kHz, give or take the frames not flushed yet
+    let unreported = produced(None)?;
+    assert!((8005..=8037).contains(&unreported), "{unreported} frames");
+    // Up to 0.5% faster when the host runs dry, slower when it backs up
+    let empty = produced(Some(0))?;
+    let backed_up = produced(Some(48_000))?;
+    assert!((8045..=8080).contains(&empty), "{empty} frames");
+    assert!((7990..8005).contains(&backed_up), "{backed_up} frames");
+    Ok(())
+}
+
+/// A host playing 0.45% faster than the emulator produces, as if the clocks disagreed
+#[test]
+fn rate_control_keeps_host_queue_filled() -> Result<(), EmuError> {
+    let (mut memory, mut clock) = get_mock_map()?;
+    let output = memory.audio_output();
+    // Frames per video frame at 48 kHz
+    let rate = 48_000.0 * 70_224.0 / 4_194_304.0 * 1.0045;
+    let target = (48_000.0 * TARGET_LATENCY) as usize;
+    // Starts half full, without rate control it runs dry after about 320 frames
+    let mut queued = target / 2;
+    let mut played = 0.0;
+    memory.set_audio_queued(queued);
+    for frame in 0..400 {
+        idle(&mut memory, &mut clock, 17556);
+        let new = output.len();
+        output.fill(&mut vec![0.0; new * 2]);
+        queued += new;
+        played += rate;
+        let consumed = played as usize;
+        played -= consumed as f64;
+        assert!(queued >= consumed, "ran dry after {frame} frames");
+        queued -= consumed;
+        assert!(queued < 2 * target, "{queued} frames queued");
+        memory.set_audio_queued(queued);
+    }
+    assert!(queued > target / 5, "{queued} frames queued");
+    Ok(())
+}
@@ -5,6 +5,7 @@ use redgb::{
         cpu_context::CpuContext,
         reg_file::{Flag, Modes, RegFile},
     },
+    error::EmuError,
     mem::map::MemoryMap,
     rom::rom_info::ROMInfo,
 };
@@ -20,7 +21,7 @@ fn get_mock_context(rom: Vec<u8>) -> CpuContext {
 }
 
 #[test]
-fn add_a_b() -> Result<(), String> {
+fn add_a_b() -> Result<(), EmuError> {
     let mut context = get_mock_context(vec![0x80, 0xDD]);
     context.registers.a = 172;
     context.registers.b = 108;
@@ -45,7 +46,7 @@ fn add_a_b() -> Result<(), String> {
 }
 
 #[test]
-fn adc_a_hl() -> Result<(), String> {
+fn adc_a_hl() -> Result<(), EmuError> {
     let mut context = get_mock_context(vec![0x8E, 0xDD]);
     context.registers.a = 172;
     alu::write_u16(&mut context.registers.l, &mut context.registers.h, 0xC001);
@@ -72,7 +73,7 @@ fn adc_a_hl() -> Result<(), String> {
 }
 
 #[test]
-fn sub_a_c() -> Result<(), String> {
+fn sub_a_c() -> Result<(), EmuError> {
     let mut context = get_mock_context(vec![0x91, 0xDD]);
     context.registers.a = 64;
     context.registers.c = 108;
@@ -97,7 +98,7 @@ fn sub_a_c() -> Result<(), String> {
 }
 
 #[test]
-fn sbc_a_hl() -> Result<(), String> {
+fn sbc_a_hl() -> Result<(), EmuError> {
     let mut context = get_mock_context(vec![0x9E, 0xDD]);
     context.registers.a = 64;
     alu::write_u16(&mut context.registers.l, &mut context.registers.h, 0xC001);
@@ -124,7 +125,7 @@ fn sbc_a_hl() -> Result<(), String> {
 }
 
 #[test]
-fn inc_b() -> Result<(), String> {
+fn inc_b() -> Result<(), EmuError> {
     let mut context = get_mock_context(vec![0x04, 0xDD]);
     context.registers.b = 255;
     let _ = context.start_exec_cycle();
@@ -137,7 +138,7 @@ fn inc_b() -> Result<(), String> {
 }
 
 #[test]
-fn dec_hl() -> Result<(), String> {
+fn dec_hl() -> Result<(), EmuError> {
     let mut context = get_mock_context(vec![0x35, 0xDD]);
     alu::write_u16(&mut context.registers.l, &mut context.registers.h, 0xC001);
     let _ = context.memory.write(&mut context.clock, 0xC001, 0);
@@ -149,3 +150,138 @@ fn dec_hl() -> Result<(), String> {
     assert!(context.registers.read_flag(Flag::Subtract));
     Ok(())
 }
+
+#[test]
+fn inc_dec_r16() -> Result<(), EmuError> {
+    // inc bc, dec sp
+    let mut context = get_mock_context(vec![0x03, 0x3B, 0xDD]);
+    alu::write_u16(&mut context.registers.c, &mut context.registers.b, 0xFFFF);
+    context.registers.sp = 0;
+    let f = context.registers.f;
+    let _ = context.start_exec_cycle();
+    assert_eq!(alu::read_u16(&context.registers.c, &context.registers.b), 0);
+    assert_eq!(context.registers.sp, 0xFFFF);
+    assert_eq!(context.registers.f, f);
+    assert_eq!(context.clock.m_cycles, 5);
+    Ok(())
+}
+
+#[test]
+fn add_hl_de() -> Result<(), EmuError> {
+    let mut context = get_mock_context(vec![0x19, 0xDD]);
+    alu::write_u16(&mut context.registers.l, &mut context.registers.h, 0x8A23);
+    alu::write_u16(&mut context.registers.e, &mut context.registers.d, 0x8605);
+    let _ = context.registers.set_flag(Flag::Zero, Some(true));
+    let _ = context.start_exec_cycle();
+    assert_eq!(
+        alu::read_u16(&context.registers.l, &context.registers.h),
+        0x1028
+    );
+    assert_eq!(context.clock.m_cycles, 3);
+    assert!(context.registers.read_flag(Flag::Carry));
+    assert!(context.registers.read_flag(Flag::HalfCarry));
+    assert!(context.registers.read_flag(Flag::Zero));
+    assert!(!context.registers.read_flag(Flag::Subtract));
+    Ok(())
+}
+
+#[test]
+fn add_sp_e8() -> Result<(), EmuError> {
+    // add sp, -1
+    let mut context = get_mock_context(vec![0xE8, 0xFF, 0xDD]);
+    context.registers.sp = 0xFFF8;
+    let _ = context.start_exec_cycle();
+    assert_eq!(context.registers.sp, 0xFFF7);
+    assert_eq!(context.clock.m_cycles, 5);
+    assert!(context.registers.read_flag(Flag::Carry));
+    assert!(context.registers.read_flag(Flag::HalfCarry));
+    assert!(!context.registers.read_flag(Flag::Zero));
+    assert!(!context.registers.read_flag(Flag::Subtract));
+
+    // add sp, 1
+    let mut context = get_mock_context(vec![0xE8, 0x01, 0xDD]);
+    context.registers.sp = 0x00FE;
+    let _ = context.start_exec_cycle();
+    assert_eq!(context.registers.sp, 0x00FF);
+    assert!(!context.registers.read_flag(Flag::Carry));
+    assert!(!context.registers.read_flag(Flag::HalfCarry));
+    Ok(())
+}
+
+#[test]
+fn adc_carry_overflow() -> Result<(), EmuError> {
+    let mut context = get_mock_context(vec![0x88, 0xDD]);
+    context.registers.a = 0x01;
+    context.registers.b = 0xFF;
+    let _ = context.registers.set_flag(Flag::Carry, Some(true));
+    let _ = context.start_exec_cycle();
+    assert_eq!(context.registers.a, 0x01);
+    assert!(context.registers.read_flag(Flag::Carry));
+    assert!(context.registers.read_flag(Flag::HalfCarry));
+    assert!(!context.registers.read_flag(Flag::Zero));
+    Ok(())
+}
+
+#[test]
+fn rla() -> Result<(), EmuError> {
+    let mut context = get_mock_context(vec![0x17, 0xDD]);
+    context.registers.a = 0x80;
+    let _ = context.registers.set_flag(Flag::Carry, Some(false));
+    let _ = context.start_exec_cycle();
+    assert_eq!(context.registers.a, 0x00);
+    assert_eq!(context.clock.m_cycles, 2);
+    assert!(context.registers.read_flag(Flag::Carry));
+    // Unlike rl a, zero is always reset
+    assert!(!context.registers.read_flag(Flag::Zero));
+    Ok(())
+}
+
+#[test]
+fn daa() -> Result<(), EmuError> {
+    // (a, b, add/sub, expected a, expected carry)
+    let cases = [
+        (0x45, 0x38, 0x80, 0x83, false),
+        (0x09, 0x08, 0x80, 0x17, false),
+        (0x90, 0x90, 0x80, 0x80, true),
+        (0x10, 0x01, 0x90, 0x09, false),
+        (0x10, 0x20, 0x90, 0x90, true),
+        (0x50, 0x50, 0x90, 0x00, false),
+    ];
+    for (a, b, opcode, expected, carry) in cases {
+        let mut context = get_mock_context(vec![opcode, 0x27, 0xDD]);
+        context.registers.a = a;
+        context.registers.b = b;
+        let _ = context.start_exec_cycle();
+        assert_eq!(context.registers.a, expected);
+        assert_eq!(context.registers.read_flag(Flag::Carry), carry);
+        assert_eq!(context.registers.read_flag(Flag::Zero), expected == 0);
+        assert!(!context.registers.read_flag(Flag::HalfCarry));
+    }
+    Ok(())
+}
+
+#[test]
+fn cpl() -> Result<(), EmuError> {
+    let mut context = get_mock_context(vec![0x2F, 0xDD]);
+    context.registers.a = 0x35;
+    let _ = context.start_exec_cycle();
+    assert_eq!(context.registers.a, 0xCA);
+    assert!(context.registers.read_flag(Flag::Subtract));
+    assert!(context.registers.read_flag(Flag::HalfCarry));
+    Ok(())
+}
+
+#[test]
+fn scf_ccf() -> Result<(), EmuError> {
+    let mut context = get_mock_context(vec![0x37, 0xDD, 0x3F, 0xDD]);
+    let _ = context.registers.set_flag(Flag::Carry, Some(false));
+    let _ = context.start_exec_cycle();
+    assert!(context.registers.read_flag(Flag::Carry));
+    assert!(!context.registers.read_flag(Flag::Subtract));
+    assert!(!context.registers.read_flag(Flag::HalfCarry));
+
+    context.registers.pc = 2;
+    let _ = context.start_exec_cycle();
+    assert!(!context.registers.read_flag(Flag::Carry));
+    Ok(())
+}
@@ -0,0 +1,110 @@
+use redgb::{
+    cpu::{
+        alu,
+        clock::Clock,
+        cpu_context::CpuContext,
+        reg_file::{Flag, Modes, RegFile},
+    },
+    error::EmuError,
+    mem::map::MemoryMap,
+    rom::rom_info::ROMInfo,
+};
+
+fn get_mock_context(rom: Vec<u8>) -> CpuContext {
+    let mut context = CpuContext::init(
+        RegFile::new(Modes::CGBDMG),
+        MemoryMap::init_rom(rom, ROMInfo::default()),
+        Clock::default(),
+    );
+    context.registers.pc = 0;
+    context
+}
+
+#[test]
+fn rlc_b() -> Result<(), EmuError> {
+    let mut context = get_mock_context(vec![0xCB, 0x00, 0xDD]);
+    context.registers.b = 0x85;
+    let _ = context.start_exec_cycle();
+    assert_eq!(context.registers.b, 0x0B);
+    assert_eq!(context.clock.m_cycles, 3);
+    assert!(context.registers.read_flag(Flag::Carry));
+    assert!(!context.registers.read_flag(Flag::HalfCarry));
+    assert!(!context.registers.read_flag(Flag::Zero));
+    assert!(!context.registers.read_flag(Flag::Subtract));
+    Ok(())
+}
+
+#[test]
+fn rr_c() -> Result<(), EmuError> {
+    let mut context = get_mock_context(vec![0xCB, 0x19, 0xDD]);
+    context.registers.c = 0x01;
+    let _ = context.registers.set_flag(Flag::Carry, Some(false));
+    let _ = context.start_exec_cycle();
+    assert_eq!(context.registers.c, 0x00);
+    assert!(context.registers.read_flag(Flag::Carry));
+    assert!(context.registers.read_flag(Flag::Zero));
+    Ok(())
+}
+
+#[test]
+fn sra_a() -> Result<(), EmuError> {
+    let mut context = get_mock_context(vec![0xCB, 0x2F, 0xDD]);
+    context.registers.a = 0x8A;
+    let _ = context.start_exec_cycle();
+    assert_eq!(context.registers.a, 0xC5);
+    assert!(!context.registers.read_flag(Flag::Carry));
+    assert!(!context.registers.read_flag(Flag::Zero));
+    Ok(())
+}
+
+#[test]
+fn swap_hl() -> Result<(), EmuError> {
+    let mut context = get_mock_context(vec![0xCB, 0x36, 0xDD]);
+    alu::write_u16(&mut context.registers.l, &mut context.registers.h, 0xC001);
+    context.memory.write(&mut context.clock, 0xC001, 0xF1)?;
+    let _ = context.start_exec_cycle();
+    assert_eq!(context.clock.m_cycles, 6);
+    assert_eq!(context.memory.read(&mut context.clock, 0xC001)?, 0x1F);
+    assert!(!context.registers.read_flag(Flag::Carry));
+    assert!(!context.registers.read_flag(Flag::Zero));
+    Ok(())
+}
+
+#[test]
+fn bit_7_h() -> Result<(), EmuError> {
+    let mut context = get_mock_context(vec![0xCB, 0x7C, 0xDD]);
+    context.registers.h = 0x7F;
+    let _ = context.registers.set_flag(Flag::Carry, Some(true));
+    let _ = context.start_exec_cycle();
+    assert_eq!(context.clock.m_cycles, 3);
+    assert!(context.registers.read_flag(Flag::Zero));
+    assert!(context.registers.read_flag(Flag::HalfCarry));
+    assert!(context.registers.read_flag(Flag::Carry));
+    assert!(!context.registers.read_flag(Flag::Subtract));
+    Ok(())
+}
+
+#[test]
+fn bit_0_hl() -> Result<(), EmuError> {
+    let mut context = get_mock_context(vec![0xCB, 0x46, 0xDD]);
+    alu::write_u16(&mut context.registers.l, &mut context.registers.h, 0xC001);
+    context.memory.write(&mut context.clock, 0xC001, 0x01)?;
+    let _ = context.start_exec_cycle();
+    assert_eq!(context.clock.m_cycles, 5);
+    assert!(!context.registers.read_flag(Flag::Zero));
+    Ok(())
+}
+
+#[test]
+fn res_set_hl() -> Result<(), EmuError> {
+    // res 0, [hl] then set 7, [hl]
+    let mut context = get_mock_context(vec![0xCB, 0x86, 0xCB, 0xFE, 0xDD]);
+    alu::write_u16(&mut context.registers.l, &mut context.registers.h, 0xC001);
+    context.memory.write(&mut context.clock, 0xC001, 0x01)?;
+    let f = context.registers.f;
+    let _ = context.start_exec_cycle();
+    assert_eq!(context.clock.m_cycles, 10);
+    assert_eq!(context.memory.read(&mut context.clock, 0xC001)?, 0x80);
+    assert_eq!(context.registers.f, f);
+    Ok(())
+}
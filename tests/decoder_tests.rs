@@ -0,0 +1,111 @@
+use redgb::{
+    cpu::{
+        alu,
+        clock::Clock,
+        cpu_context::CpuContext,
+        decoder::{CB_OPCODES, OPCODES, Op},
+        reg_file::{Modes, RegFile},
+    },
+    error::EmuError,
+    mem::map::MemoryMap,
+    rom::rom_info::ROMInfo,
+};
+
+fn get_mock_context(rom: Vec<u8>) -> CpuContext {
+    let mut context = CpuContext::init(
+        RegFile::new(Modes::CGBDMG),
+        MemoryMap::init_rom(rom, ROMInfo::default()),
+        Clock::default(),
+    );
+    context.registers.pc = 0;
+    context
+}
+
+#[test]
+fn illegal_opcodes() {
+    let illegal: Vec<u8> = OPCODES
+        .iter()
+        .filter(|instruction| instruction.op == Op::Illegal)
+        .map(|instruction| instruction.opcode)
+        .collect();
+    assert_eq!(
+        illegal,
+        vec![
+            0xD3, 0xDB, 0xDD, 0xE3, 0xE4, 0xEB, 0xEC, 0xED, 0xF4, 0xFC, 0xFD
+        ]
+    );
+    assert!(CB_OPCODES.iter().all(|instruction| instruction.prefixed));
+}
+
+#[test]
+fn disassembly() {
+    assert_eq!(OPCODES[0x00].to_string(), "nop");
+    assert_eq!(OPCODES[0x2A].to_string(), "ld a, [hl+]");
+    assert_eq!(OPCODES[0x36].to_string(), "ld [hl], n8");
+    assert_eq!(OPCODES[0x20].to_string(), "jr nz, e8");
+    assert_eq!(OPCODES[0xE2].to_string(), "ldh [c], a");
+    assert_eq!(OPCODES[0xF1].to_string(), "pop af");
+    assert_eq!(OPCODES[0xFF].to_string(), "rst $38");
+    assert_eq!(OPCODES[0xF8].to_string(), "ld hl, sp + e8");
+    assert_eq!(CB_OPCODES[0x7C].to_string(), "bit 7, h");
+    assert_eq!(CB_OPCODES[0x36].to_string(), "swap [hl]");
+}
+
+#[test]
+fn lengths_and_cycles() {
+    assert_eq!((OPCODES[0xCD].length, OPCODES[0xCD].cycles), (3, 6));
+    assert_eq!(OPCODES[0xC4].branch_cycles, Some(6));
+    assert_eq!(OPCODES[0xC0].cycles, 2);
+    assert_eq!(OPCODES[0xC0].branch_cycles, Some(5));
+    assert_eq!(OPCODES[0x34].cycles, 3);
+    assert_eq!(CB_OPCODES[0x46].cycles, 3);
+    assert_eq!(CB_OPCODES[0x06].cycles, 4);
+    assert_eq!(CB_OPCODES[0x06].length, 2);
+}
+
+/// Executes every legal opcode with all flags clear and all flags set, so conditional
+/// branches go both ways, and checks the cycles against the table
+#[test]
+fn timing() -> Result<(), EmuError> {
+    let legal = OPCODES
+        .iter()
+        .filter(|instruction| !matches!(instruction.op, Op::Illegal | Op::Prefix));
+    let prefixed = CB_OPCODES.iter();
+    for (instruction, flags) in legal
+        .chain(prefixed)
+        .flat_map(|instruction| [(instruction, 0x00), (instruction, 0xF0)])
+    {
+        let rom = if instruction.prefixed {
+            vec![0xCB, instruction.opcode]
+        } else {
+            // n8 = 0x00, n16 = 0xC000
+            vec![instruction.opcode, 0x00, 0xC0]
+        };
+        let mut context = get_mock_context(rom);
+        alu::write_u16(&mut context.registers.l, &mut context.registers.h, 0xC000);
+        alu::write_u16(&mut context.registers.c, &mut context.registers.b, 0xC000);
+        alu::write_u16(&mut context.registers.e, &mut context.registers.d, 0xC000);
+        context.registers.sp = 0xD000;
+        context.registers.f = flags;
+        context.step()?;
+        assert!(
+            instruction.takes(context.clock.m_cycles),
+            "{instruction} took {} with F={flags:#04X}",
+            context.clock.m_cycles
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn illegal_opcode_error() {
+    let mut context = get_mock_context(vec![0x00, 0xDD]);
+    context.step().unwrap();
+    assert_eq!(
+        context.step(),
+        Err(EmuError::IllegalOpcode {
+            pc: 0x1,
+            opcode: 0xDD
+        })
+    );
+}
@@ -0,0 +1,243 @@
+use redgb::{
+    cpu::{
+        clock::Clock,
+        cpu_context::{CpuContext, RunState},
+        reg_file::{Modes, RegFile},
+    },
+    error::EmuError,
+    mem::map::MemoryMap,
+    rom::rom_info::ROMInfo,
+};
+
+/// Map with the LCD off so OAM is always accessible, WRAM page 0xC1 counts up and 0xC2 down
+fn get_mock_map() -> Result<(MemoryMap, Clock), EmuError> {
+    let mut clock = Clock::default();
+    let mut memory = MemoryMap::new(vec![0; 0x8000], ROMInfo::default(), Modes::DMG);
+    memory.write(&mut clock, 0xFF40, 0x00)?;
+    for i in 0..0xA0u16 {
+        memory.write(&mut clock, 0xC100 + i, i as u8)?;
+        memory.write(&mut clock, 0xC200 + i, 0xFF - i as u8)?;
+    }
+    Ok((memory, clock))
+}
+
+fn idle(memory: &mut MemoryMap, clock: &mut Clock, m_cycles: u64) {
+    for _ in 0..m_cycles {
+        memory.tick(clock);
+    }
+}
+
+#[test]
+fn oam_dma() -> Result<(), EmuError> {
+    let (mut memory, mut clock) = get_mock_map()?;
+    memory.write(&mut clock, 0xFF80, 0x42)?;
+    memory.write(&mut clock, 0xFF46, 0xC1)?;
+    let start = clock.m_cycles;
+    // 1 M-C before the first byte is copied
+    assert_eq!(memory.read(&mut clock, 0xC100)?, 0x00);
+    assert_eq!(memory.read(&mut clock, 0xC101)?, 0xFF);
+    assert_eq!(memory.read(&mut clock, 0xFE00)?, 0xFF);
+    // HRAM and the IO registers stay accessible
+    assert_eq!(memory.read(&mut clock, 0xFF80)?, 0x42);
+    assert_eq!(memory.read(&mut clock, 0xFF46)?, 0xC1);
+    // Writes are dropped
+    memory.write(&mut clock, 0xC100, 0x12)?;
+    let elapsed = clock.m_cycles - start;
+    idle(&mut memory, &mut clock, 160 - elapsed);
+    assert_eq!(memory.read(&mut clock, 0xC100)?, 0x00);
+    assert_eq!(clock.m_cycles - start, 161);
+    for i in 0..0xA0 {
+        assert_eq!(memory.read(&mut clock, 0xFE00 + i)?, i as u8);
+    }
+    Ok(())
+}
+
+#[test]
+fn oam_dma_restart() -> Result<(), EmuError> {
+    let (mut memory, mut clock) = get_mock_map()?;
+    memory.write(&mut clock, 0xFF46, 0xC1)?;
+    idle(&mut memory, &mut clock, 50);
+    memory.write(&mut clock, 0xFF46, 0xC2)?;
+    // The first transfer keeps the bus busy until the second one takes over
+    assert_eq!(memory.read(&mut clock, 0xC100)?, 0xFF);
+    idle(&mut memory, &mut clock, 159);
+    assert_eq!(memory.read(&mut clock, 0xC100)?, 0x00);
+    for i in 0..0xA0 {
+        assert_eq!(memory.read(&mut clock, 0xFE00 + i)?, 0xFF - i as u8);
+    }
+    Ok(())
+}
+
+#[test]
+fn oam_dma_echo_source() -> Result<(), EmuError> {
+    let (mut memory, mut clock) = get_mock_map()?;
+    // 0xE100 is the echo of 0xC100
+    memory.write(&mut clock, 0xFF46, 0xE1)?;
+    idle(&mut memory, &mut clock, 161);
+    assert_eq!(memory.read(&mut clock, 0xFF46)?, 0xE1);
+    for i in 0..0xA0 {
+        assert_eq!(memory.read(&mut clock, 0xFE00 + i)?, i as u8);
+    }
+    // Above the echo the source still wraps into WRAM
+    memory.write(&mut clock, 0xDE00, 0x34)?;
+    memory.write(&mut clock, 0xFF46, 0xFE)?;
+    idle(&mut memory, &mut clock, 161);
+    assert_eq!(memory.read(&mut clock, 0xFE00)?, 0x34);
+    Ok(())
+}
+
+#[test]
+fn prohibited_area_writes_ignored() -> Result<(), EmuError> {
+    let (mut memory, mut clock) = get_mock_map()?;
+    // OAM clearing loops that run past the end of OAM
+    for addr in 0xFE00..=0xFEFF {
+        memory.write(&mut clock, addr, 0xAA)?;
+    }
+    assert_eq!(memory.read(&mut clock, 0xFE9F)?, 0xAA);
+    assert_eq!(memory.read(&mut clock, 0xFEA0)?, 0x00);
+    Ok(())
+}
+
+/// CGB map with the source at 0xC100 (from `get_mock_map`) and the destination at 0x8800
+fn get_hdma_map(rom: Vec<u8>, lcd: bool) -> Result<(MemoryMap, Clock), EmuError> {
+    let mut clock = Clock::default();
+    let mut memory = MemoryMap::new(rom, ROMInfo::default(), Modes::CGB);
+    memory.write(&mut clock, 0xFF40, 0x00)?;
+    for i in 0..0xA0u16 {
+        memory.write(&mut clock, 0xC100 + i, i as u8)?;
+    }
+    if lcd {
+        memory.write(&mut clock, 0xFF40, 0x91)?;
+    }
+    // The low nibbles are ignored
+    for (addr, value) in [
+        (0xFF51, 0xC1),
+        (0xFF52, 0x0F),
+        (0xFF53, 0xE8),
+        (0xFF54, 0x0F),
+    ] {
+        memory.write(&mut clock, addr, value)?;
+    }
+    Ok((memory, clock))
+}
+
+fn assert_vram(memory: &mut MemoryMap, clock: &mut Clock, len: u16) -> Result<(), EmuError> {
+    for i in 0..len {
+        assert_eq!(memory.read(clock, 0x8800 + i)?, i as u8);
+    }
+    assert_eq!(memory.read(clock, 0x8800 + len)?, 0);
+    Ok(())
+}
+
+#[test]
+fn general_purpose_hdma() -> Result<(), EmuError> {
+    // ld a, 0x02 | ldh [0x55], a
+    let mut rom = vec![0x3E, 0x02, 0xE0, 0x55, 0xDD];
+    rom.resize(0x8000, 0);
+    let (memory, _) = get_hdma_map(rom, false)?;
+    let mut context = CpuContext::init(RegFile::new(Modes::CGB), memory, Clock::default());
+    context.registers.pc = 0;
+    context.step()?;
+    context.step()?;
+    assert_eq!(context.clock.m_cycles, 5);
+    // 3 blocks of 8 M-C each, the CPU doesn't run in between
+    for _ in 0..24 {
+        context.step()?;
+    }
+    assert_eq!(context.clock.m_cycles, 5 + 24);
+    assert_eq!(context.registers.pc, 4);
+    assert_eq!(context.memory.read(&mut context.clock, 0xFF55)?, 0xFF);
+    assert_vram(&mut context.memory, &mut context.clock, 0x30)
+}
+
+#[test]
+fn hblank_hdma() -> Result<(), EmuError> {
+    let (mut memory, mut clock) = get_hdma_map(vec![0; 0x8000], true)?;
+    memory.write(&mut clock, 0xFF55, 0x82)?;
+    assert_eq!(memory.read(&mut clock, 0xFF55)?, 0x02);
+    // Nothing is copied before the first HBlank
+    assert!(!memory.step_hdma(&mut clock));
+    while memory.read(&mut clock, 0xFF41)? & 0x3 != 0 {}
+    let start = clock.m_cycles;
+    while memory.step_hdma(&mut clock) {}
+    assert_eq!(clock.m_cycles - start, 8);
+    assert_eq!(memory.read(&mut clock, 0xFF55)?, 0x01);
+
+    // 1 block per line
+    while memory.read(&mut clock, 0xFF44)? != 1 {}
+    while memory.read(&mut clock, 0xFF41)? & 0x3 != 0 {}
+    while memory.step_hdma(&mut clock) {}
+    assert_eq!(memory.read(&mut clock, 0xFF55)?, 0x00);
+
+    // Cancelled with a block left
+    memory.write(&mut clock, 0xFF55, 0x00)?;
+    assert_eq!(memory.read(&mut clock, 0xFF55)?, 0x80);
+    while memory.read(&mut clock, 0xFF44)? != 3 {}
+    assert!(!memory.step_hdma(&mut clock));
+    memory.write(&mut clock, 0xFF40, 0x00)?;
+    assert_vram(&mut memory, &mut clock, 0x20)
+}
+
+#[test]
+fn hblank_hdma_paused_while_halted() -> Result<(), EmuError> {
+    // halt
+    let mut rom = vec![0x76];
+    rom.resize(0x8000, 0);
+    let (mut memory, mut clock) = get_hdma_map(rom, true)?;
+    memory.write(&mut clock, 0xFFFF, 0x00)?;
+    memory.write(&mut clock, 0xFF55, 0x82)?;
+    let mut context = CpuContext::init(RegFile::new(Modes::CGB), memory, clock);
+    context.registers.pc = 0;
+    while context.state != RunState::Halted {
+        context.step()?;
+    }
+    let hdma5 = context.memory.read(&mut context.clock, 0xFF55)?;
+    // 10 lines of HBlanks go by without a copy
+    for _ in 0..1140 {
+        context.step()?;
+    }
+    assert_eq!(context.state, RunState::Halted);
+    assert_eq!(context.memory.read(&mut context.clock, 0xFF55)?, hdma5);
+
+    // Resumes on the HBlanks after waking up
+    context.memory.write(&mut context.clock, 0xFF0F, 0x01)?;
+    context.memory.write(&mut context.clock, 0xFFFF, 0x01)?;
+    for _ in 0..1140 {
+        context.step()?;
+    }
+    assert_eq!(context.state, RunState::Running);
+    assert_eq!(context.memory.read(&mut context.clock, 0xFF55)?, 0xFF);
+    context.memory.write(&mut context.clock, 0xFF40, 0x00)?;
+    assert_vram(&mut context.memory, &mut context.clock, 0x30)
+}
+
+#[test]
+fn hdma_stops_at_vram_end() -> Result<(), EmuError> {
+    let (mut memory, mut clock) = get_hdma_map(vec![0; 0x8000], false)?;
+    memory.write(&mut clock, 0xFF53, 0x1F)?;
+    memory.write(&mut clock, 0xFF54, 0xF0)?;
+    memory.write(&mut clock, 0xFF55, 0x01)?;
+    let start = clock.m_cycles;
+    while memory.step_hdma(&mut clock) {}
+    // Only the block that fits is copied
+    assert_eq!(clock.m_cycles - start, 8);
+    assert_eq!(memory.read(&mut clock, 0xFF55)?, 0xFF);
+    for i in 0..0x10 {
+        assert_eq!(memory.read(&mut clock, 0x9FF0 + i)?, i as u8);
+    }
+    assert_eq!(memory.read(&mut clock, 0x8000)?, 0);
+    Ok(())
+}
+
+#[test]
+fn hdma_double_speed() -> Result<(), EmuError> {
+    let (mut memory, mut clock) = get_hdma_map(vec![0; 0x8000], false)?;
+    memory.write(&mut clock, 0xFF4D, 0x01)?;
+    memory.switch_speed();
+    memory.write(&mut clock, 0xFF55, 0x00)?;
+    let start = clock.m_cycles;
+    while memory.step_hdma(&mut clock) {}
+    // Same real time, twice the M-Cs
+    assert_eq!(clock.m_cycles - start, 16);
+    assert_vram(&mut memory, &mut clock, 0x10)
+}
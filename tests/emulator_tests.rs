@@ -0,0 +1,54 @@
+use std::{fs, path::PathBuf};
+
+use redgb::{
+    emulator::{self, Frontend},
+    error::EmuError,
+    mem::{map::MemoryMap, save::SaveFile},
+    ppu::Renderer,
+    rom::{cartridge_type::CartridgeType, rom_info::ROMInfo},
+};
+
+/// Quits after `frames` frames
+struct MockFrontend {
+    frames: u32,
+}
+
+impl Frontend for MockFrontend {
+    fn frame(&mut self, _memory: &mut MemoryMap) -> bool {
+        self.frames -= 1;
+        self.frames > 0
+    }
+}
+
+#[test]
+fn quit_flushes_save() -> Result<(), EmuError> {
+    // ld a, 0x0A | ld [0x0000], a | ld a, 0x42 | ld [0xA000], a | jr -2
+    let mut rom = vec![0; 0x8000];
+    rom[0x100..0x10C].copy_from_slice(&[
+        0x3E, 0x0A, 0xEA, 0x00, 0x00, 0x3E, 0x42, 0xEA, 0x00, 0xA0, 0x18, 0xFE,
+    ]);
+    let info = ROMInfo {
+        // MBC1+RAM+BATTERY
+        cartridge_type: CartridgeType::from_code(0x03),
+        rom_banks: 2,
+        mem_banks: 1,
+        ..ROMInfo::default()
+    };
+    let path: PathBuf = std::env::temp_dir().join(format!("redgb_quit_{}.sav", std::process::id()));
+    let _ = fs::remove_file(&path);
+    let mut frontend = MockFrontend { frames: 3 };
+    emulator::init_emulation(
+        rom,
+        info,
+        Some(SaveFile::new(path.clone())),
+        Renderer::Scanline,
+        false,
+        false,
+        &mut frontend,
+    )?;
+    assert_eq!(frontend.frames, 0);
+    let data = fs::read(&path).expect("the save is written on quit");
+    assert_eq!(data[0], 0x42);
+    let _ = fs::remove_file(&path);
+    Ok(())
+}
@@ -0,0 +1,162 @@
+use redgb::{
+    cpu::{
+        clock::Clock,
+        cpu_context::{CpuContext, RunState},
+        interrupts::Interrupt,
+        reg_file::{Modes, RegFile},
+    },
+    error::EmuError,
+    joypad::Button,
+    mem::map::MemoryMap,
+    rom::rom_info::ROMInfo,
+};
+
+fn get_mock_context(rom: Vec<u8>) -> CpuContext {
+    let mut context = CpuContext::init(
+        RegFile::new(Modes::CGBDMG),
+        MemoryMap::init_rom(rom, ROMInfo::default()),
+        Clock::default(),
+    );
+    context.registers.pc = 0;
+    context
+}
+
+/// ROM with `code` at 0x0 and an illegal opcode on every interrupt vector
+fn get_mock_rom(code: &[u8]) -> Vec<u8> {
+    let mut rom = vec![0xDD; 0x100];
+    rom[..code.len()].copy_from_slice(code);
+    rom
+}
+
+#[test]
+fn halt_until_interrupt() -> Result<(), EmuError> {
+    let mut context = get_mock_context(get_mock_rom(&[0x76, 0x00]));
+    context.memory.write(&mut context.clock, 0xFFFF, 0x01)?;
+    // The boot ROM leaves VBlank requested
+    context.memory.write(&mut context.clock, 0xFF0F, 0x00)?;
+    context.registers.ime = true;
+    context.clock.m_cycles = 0;
+    context.step()?;
+    assert_eq!(context.state, RunState::Halted);
+    for _ in 0..10 {
+        context.step()?;
+    }
+    assert_eq!(context.state, RunState::Halted);
+    assert_eq!(context.clock.m_cycles, 11);
+
+    context.memory.request_interrupt(Interrupt::VBlank);
+    let _ = context.start_exec_cycle();
+    assert_eq!(context.state, RunState::Running);
+    assert_eq!(context.registers.pc, 0x41);
+    assert_eq!(context.memory.read(&mut context.clock, 0xFFFC)?, 0x01);
+    Ok(())
+}
+
+#[test]
+fn halt_ime_disabled() -> Result<(), EmuError> {
+    // halt, inc a
+    let mut context = get_mock_context(get_mock_rom(&[0x76, 0x3C]));
+    context.memory.write(&mut context.clock, 0xFFFF, 0x04)?;
+    context.registers.a = 0;
+    context.step()?;
+    assert_eq!(context.state, RunState::Halted);
+    context.memory.request_interrupt(Interrupt::Timer);
+    // Resumes without servicing the interrupt
+    let _ = context.start_exec_cycle();
+    assert_eq!(context.registers.a, 1);
+    assert_eq!(context.registers.pc, 3);
+    assert!(context.memory.interrupt_requested(Interrupt::Timer));
+    Ok(())
+}
+
+#[test]
+fn halt_bug() -> Result<(), EmuError> {
+    // halt, inc a
+    let mut context = get_mock_context(get_mock_rom(&[0x76, 0x3C]));
+    context.memory.write(&mut context.clock, 0xFFFF, 0x04)?;
+    context.memory.request_interrupt(Interrupt::Timer);
+    context.registers.a = 0;
+    let _ = context.start_exec_cycle();
+    assert_eq!(context.state, RunState::Running);
+    // inc a is executed twice
+    assert_eq!(context.registers.a, 2);
+    assert_eq!(context.registers.pc, 3);
+    Ok(())
+}
+
+#[test]
+fn stop() -> Result<(), EmuError> {
+    let mut context = get_mock_context(get_mock_rom(&[0x10, 0x00, 0x00]));
+    context.step()?;
+    assert_eq!(context.state, RunState::Stopped);
+    assert_eq!(context.registers.pc, 2);
+    // Time passes but DIV, reset by stop, stays frozen
+    for _ in 0..100 {
+        context.step()?;
+    }
+    assert_eq!(context.state, RunState::Stopped);
+    assert_eq!(context.clock.m_cycles, 101);
+    assert_eq!(context.memory.read(&mut context.clock, 0xFF04)?, 0x00);
+
+    // A requested interrupt alone doesn't wake it up
+    context.memory.request_interrupt(Interrupt::Joypad);
+    context.step()?;
+    assert_eq!(context.state, RunState::Stopped);
+
+    // Neither does a press on a line that isn't selected
+    context.memory.write(&mut context.clock, 0xFF00, 0x10)?;
+    context.memory.set_button(Button::Up, true);
+    context.step()?;
+    assert_eq!(context.state, RunState::Stopped);
+    context.memory.set_button(Button::Start, true);
+    context.step()?;
+    assert_eq!(context.state, RunState::Running);
+    assert_eq!(context.registers.pc, 3);
+    Ok(())
+}
+
+#[test]
+fn stop_ignores_earlier_presses() -> Result<(), EmuError> {
+    let mut context = get_mock_context(get_mock_rom(&[0x10, 0x00, 0x00]));
+    context.memory.write(&mut context.clock, 0xFF00, 0x10)?;
+    context.memory.set_button(Button::A, true);
+    context.step()?;
+    context.step()?;
+    assert_eq!(context.state, RunState::Stopped);
+    Ok(())
+}
+
+#[test]
+fn stop_speed_switch() -> Result<(), EmuError> {
+    let mut context = get_mock_context(get_mock_rom(&[0x10, 0x00]));
+    context.memory.write(&mut context.clock, 0xFF4D, 0x01)?;
+    assert_eq!(context.memory.read(&mut context.clock, 0xFF4D)?, 0x7F);
+    context.step()?;
+    assert!(context.memory.double_speed());
+    // The CPU is paused while the clocks settle
+    let start = context.clock.m_cycles;
+    while context.state != RunState::Running {
+        context.step()?;
+    }
+    assert_eq!(context.clock.m_cycles - start, 2050);
+    assert_eq!(context.memory.read(&mut context.clock, 0xFF4D)?, 0xFE);
+
+    // And back to single speed
+    context.memory.write(&mut context.clock, 0xFF4D, 0x01)?;
+    context.registers.pc = 0;
+    context.step()?;
+    assert!(!context.memory.double_speed());
+    assert_eq!(context.memory.read(&mut context.clock, 0xFF4D)?, 0x7E);
+    Ok(())
+}
+
+#[test]
+fn fetch_wraps_past_ffff() -> Result<(), EmuError> {
+    let mut context = get_mock_context(get_mock_rom(&[0x00]));
+    // IE doubles as a nop when executed
+    context.memory.write(&mut context.clock, 0xFFFF, 0x00)?;
+    context.registers.pc = 0xFFFF;
+    context.step()?;
+    assert_eq!(context.registers.pc, 0x0000);
+    Ok(())
+}
@@ -0,0 +1,105 @@
+use redgb::{
+    cpu::{
+        clock::Clock,
+        cpu_context::CpuContext,
+        interrupts::Interrupt,
+        reg_file::{Modes, RegFile},
+    },
+    error::EmuError,
+    mem::map::MemoryMap,
+    rom::rom_info::ROMInfo,
+};
+
+fn get_mock_context(rom: Vec<u8>) -> CpuContext {
+    let mut context = CpuContext::init(
+        RegFile::new(Modes::CGBDMG),
+        MemoryMap::init_rom(rom, ROMInfo::default()),
+        Clock::default(),
+    );
+    context.registers.pc = 0;
+    context
+}
+
+/// ROM with `code` at 0x0 and an illegal opcode on every interrupt vector
+fn get_mock_rom(code: &[u8]) -> Vec<u8> {
+    let mut rom = vec![0xDD; 0x100];
+    rom[..code.len()].copy_from_slice(code);
+    rom
+}
+
+#[test]
+fn vblank_dispatch() -> Result<(), EmuError> {
+    let mut context = get_mock_context(get_mock_rom(&[0x00]));
+    context.memory.write(&mut context.clock, 0xFFFF, 0x01)?;
+    context.memory.request_interrupt(Interrupt::VBlank);
+    context.registers.ime = true;
+    context.clock.m_cycles = 0;
+    let _ = context.start_exec_cycle();
+    assert_eq!(context.registers.pc, 0x41);
+    assert_eq!(context.registers.sp, 0xFFFC);
+    assert!(!context.registers.ime);
+    assert_eq!(context.clock.m_cycles, 6);
+    assert_eq!(context.memory.read(&mut context.clock, 0xFF0F)?, 0xE0);
+    Ok(())
+}
+
+#[test]
+fn priority() -> Result<(), EmuError> {
+    let mut context = get_mock_context(get_mock_rom(&[0x00]));
+    context.memory.write(&mut context.clock, 0xFFFF, 0x1F)?;
+    context.memory.write(&mut context.clock, 0xFF0F, 0x14)?;
+    context.registers.ime = true;
+    let _ = context.start_exec_cycle();
+    assert_eq!(context.registers.pc, Interrupt::Timer.vector() + 1);
+    assert_eq!(context.memory.read(&mut context.clock, 0xFF0F)?, 0xF0);
+    Ok(())
+}
+
+#[test]
+fn disabled() -> Result<(), EmuError> {
+    let mut context = get_mock_context(get_mock_rom(&[0x00]));
+    context.memory.write(&mut context.clock, 0xFFFF, 0x01)?;
+    context.memory.request_interrupt(Interrupt::VBlank);
+    let _ = context.start_exec_cycle();
+    assert_eq!(context.registers.pc, 2);
+    assert_eq!(context.memory.pending_interrupts(), 0x01);
+    Ok(())
+}
+
+#[test]
+fn ei_delay() -> Result<(), EmuError> {
+    // ei, nop
+    let mut context = get_mock_context(get_mock_rom(&[0xFB, 0x00, 0x00]));
+    context.memory.write(&mut context.clock, 0xFFFF, 0x04)?;
+    context.memory.request_interrupt(Interrupt::Timer);
+    let _ = context.start_exec_cycle();
+    assert_eq!(context.registers.pc, 0x51);
+    // Return address points after the nop
+    assert_eq!(context.memory.read(&mut context.clock, 0xFFFC)?, 0x02);
+    Ok(())
+}
+
+#[test]
+fn ei_di() -> Result<(), EmuError> {
+    let mut context = get_mock_context(get_mock_rom(&[0xFB, 0xF3, 0x00]));
+    context.memory.write(&mut context.clock, 0xFFFF, 0x04)?;
+    context.memory.request_interrupt(Interrupt::Timer);
+    let _ = context.start_exec_cycle();
+    assert_eq!(context.registers.pc, 4);
+    assert!(!context.registers.ime);
+    Ok(())
+}
+
+#[test]
+fn reti() -> Result<(), EmuError> {
+    let mut context = get_mock_context(get_mock_rom(&[0xD9]));
+    context.registers.sp = 0xC000;
+    context.memory.write(&mut context.clock, 0xC000, 0x10)?;
+    context.memory.write(&mut context.clock, 0xC001, 0x00)?;
+    context.clock.m_cycles = 0;
+    let _ = context.start_exec_cycle();
+    assert_eq!(context.registers.pc, 0x11);
+    assert!(context.registers.ime);
+    assert_eq!(context.clock.m_cycles, 5);
+    Ok(())
+}
@@ -0,0 +1,135 @@
+use redgb::{
+    cpu::{clock::Clock, interrupts::Interrupt, reg_file::Modes},
+    error::EmuError,
+    joypad::Button,
+    mem::map::MemoryMap,
+    rom::rom_info::ROMInfo,
+};
+
+fn get_mock_map(mode: Modes) -> MemoryMap {
+    MemoryMap::new(vec![0; 0x8000], ROMInfo::default(), mode)
+}
+
+#[test]
+fn post_boot_values() -> Result<(), EmuError> {
+    let mut clock = Clock::default();
+    let mut memory = get_mock_map(Modes::DMG);
+    assert_eq!(memory.read(&mut clock, 0xFF00)?, 0xCF);
+    assert_eq!(memory.read(&mut clock, 0xFF02)?, 0x7E);
+    assert_eq!(memory.read(&mut clock, 0xFF04)?, 0xAB);
+    assert_eq!(memory.read(&mut clock, 0xFF07)?, 0xF8);
+    assert_eq!(memory.read(&mut clock, 0xFF0F)?, 0xE1);
+    assert_eq!(memory.read(&mut clock, 0xFF26)?, 0xF1);
+    assert_eq!(memory.read(&mut clock, 0xFF40)?, 0x91);
+    // OAM scan of line 0, LY=LYC
+    assert_eq!(memory.read(&mut clock, 0xFF41)?, 0x86);
+    assert_eq!(memory.read(&mut clock, 0xFF46)?, 0xFF);
+    assert_eq!(memory.read(&mut clock, 0xFF47)?, 0xFC);
+    assert_eq!(memory.read(&mut clock, 0xFF4D)?, 0xFF);
+
+    let mut memory = get_mock_map(Modes::CGB);
+    assert_eq!(memory.read(&mut clock, 0xFF02)?, 0x7C);
+    assert_eq!(memory.read(&mut clock, 0xFF46)?, 0x00);
+    assert_eq!(memory.read(&mut clock, 0xFF4D)?, 0x7E);
+    assert_eq!(memory.read(&mut clock, 0xFF4F)?, 0xFE);
+    assert_eq!(memory.read(&mut clock, 0xFF70)?, 0xF9);
+    Ok(())
+}
+
+#[test]
+fn read_masks() -> Result<(), EmuError> {
+    let mut clock = Clock::default();
+    let mut memory = get_mock_map(Modes::DMG);
+    memory.write(&mut clock, 0xFF07, 0x05)?;
+    assert_eq!(memory.read(&mut clock, 0xFF07)?, 0xFD);
+    memory.write(&mut clock, 0xFF0F, 0x00)?;
+    assert_eq!(memory.read(&mut clock, 0xFF0F)?, 0xE0);
+    // NR13 is write-only
+    memory.write(&mut clock, 0xFF13, 0x12)?;
+    assert_eq!(memory.read(&mut clock, 0xFF13)?, 0xFF);
+    memory.write(&mut clock, 0xFF11, 0x12)?;
+    assert_eq!(memory.read(&mut clock, 0xFF11)?, 0x3F);
+    // Unmapped
+    memory.write(&mut clock, 0xFF03, 0x12)?;
+    assert_eq!(memory.read(&mut clock, 0xFF03)?, 0xFF);
+    assert_eq!(memory.read(&mut clock, 0xFF4C)?, 0xFF);
+    Ok(())
+}
+
+#[test]
+fn read_only_bits() -> Result<(), EmuError> {
+    let mut clock = Clock::default();
+    let mut memory = get_mock_map(Modes::DMG);
+    // LY is read-only, the mode bits of STAT too
+    memory.write(&mut clock, 0xFF44, 0x12)?;
+    assert_eq!(memory.read(&mut clock, 0xFF44)?, 0x00);
+    memory.write(&mut clock, 0xFF41, 0x40)?;
+    assert_eq!(memory.read(&mut clock, 0xFF41)?, 0xC6);
+    // Any write resets DIV
+    memory.write(&mut clock, 0xFF04, 0x12)?;
+    assert_eq!(memory.read(&mut clock, 0xFF04)?, 0x00);
+    // The channel bits of NR52 are read-only
+    memory.write(&mut clock, 0xFF26, 0x80)?;
+    assert_eq!(memory.read(&mut clock, 0xFF26)?, 0xF1);
+    Ok(())
+}
+
+#[test]
+fn apu_power_off() -> Result<(), EmuError> {
+    let mut clock = Clock::default();
+    let mut memory = get_mock_map(Modes::DMG);
+    memory.write(&mut clock, 0xFF26, 0x00)?;
+    assert_eq!(memory.read(&mut clock, 0xFF26)?, 0x70);
+    assert_eq!(memory.read(&mut clock, 0xFF24)?, 0x00);
+    memory.write(&mut clock, 0xFF24, 0x77)?;
+    assert_eq!(memory.read(&mut clock, 0xFF24)?, 0x00);
+    // Wave RAM is still accessible
+    memory.write(&mut clock, 0xFF30, 0x12)?;
+    assert_eq!(memory.read(&mut clock, 0xFF30)?, 0x12);
+    memory.write(&mut clock, 0xFF26, 0x80)?;
+    memory.write(&mut clock, 0xFF24, 0x77)?;
+    assert_eq!(memory.read(&mut clock, 0xFF24)?, 0x77);
+    Ok(())
+}
+
+#[test]
+fn cgb_banks() -> Result<(), EmuError> {
+    let mut clock = Clock::default();
+    let mut memory = get_mock_map(Modes::CGB);
+    memory.write(&mut clock, 0xD000, 0x11)?;
+    memory.write(&mut clock, 0xFF70, 0x02)?;
+    assert_eq!(memory.read(&mut clock, 0xD000)?, 0x00);
+    memory.write(&mut clock, 0xFF70, 0x00)?;
+    assert_eq!(memory.read(&mut clock, 0xFF70)?, 0xF9);
+    assert_eq!(memory.read(&mut clock, 0xD000)?, 0x11);
+
+    memory.write(&mut clock, 0x8000, 0x22)?;
+    memory.write(&mut clock, 0xFF4F, 0x01)?;
+    assert_eq!(memory.read(&mut clock, 0x8000)?, 0x00);
+    assert_eq!(memory.read(&mut clock, 0xFF4F)?, 0xFF);
+
+    // DMG ignores both
+    let mut memory = get_mock_map(Modes::DMG);
+    memory.write(&mut clock, 0xD000, 0x11)?;
+    memory.write(&mut clock, 0xFF70, 0x02)?;
+    assert_eq!(memory.read(&mut clock, 0xD000)?, 0x11);
+    Ok(())
+}
+
+#[test]
+fn joypad() -> Result<(), EmuError> {
+    let mut clock = Clock::default();
+    let mut memory = get_mock_map(Modes::DMG);
+    memory.write(&mut clock, 0xFF0F, 0x00)?;
+    // Buttons selected, pressing the d-pad doesn't show up
+    memory.write(&mut clock, 0xFF00, 0x10)?;
+    memory.set_button(Button::Down, true);
+    assert_eq!(memory.read(&mut clock, 0xFF00)?, 0xDF);
+    assert!(!memory.interrupt_requested(Interrupt::Joypad));
+    memory.set_button(Button::Start, true);
+    assert_eq!(memory.read(&mut clock, 0xFF00)?, 0xD7);
+    assert!(memory.interrupt_requested(Interrupt::Joypad));
+    memory.write(&mut clock, 0xFF00, 0x20)?;
+    assert_eq!(memory.read(&mut clock, 0xFF00)?, 0xE7);
+    Ok(())
+}
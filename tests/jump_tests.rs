@@ -4,6 +4,7 @@ use redgb::{
         cpu_context::CpuContext,
         reg_file::{Flag, Modes, RegFile},
     },
+    error::EmuError,
     mem::map::MemoryMap,
     rom::rom_info::ROMInfo,
 };
@@ -19,7 +20,7 @@ fn get_mock_context(rom: Vec<u8>) -> CpuContext {
 }
 
 #[test]
-fn jmp() -> Result<(), String> {
+fn jmp() -> Result<(), EmuError> {
     let mut context = get_mock_context(vec![0xC3, 0x4, 0x0, 0xDD, 0xDD]);
     let _ = context.start_exec_cycle();
     assert_eq!(context.registers.pc, 5);
@@ -28,7 +29,7 @@ fn jmp() -> Result<(), String> {
 }
 
 #[test]
-fn jmp_nz() -> Result<(), String> {
+fn jmp_nz() -> Result<(), EmuError> {
     // Jumps
     let mut context = get_mock_context(vec![0xC2, 0x4, 0x0, 0xDD, 0xDD]);
     let _ = context.registers.set_flag(Flag::Zero, Some(false));
@@ -46,3 +47,14 @@ fn jmp_nz() -> Result<(), String> {
     assert_eq!(context.clock.m_cycles, 4);
     Ok(())
 }
+
+#[test]
+fn jr_negative() -> Result<(), EmuError> {
+    // jr -3 jumps back to the illegal opcode before it
+    let mut context = get_mock_context(vec![0x00, 0xDD, 0x18, 0xFD, 0xDD]);
+    context.registers.pc = 2;
+    let _ = context.start_exec_cycle();
+    assert_eq!(context.registers.pc, 2);
+    assert_eq!(context.clock.m_cycles, 4);
+    Ok(())
+}
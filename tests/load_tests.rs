@@ -3,8 +3,9 @@ use redgb::{
         alu,
         clock::Clock,
         cpu_context::CpuContext,
-        reg_file::{Flag, Modes, RegFile},
+        reg_file::{Modes, RegFile},
     },
+    error::EmuError,
     mem::map::MemoryMap,
     rom::rom_info::ROMInfo,
 };
@@ -20,7 +21,7 @@ fn get_mock_context(rom: Vec<u8>) -> CpuContext {
 }
 
 #[test]
-fn ld_b_l() -> Result<(), String> {
+fn ld_b_l() -> Result<(), EmuError> {
     let mut context = get_mock_context(vec![0x45, 0xDD]);
     let _ = context.start_exec_cycle();
     assert_eq!(context.registers.l, context.registers.b);
@@ -29,7 +30,7 @@ fn ld_b_l() -> Result<(), String> {
 }
 
 #[test]
-fn ld_b_hl() -> Result<(), String> {
+fn ld_b_hl() -> Result<(), EmuError> {
     let mut context = get_mock_context(vec![0x46, 0xDD]);
     let _ = context.memory.write(&mut context.clock, 0xC001, 0xB1);
     alu::write_u16(&mut context.registers.l, &mut context.registers.h, 0xC001);
@@ -49,7 +50,7 @@ fn ld_b_hl() -> Result<(), String> {
 }
 
 #[test]
-fn ld_hl_a() -> Result<(), String> {
+fn ld_hl_a() -> Result<(), EmuError> {
     let mut context = get_mock_context(vec![0x77, 0xDD]);
     context.registers.a = 0xB1;
     alu::write_u16(&mut context.registers.l, &mut context.registers.h, 0xC001);
@@ -69,7 +70,7 @@ fn ld_hl_a() -> Result<(), String> {
 }
 
 #[test]
-fn ld_hl_n8() -> Result<(), String> {
+fn ld_hl_n8() -> Result<(), EmuError> {
     let mut context = get_mock_context(vec![0x36, 0x67, 0xDD]);
     alu::write_u16(&mut context.registers.l, &mut context.registers.h, 0xC001);
     let _ = context.start_exec_cycle();
@@ -88,7 +89,7 @@ fn ld_hl_n8() -> Result<(), String> {
 }
 
 #[test]
-fn ld_e_n8() -> Result<(), String> {
+fn ld_e_n8() -> Result<(), EmuError> {
     let mut context = get_mock_context(vec![0x1E, 0x67, 0xDD]);
     let _ = context.start_exec_cycle();
     assert_eq!(context.registers.e, 0x67);
@@ -97,7 +98,7 @@ fn ld_e_n8() -> Result<(), String> {
 }
 
 #[test]
-fn ld_de_n16() -> Result<(), String> {
+fn ld_de_n16() -> Result<(), EmuError> {
     let mut context = get_mock_context(vec![0x11, 0x34, 0x12, 0xDD]);
     let _ = context.start_exec_cycle();
     assert_eq!(
@@ -109,7 +110,7 @@ fn ld_de_n16() -> Result<(), String> {
 }
 
 #[test]
-fn ld_sp_n16() -> Result<(), String> {
+fn ld_sp_n16() -> Result<(), EmuError> {
     let mut context = get_mock_context(vec![0x31, 0x34, 0x12, 0xDD]);
     let _ = context.start_exec_cycle();
     assert_eq!(context.registers.sp, 0x1234);
@@ -118,7 +119,7 @@ fn ld_sp_n16() -> Result<(), String> {
 }
 
 #[test]
-fn ld_bcmem_a() -> Result<(), String> {
+fn ld_bcmem_a() -> Result<(), EmuError> {
     let mut context = get_mock_context(vec![0x02, 0xDD]);
     context.registers.a = 10;
     alu::write_u16(&mut context.registers.c, &mut context.registers.b, 0xC001);
@@ -129,7 +130,7 @@ fn ld_bcmem_a() -> Result<(), String> {
 }
 
 #[test]
-fn ld_hlimem_a() -> Result<(), String> {
+fn ld_hlimem_a() -> Result<(), EmuError> {
     let mut context = get_mock_context(vec![0x22, 0xDD]);
     context.registers.a = 10;
     alu::write_u16(&mut context.registers.l, &mut context.registers.h, 0xC001);
@@ -144,7 +145,7 @@ fn ld_hlimem_a() -> Result<(), String> {
 }
 
 #[test]
-fn ld_hldmem_a() -> Result<(), String> {
+fn ld_hldmem_a() -> Result<(), EmuError> {
     let mut context = get_mock_context(vec![0x32, 0xDD]);
     context.registers.a = 10;
     alu::write_u16(&mut context.registers.l, &mut context.registers.h, 0xC001);
@@ -159,7 +160,7 @@ fn ld_hldmem_a() -> Result<(), String> {
 }
 
 #[test]
-fn ld_a_demem() -> Result<(), String> {
+fn ld_a_demem() -> Result<(), EmuError> {
     let mut context = get_mock_context(vec![0x1A, 0xDD]);
     alu::write_u16(&mut context.registers.e, &mut context.registers.d, 0xC001);
     context.memory.write(&mut context.clock, 0xC001, 18)?;
@@ -170,7 +171,7 @@ fn ld_a_demem() -> Result<(), String> {
 }
 
 #[test]
-fn ld_a_hlimem() -> Result<(), String> {
+fn ld_a_hlimem() -> Result<(), EmuError> {
     let mut context = get_mock_context(vec![0x2A, 0xDD]);
     alu::write_u16(&mut context.registers.l, &mut context.registers.h, 0xC001);
     context.memory.write(&mut context.clock, 0xC001, 18)?;
@@ -185,7 +186,7 @@ fn ld_a_hlimem() -> Result<(), String> {
 }
 
 #[test]
-fn ld_a_hldmem() -> Result<(), String> {
+fn ld_a_hldmem() -> Result<(), EmuError> {
     let mut context = get_mock_context(vec![0x3A, 0xDD]);
     alu::write_u16(&mut context.registers.l, &mut context.registers.h, 0xC001);
     context.memory.write(&mut context.clock, 0xC001, 18)?;
@@ -198,3 +199,60 @@ fn ld_a_hldmem() -> Result<(), String> {
     assert_eq!(context.clock.m_cycles, 4);
     Ok(())
 }
+
+#[test]
+fn ld_hl_sp_e8() -> Result<(), EmuError> {
+    // ld hl, sp - 2
+    let mut context = get_mock_context(vec![0xF8, 0xFE, 0xDD]);
+    context.registers.sp = 0xC001;
+    let _ = context.start_exec_cycle();
+    assert_eq!(
+        alu::read_u16(&context.registers.l, &context.registers.h),
+        0xBFFF
+    );
+    assert_eq!(context.registers.sp, 0xC001);
+    assert_eq!(context.clock.m_cycles, 4);
+    assert_eq!(context.registers.f & 0xF0, 0x00);
+    Ok(())
+}
+
+#[test]
+fn ld_sp_hl() -> Result<(), EmuError> {
+    let mut context = get_mock_context(vec![0xF9, 0xDD]);
+    alu::write_u16(&mut context.registers.l, &mut context.registers.h, 0xC001);
+    let _ = context.start_exec_cycle();
+    assert_eq!(context.registers.sp, 0xC001);
+    assert_eq!(context.clock.m_cycles, 3);
+    Ok(())
+}
+
+#[test]
+fn ldh_n8_a() -> Result<(), EmuError> {
+    // ldh [0x80], a | ldh a, [c]
+    let mut context = get_mock_context(vec![0xE0, 0x80, 0xF2, 0xDD]);
+    context.registers.a = 0x42;
+    context.registers.c = 0x80;
+    let _ = context.start_exec_cycle();
+    assert_eq!(context.clock.m_cycles, 6);
+    context.registers.a = 0;
+    context.registers.pc = 2;
+    let _ = context.start_exec_cycle();
+    assert_eq!(context.registers.a, 0x42);
+    assert_eq!(context.clock.m_cycles, 9);
+    Ok(())
+}
+
+#[test]
+fn ld_n16_a() -> Result<(), EmuError> {
+    // ld [0xC001], a | ld a, [0xC001]
+    let mut context = get_mock_context(vec![0xEA, 0x01, 0xC0, 0xDD, 0xFA, 0x01, 0xC0, 0xDD]);
+    context.registers.a = 0x42;
+    let _ = context.start_exec_cycle();
+    assert_eq!(context.clock.m_cycles, 5);
+    assert_eq!(context.memory.read(&mut context.clock, 0xC001)?, 0x42);
+    context.registers.a = 0;
+    context.registers.pc = 4;
+    let _ = context.start_exec_cycle();
+    assert_eq!(context.registers.a, 0x42);
+    Ok(())
+}
@@ -0,0 +1,315 @@
+use redgb::{
+    cpu::clock::Clock,
+    error::EmuError,
+    mem::map::MemoryMap,
+    rom::{
+        cartridge_type::CartridgeType,
+        rom_info::ROMInfo,
+        rom_parser::{NINTENDO, NINTENDO_LOGO_RANGE},
+    },
+};
+
+/// ROM where the first byte of every bank is the bank number
+fn get_mock_rom(banks: usize) -> Vec<u8> {
+    let mut rom = vec![0; banks * 0x4000];
+    for bank in 0..banks {
+        rom[bank * 0x4000] = bank as u8;
+    }
+    rom
+}
+
+fn get_mock_map(rom: Vec<u8>, cartridge_type: u8, mem_banks: u16) -> MemoryMap {
+    let info = ROMInfo {
+        cartridge_type: CartridgeType::from_code(cartridge_type),
+        rom_banks: rom.len().div_ceil(0x4000) as u16,
+        mem_banks,
+        ..ROMInfo::default()
+    };
+    MemoryMap::init_rom(rom, info)
+}
+
+#[test]
+fn mbc1_rom_banking() -> Result<(), EmuError> {
+    let mut clock = Clock::default();
+    let mut memory = get_mock_map(get_mock_rom(128), 0x01, 0);
+    assert_eq!(memory.read(&mut clock, 0x4000)?, 1);
+    memory.write(&mut clock, 0x2000, 0x05)?;
+    assert_eq!(memory.read(&mut clock, 0x4000)?, 5);
+    // Bank 0 can't be selected at 0x4000
+    memory.write(&mut clock, 0x2000, 0x00)?;
+    assert_eq!(memory.read(&mut clock, 0x4000)?, 1);
+    // Only 5 bits are used, and the zero check applies to them
+    memory.write(&mut clock, 0x2000, 0xE0)?;
+    assert_eq!(memory.read(&mut clock, 0x4000)?, 1);
+    memory.write(&mut clock, 0x2000, 0x02)?;
+    memory.write(&mut clock, 0x4000, 0x01)?;
+    assert_eq!(memory.read(&mut clock, 0x4000)?, 0x22);
+    // 0x20 is mapped as 0x21
+    memory.write(&mut clock, 0x2000, 0x00)?;
+    assert_eq!(memory.read(&mut clock, 0x4000)?, 0x21);
+    // Mode 1 maps BANK2 at 0x0000 too
+    assert_eq!(memory.read(&mut clock, 0x0000)?, 0);
+    memory.write(&mut clock, 0x6000, 0x01)?;
+    assert_eq!(memory.read(&mut clock, 0x0000)?, 0x20);
+    Ok(())
+}
+
+#[test]
+fn mbc1_bank_wraps_to_rom_size() -> Result<(), EmuError> {
+    let mut clock = Clock::default();
+    let mut memory = get_mock_map(get_mock_rom(8), 0x01, 0);
+    memory.write(&mut clock, 0x2000, 0x09)?;
+    assert_eq!(memory.read(&mut clock, 0x4000)?, 1);
+    Ok(())
+}
+
+#[test]
+fn mbc1_ram() -> Result<(), EmuError> {
+    let mut clock = Clock::default();
+    let mut memory = get_mock_map(get_mock_rom(4), 0x03, 4);
+    // Disabled RAM ignores writes and reads open bus
+    memory.write(&mut clock, 0xA000, 0x42)?;
+    assert_eq!(memory.read(&mut clock, 0xA000)?, 0xFF);
+    memory.write(&mut clock, 0x0000, 0x0A)?;
+    memory.write(&mut clock, 0xA000, 0x42)?;
+    assert_eq!(memory.read(&mut clock, 0xA000)?, 0x42);
+
+    // BANK2 only selects the RAM bank in mode 1
+    memory.write(&mut clock, 0x4000, 0x02)?;
+    assert_eq!(memory.read(&mut clock, 0xA000)?, 0x42);
+    memory.write(&mut clock, 0x6000, 0x01)?;
+    assert_eq!(memory.read(&mut clock, 0xA000)?, 0x00);
+    memory.write(&mut clock, 0xA000, 0x24)?;
+    memory.write(&mut clock, 0x4000, 0x00)?;
+    assert_eq!(memory.read(&mut clock, 0xA000)?, 0x42);
+
+    // Any value without 0xA in the low nibble disables it
+    memory.write(&mut clock, 0x0000, 0x1B)?;
+    assert_eq!(memory.read(&mut clock, 0xA000)?, 0xFF);
+    Ok(())
+}
+
+#[test]
+fn mbc1_multicart() -> Result<(), EmuError> {
+    let mut clock = Clock::default();
+    let mut rom = get_mock_rom(64);
+    for game in [0x00, 0x10, 0x20, 0x30] {
+        let logo = game * 0x4000 + NINTENDO_LOGO_RANGE.start();
+        rom[logo..logo + NINTENDO.len()].copy_from_slice(&NINTENDO);
+    }
+    let mut memory = get_mock_map(rom, 0x01, 0);
+    // BANK2 starts at bit 4 and bit 4 of BANK1 is ignored
+    memory.write(&mut clock, 0x2000, 0x12)?;
+    memory.write(&mut clock, 0x4000, 0x01)?;
+    assert_eq!(memory.read(&mut clock, 0x4000)?, 0x12);
+    memory.write(&mut clock, 0x6000, 0x01)?;
+    assert_eq!(memory.read(&mut clock, 0x0000)?, 0x10);
+    Ok(())
+}
+
+#[test]
+fn rom_only_ignores_writes() -> Result<(), EmuError> {
+    let mut clock = Clock::default();
+    let mut memory = get_mock_map(get_mock_rom(2), 0x00, 0);
+    memory.write(&mut clock, 0x2000, 0x05)?;
+    assert_eq!(memory.read(&mut clock, 0x4000)?, 1);
+    assert_eq!(memory.read(&mut clock, 0xA000)?, 0xFF);
+    Ok(())
+}
+
+#[test]
+fn short_rom_reads_open_bus() -> Result<(), EmuError> {
+    let mut clock = Clock::default();
+    let mut memory = get_mock_map(vec![0x12; 0x5000], 0x00, 0);
+    assert_eq!(memory.read(&mut clock, 0x4FFF)?, 0x12);
+    assert_eq!(memory.read(&mut clock, 0x5000)?, 0xFF);
+    assert_eq!(memory.read(&mut clock, 0x7FFF)?, 0xFF);
+
+    // Same past the end of a banked ROM's last bank
+    let mut memory = get_mock_map(vec![0x12; 0x5000], 0x01, 0);
+    assert_eq!(memory.read(&mut clock, 0x4FFF)?, 0x12);
+    assert_eq!(memory.read(&mut clock, 0x6000)?, 0xFF);
+    Ok(())
+}
+
+const SECOND: u64 = 4_194_304;
+
+/// Latches the RTC and reads register `select`
+fn read_rtc(memory: &mut MemoryMap, clock: &mut Clock, select: u8) -> Result<u8, EmuError> {
+    memory.write(clock, 0x6000, 0x00)?;
+    memory.write(clock, 0x6000, 0x01)?;
+    memory.write(clock, 0x4000, select)?;
+    memory.read(clock, 0xA000)
+}
+
+fn write_rtc(
+    memory: &mut MemoryMap,
+    clock: &mut Clock,
+    select: u8,
+    value: u8,
+) -> Result<(), EmuError> {
+    memory.write(clock, 0x4000, select)?;
+    memory.write(clock, 0xA000, value)
+}
+
+#[test]
+fn mbc3_banking() -> Result<(), EmuError> {
+    let mut clock = Clock::default();
+    let mut memory = get_mock_map(get_mock_rom(128), 0x13, 4);
+    memory.write(&mut clock, 0x2000, 0x7F)?;
+    assert_eq!(memory.read(&mut clock, 0x4000)?, 0x7F);
+    memory.write(&mut clock, 0x2000, 0x80)?;
+    assert_eq!(memory.read(&mut clock, 0x4000)?, 0x01);
+
+    memory.write(&mut clock, 0x0000, 0x0A)?;
+    memory.write(&mut clock, 0x4000, 0x03)?;
+    memory.write(&mut clock, 0xA000, 0x33)?;
+    memory.write(&mut clock, 0x4000, 0x00)?;
+    assert_eq!(memory.read(&mut clock, 0xA000)?, 0x00);
+    memory.write(&mut clock, 0x4000, 0x03)?;
+    assert_eq!(memory.read(&mut clock, 0xA000)?, 0x33);
+    // No RTC on this cart
+    memory.write(&mut clock, 0x4000, 0x08)?;
+    assert_eq!(memory.read(&mut clock, 0xA000)?, 0xFF);
+    Ok(())
+}
+
+#[test]
+fn mbc3_rtc_counts_cycles() -> Result<(), EmuError> {
+    let mut clock = Clock::default();
+    let mut memory = get_mock_map(get_mock_rom(4), 0x10, 4);
+    memory.write(&mut clock, 0x0000, 0x0A)?;
+    write_rtc(&mut memory, &mut clock, 0x08, 58)?;
+    write_rtc(&mut memory, &mut clock, 0x09, 59)?;
+    clock.t_cycles += 2 * SECOND;
+    assert_eq!(read_rtc(&mut memory, &mut clock, 0x08)?, 0);
+    assert_eq!(read_rtc(&mut memory, &mut clock, 0x09)?, 0);
+    assert_eq!(read_rtc(&mut memory, &mut clock, 0x0A)?, 1);
+
+    // Registers only change when latched
+    clock.t_cycles += 5 * SECOND;
+    memory.write(&mut clock, 0x4000, 0x08)?;
+    assert_eq!(memory.read(&mut clock, 0xA000)?, 0);
+    assert_eq!(read_rtc(&mut memory, &mut clock, 0x08)?, 5);
+    Ok(())
+}
+
+#[test]
+fn mbc3_rtc_halt_and_day_carry() -> Result<(), EmuError> {
+    let mut clock = Clock::default();
+    let mut memory = get_mock_map(get_mock_rom(4), 0x0F, 0);
+    memory.write(&mut clock, 0x0000, 0x0A)?;
+    write_rtc(&mut memory, &mut clock, 0x0C, 0x41)?;
+    write_rtc(&mut memory, &mut clock, 0x0B, 0xFF)?;
+    write_rtc(&mut memory, &mut clock, 0x0A, 23)?;
+    write_rtc(&mut memory, &mut clock, 0x09, 59)?;
+    write_rtc(&mut memory, &mut clock, 0x08, 59)?;
+    clock.t_cycles += 10 * SECOND;
+    assert_eq!(read_rtc(&mut memory, &mut clock, 0x08)?, 59);
+
+    write_rtc(&mut memory, &mut clock, 0x0C, 0x01)?;
+    clock.t_cycles += SECOND;
+    assert_eq!(read_rtc(&mut memory, &mut clock, 0x0B)?, 0x00);
+    assert_eq!(read_rtc(&mut memory, &mut clock, 0x0C)?, 0x80);
+    assert_eq!(read_rtc(&mut memory, &mut clock, 0x0A)?, 0);
+    Ok(())
+}
+
+#[test]
+fn mbc3_rtc_invalid_values_wrap() -> Result<(), EmuError> {
+    let mut clock = Clock::default();
+    let mut memory = get_mock_map(get_mock_rom(4), 0x0F, 0);
+    memory.write(&mut clock, 0x0000, 0x0A)?;
+    write_rtc(&mut memory, &mut clock, 0x08, 62)?;
+    clock.t_cycles += 2 * SECOND;
+    assert_eq!(read_rtc(&mut memory, &mut clock, 0x08)?, 0);
+    assert_eq!(read_rtc(&mut memory, &mut clock, 0x09)?, 0);
+    Ok(())
+}
+
+#[test]
+fn mbc3_rtc_save_roundtrip() -> Result<(), EmuError> {
+    let mut clock = Clock::default();
+    let mut memory = get_mock_map(get_mock_rom(4), 0x10, 1);
+    memory.write(&mut clock, 0x0000, 0x0A)?;
+    write_rtc(&mut memory, &mut clock, 0x0A, 13)?;
+    write_rtc(&mut memory, &mut clock, 0x0B, 0x2A)?;
+    let footer = memory.rtc_mut().expect("MBC3+TIMER has an RTC").save();
+    assert_eq!(footer.len(), 48);
+    assert_eq!(footer[8], 13);
+
+    let mut memory = get_mock_map(get_mock_rom(4), 0x10, 1);
+    let rtc = memory.rtc_mut().expect("MBC3+TIMER has an RTC");
+    assert!(rtc.load(&footer));
+    assert_eq!(rtc.registers().hours, 13);
+    assert_eq!(rtc.registers().days_low, 0x2A);
+    assert!(!rtc.load(&footer[..40]));
+    Ok(())
+}
+
+#[test]
+fn mbc5_banking() -> Result<(), EmuError> {
+    let mut clock = Clock::default();
+    let mut rom = get_mock_rom(512);
+    // The mock rom only tags the low 8 bits of the bank number
+    rom[0x100 * 0x4000 + 1] = 0x01;
+    let mut memory = get_mock_map(rom, 0x1B, 16);
+    memory.write(&mut clock, 0x2000, 0x00)?;
+    assert_eq!(memory.read(&mut clock, 0x4000)?, 0x00);
+    assert_eq!(memory.read(&mut clock, 0x4001)?, 0x00);
+    memory.write(&mut clock, 0x3000, 0x01)?;
+    assert_eq!(memory.read(&mut clock, 0x4001)?, 0x01);
+    memory.write(&mut clock, 0x2000, 0x42)?;
+    assert_eq!(memory.read(&mut clock, 0x4000)?, 0x42);
+
+    memory.write(&mut clock, 0x0000, 0x0A)?;
+    memory.write(&mut clock, 0x4000, 0x0F)?;
+    memory.write(&mut clock, 0xA000, 0x5A)?;
+    memory.write(&mut clock, 0x4000, 0x07)?;
+    assert_eq!(memory.read(&mut clock, 0xA000)?, 0x00);
+    memory.write(&mut clock, 0x4000, 0x0F)?;
+    assert_eq!(memory.read(&mut clock, 0xA000)?, 0x5A);
+    assert!(!memory.rumble());
+    // Only 0x0A enables RAM on MBC5
+    memory.write(&mut clock, 0x0000, 0x1A)?;
+    assert_eq!(memory.read(&mut clock, 0xA000)?, 0xFF);
+    Ok(())
+}
+
+#[test]
+fn mbc5_rumble() -> Result<(), EmuError> {
+    let mut clock = Clock::default();
+    let mut memory = get_mock_map(get_mock_rom(4), 0x1E, 16);
+    memory.write(&mut clock, 0x0000, 0x0A)?;
+    memory.write(&mut clock, 0x4000, 0x01)?;
+    memory.write(&mut clock, 0xA000, 0x11)?;
+    memory.write(&mut clock, 0x4000, 0x09)?;
+    assert!(memory.rumble());
+    // Bit 3 drives the motor instead of selecting a bank
+    assert_eq!(memory.read(&mut clock, 0xA000)?, 0x11);
+    memory.write(&mut clock, 0x4000, 0x01)?;
+    assert!(!memory.rumble());
+    Ok(())
+}
+
+#[test]
+fn mbc2() -> Result<(), EmuError> {
+    let mut clock = Clock::default();
+    let mut memory = get_mock_map(get_mock_rom(16), 0x06, 0);
+    // Address bit 8 set selects the ROM bank
+    memory.write(&mut clock, 0x2100, 0x05)?;
+    assert_eq!(memory.read(&mut clock, 0x4000)?, 0x05);
+    memory.write(&mut clock, 0x0100, 0x00)?;
+    assert_eq!(memory.read(&mut clock, 0x4000)?, 0x01);
+    // and clear selects RAM enable
+    memory.write(&mut clock, 0x2000, 0x0A)?;
+    assert_eq!(memory.read(&mut clock, 0x4000)?, 0x01);
+    memory.write(&mut clock, 0xA000, 0x3C)?;
+    assert_eq!(memory.read(&mut clock, 0xA000)?, 0xFC);
+    // The 512 half-bytes are echoed through the whole area
+    assert_eq!(memory.read(&mut clock, 0xA200)?, 0xFC);
+    assert_eq!(memory.read(&mut clock, 0xBE00)?, 0xFC);
+    memory.write(&mut clock, 0x0000, 0x00)?;
+    assert_eq!(memory.read(&mut clock, 0xA000)?, 0xFF);
+    Ok(())
+}
@@ -0,0 +1,468 @@
+use redgb::{
+    cpu::{clock::Clock, interrupts::Interrupt, reg_file::Modes},
+    error::EmuError,
+    mem::map::MemoryMap,
+    ppu::{Renderer, SCREEN_WIDTH},
+    rom::rom_info::ROMInfo,
+};
+
+/// Map with the LCD off so VRAM and OAM can be set up freely
+fn get_mock_map(renderer: Renderer) -> Result<(MemoryMap, Clock), EmuError> {
+    let mut clock = Clock::default();
+    let mut memory =
+        MemoryMap::with_renderer(vec![0; 0x8000], ROMInfo::default(), Modes::DMG, renderer);
+    memory.write(&mut clock, 0xFF40, 0x00)?;
+    memory.write(&mut clock, 0xFF0F, 0x00)?;
+    Ok((memory, clock))
+}
+
+fn run_frame(memory: &mut MemoryMap, clock: &mut Clock) {
+    memory.take_frame_ready();
+    while !memory.take_frame_ready() {
+        memory.tick(clock);
+    }
+}
+
+/// Fills the 16 bytes of tile `tile` (at 0x8000) with a single color index
+fn write_solid_tile(
+    memory: &mut MemoryMap,
+    clock: &mut Clock,
+    tile: u16,
+    color: u8,
+) -> Result<(), EmuError> {
+    let low = if color & 0x1 != 0 { 0xFF } else { 0x00 };
+    let high = if color & 0x2 != 0 { 0xFF } else { 0x00 };
+    for row in 0..8 {
+        memory.write(clock, 0x8000 + tile * 16 + row * 2, low)?;
+        memory.write(clock, 0x8000 + tile * 16 + row * 2 + 1, high)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn line_timing() -> Result<(), EmuError> {
+    let (mut memory, mut clock) = get_mock_map(Renderer::Scanline)?;
+    memory.write(&mut clock, 0xFF40, 0x91)?;
+    let start = clock.m_cycles;
+    // OAM scan takes 80 dots, drawing 172 and HBlank the rest of the 456 dot line
+    while memory.read(&mut clock, 0xFF41)? & 0x3 == 2 {}
+    assert_eq!(clock.m_cycles - start, 20);
+    while memory.read(&mut clock, 0xFF41)? & 0x3 == 3 {}
+    assert_eq!(clock.m_cycles - start, 63);
+    while memory.read(&mut clock, 0xFF44)? == 0 {}
+    assert_eq!(clock.m_cycles - start, 114);
+    assert!(!memory.interrupt_requested(Interrupt::VBlank));
+
+    while memory.read(&mut clock, 0xFF44)? != 144 {}
+    assert_eq!(clock.m_cycles - start, 144 * 114);
+    assert!(memory.interrupt_requested(Interrupt::VBlank));
+    assert_eq!(memory.read(&mut clock, 0xFF41)? & 0x3, 1);
+    while memory.read(&mut clock, 0xFF44)? != 0 {}
+    assert_eq!(clock.m_cycles - start, 154 * 114);
+    Ok(())
+}
+
+#[test]
+fn lcd_off() -> Result<(), EmuError> {
+    let (mut memory, mut clock) = get_mock_map(Renderer::Scanline)?;
+    for _ in 0..1000 {
+        memory.tick(&mut clock);
+    }
+    assert_eq!(memory.read(&mut clock, 0xFF44)?, 0);
+    assert_eq!(memory.read(&mut clock, 0xFF41)? & 0x3, 0);
+    assert!(!memory.interrupt_requested(Interrupt::VBlank));
+    Ok(())
+}
+
+#[test]
+fn stat_interrupts() -> Result<(), EmuError> {
+    let (mut memory, mut clock) = get_mock_map(Renderer::Scanline)?;
+    memory.write(&mut clock, 0xFF45, 3)?;
+    memory.write(&mut clock, 0xFF41, 0x40)?;
+    memory.write(&mut clock, 0xFF40, 0x91)?;
+    while !memory.interrupt_requested(Interrupt::Stat) {
+        memory.tick(&mut clock);
+    }
+    assert_eq!(memory.read(&mut clock, 0xFF44)?, 3);
+    assert_eq!(memory.read(&mut clock, 0xFF41)? & 0x4, 0x4);
+
+    // The line stays high while LY=LYC, enabling HBlank too doesn't fire again
+    memory.clear_interrupt(Interrupt::Stat);
+    memory.write(&mut clock, 0xFF41, 0x48)?;
+    while memory.read(&mut clock, 0xFF44)? == 3 {}
+    assert!(!memory.interrupt_requested(Interrupt::Stat));
+    // HBlank of line 4 is a new rising edge
+    while memory.read(&mut clock, 0xFF41)? & 0x3 != 0 {}
+    assert!(memory.interrupt_requested(Interrupt::Stat));
+    Ok(())
+}
+
+#[test]
+fn vram_blocked_while_drawing() -> Result<(), EmuError> {
+    let (mut memory, mut clock) = get_mock_map(Renderer::Scanline)?;
+    memory.write(&mut clock, 0x8000, 0x12)?;
+    memory.write(&mut clock, 0xFE00, 0x34)?;
+    memory.write(&mut clock, 0xFF40, 0x91)?;
+    // OAM scan
+    assert_eq!(memory.read(&mut clock, 0x8000)?, 0x12);
+    assert_eq!(memory.read(&mut clock, 0xFE00)?, 0xFF);
+    while memory.read(&mut clock, 0xFF41)? & 0x3 != 3 {}
+    assert_eq!(memory.read(&mut clock, 0x8000)?, 0xFF);
+    memory.write(&mut clock, 0x8000, 0x56)?;
+    while memory.read(&mut clock, 0xFF41)? & 0x3 != 0 {}
+    assert_eq!(memory.read(&mut clock, 0x8000)?, 0x12);
+    assert_eq!(memory.read(&mut clock, 0xFE00)?, 0x34);
+    Ok(())
+}
+
+#[test]
+fn background() -> Result<(), EmuError> {
+    let (mut memory, mut clock) = get_mock_map(Renderer::Scanline)?;
+    write_solid_tile(&mut memory, &mut clock, 1, 3)?;
+    // Second tile of the first map row
+    memory.write(&mut clock, 0x9801, 1)?;
+    memory.write(&mut clock, 0xFF47, 0xE4)?;
+    memory.write(&mut clock, 0xFF43, 4)?;
+    memory.write(&mut clock, 0xFF40, 0x91)?;
+    run_frame(&mut memory, &mut clock);
+    let frame = memory.framebuffer();
+    // SCX shifts the tile 4 pixels to the left
+    assert_eq!(frame[3], 0xFFFFFF);
+    assert_eq!(frame[4], 0x000000);
+    assert_eq!(frame[11], 0x000000);
+    assert_eq!(frame[12], 0xFFFFFF);
+    assert_eq!(frame[7 * SCREEN_WIDTH + 4], 0x000000);
+    assert_eq!(frame[8 * SCREEN_WIDTH + 4], 0xFFFFFF);
+    Ok(())
+}
+
+#[test]
+fn window() -> Result<(), EmuError> {
+    let (mut memory, mut clock) = get_mock_map(Renderer::Scanline)?;
+    write_solid_tile(&mut memory, &mut clock, 1, 2)?;
+    // The window uses the 0x9C00 map
+    for tile in 0..32 * 32 {
+        memory.write(&mut clock, 0x9C00 + tile, 1)?;
+    }
+    memory.write(&mut clock, 0xFF47, 0xE4)?;
+    memory.write(&mut clock, 0xFF4A, 10)?;
+    memory.write(&mut clock, 0xFF4B, 27)?;
+    memory.write(&mut clock, 0xFF40, 0xF1)?;
+    run_frame(&mut memory, &mut clock);
+    let frame = memory.framebuffer();
+    assert_eq!(frame[9 * SCREEN_WIDTH + 30], 0xFFFFFF);
+    assert_eq!(frame[10 * SCREEN_WIDTH + 19], 0xFFFFFF);
+    assert_eq!(frame[10 * SCREEN_WIDTH + 20], 0x555555);
+    assert_eq!(frame[143 * SCREEN_WIDTH + 159], 0x555555);
+    Ok(())
+}
+
+#[test]
+fn sprites() -> Result<(), EmuError> {
+    let (mut memory, mut clock) = get_mock_map(Renderer::Scanline)?;
+    // Tile 1 has color 1 on its left half and 0 on the right
+    for row in 0..8 {
+        memory.write(&mut clock, 0x8010 + row * 2, 0xF0)?;
+    }
+    write_solid_tile(&mut memory, &mut clock, 2, 3)?;
+    // Sprite 0 at (0, 0), flipped horizontally
+    for (i, value) in [16, 8, 1, 0x20].iter().enumerate() {
+        memory.write(&mut clock, 0xFE00 + i as u16, *value)?;
+    }
+    // Sprite 1 overlaps it 4 pixels to the left, lower X wins
+    for (i, value) in [16, 4, 2, 0x00].iter().enumerate() {
+        memory.write(&mut clock, 0xFE04 + i as u16, *value)?;
+    }
+    memory.write(&mut clock, 0xFF48, 0xE4)?;
+    memory.write(&mut clock, 0xFF40, 0x93)?;
+    run_frame(&mut memory, &mut clock);
+    let frame = memory.framebuffer();
+    assert_eq!(frame[0], 0x000000);
+    assert_eq!(frame[3], 0x000000);
+    // Flipped sprite 0 shows through where sprite 1 ends
+    assert_eq!(frame[4], 0xAAAAAA);
+    assert_eq!(frame[7], 0xAAAAAA);
+    assert_eq!(frame[8], 0xFFFFFF);
+    assert_eq!(frame[8 * SCREEN_WIDTH], 0xFFFFFF);
+    Ok(())
+}
+
+#[test]
+fn sprite_limit() -> Result<(), EmuError> {
+    let (mut memory, mut clock) = get_mock_map(Renderer::Scanline)?;
+    write_solid_tile(&mut memory, &mut clock, 1, 3)?;
+    // 11 sprites side by side on the first line, the last one isn't drawn
+    for sprite in 0..11 {
+        for (i, value) in [16, 8 + sprite as u8 * 8, 1, 0].iter().enumerate() {
+            memory.write(&mut clock, 0xFE00 + sprite * 4 + i as u16, *value)?;
+        }
+    }
+    memory.write(&mut clock, 0xFF48, 0xE4)?;
+    memory.write(&mut clock, 0xFF40, 0x93)?;
+    run_frame(&mut memory, &mut clock);
+    let frame = memory.framebuffer();
+    assert_eq!(frame[79], 0x000000);
+    assert_eq!(frame[80], 0xFFFFFF);
+    Ok(())
+}
+
+/// BG with fine scroll, the window and overlapping, flipped and clipped sprites
+fn draw_scene(renderer: Renderer) -> Result<Vec<u32>, EmuError> {
+    let (mut memory, mut clock) = get_mock_map(renderer)?;
+    for tile in 0..4u16 {
+        for row in 0..8 {
+            memory.write(
+                &mut clock,
+                0x8000 + tile * 16 + row * 2,
+                0x3C ^ (tile * 0x11) as u8,
+            )?;
+            memory.write(
+                &mut clock,
+                0x8000 + tile * 16 + row * 2 + 1,
+                (row * 0x25) as u8,
+            )?;
+        }
+    }
+    for tile in 0..32 * 32 {
+        memory.write(&mut clock, 0x9800 + tile, (tile % 3) as u8)?;
+        memory.write(&mut clock, 0x9C00 + tile, 3)?;
+    }
+    let sprites = [
+        [16, 0, 1, 0x00],
+        [16, 4, 2, 0x20],
+        [20, 4, 3, 0x10],
+        [40, 50, 1, 0x80],
+        [44, 53, 2, 0x40],
+        [60, 165, 3, 0x00],
+    ];
+    for (index, sprite) in sprites.iter().enumerate() {
+        for (i, value) in sprite.iter().enumerate() {
+            memory.write(&mut clock, 0xFE00 + (index * 4 + i) as u16, *value)?;
+        }
+    }
+    memory.write(&mut clock, 0xFF47, 0xE4)?;
+    memory.write(&mut clock, 0xFF48, 0xD2)?;
+    memory.write(&mut clock, 0xFF49, 0x1B)?;
+    memory.write(&mut clock, 0xFF42, 5)?;
+    memory.write(&mut clock, 0xFF43, 3)?;
+    memory.write(&mut clock, 0xFF4A, 100)?;
+    memory.write(&mut clock, 0xFF4B, 87)?;
+    memory.write(&mut clock, 0xFF40, 0xF3)?;
+    run_frame(&mut memory, &mut clock);
+    Ok(memory.framebuffer().to_vec())
+}
+
+#[test]
+fn fifo_matches_scanline() -> Result<(), EmuError> {
+    assert!(draw_scene(Renderer::Scanline)? == draw_scene(Renderer::Fifo)?);
+    Ok(())
+}
+
+/// M-Cs from the start of line 0 until HBlank
+/// Sprites due together at the left edge, listed in OAM with the higher X first
+fn draw_left_edge_sprites(renderer: Renderer) -> Result<Vec<u32>, EmuError> {
+    let (mut memory, mut clock) = get_mock_map(renderer)?;
+    for tile in 1..4 {
+        write_solid_tile(&mut memory, &mut clock, tile, tile as u8)?;
+    }
+    let sprites = [[16, 6, 1, 0x00], [16, 4, 2, 0x00], [16, 2, 3, 0x00]];
+    for (index, sprite) in sprites.iter().enumerate() {
+        for (i, value) in sprite.iter().enumerate() {
+            memory.write(&mut clock, 0xFE00 + (index * 4 + i) as u16, *value)?;
+        }
+    }
+    memory.write(&mut clock, 0xFF48, 0xE4)?;
+    memory.write(&mut clock, 0xFF40, 0x93)?;
+    run_frame(&mut memory, &mut clock);
+    Ok(memory.framebuffer().to_vec())
+}
+
+#[test]
+fn fifo_sprite_x_priority() -> Result<(), EmuError> {
+    let fifo = draw_left_edge_sprites(Renderer::Fifo)?;
+    assert!(draw_left_edge_sprites(Renderer::Scanline)? == fifo);
+    // The lowest X wins, whatever its OAM index
+    assert_eq!(fifo[0], 0x000000);
+    assert_eq!(fifo[2], 0x555555);
+    assert_eq!(fifo[4], 0xAAAAAA);
+    Ok(())
+}
+
+fn drawing_length(memory: &mut MemoryMap, clock: &mut Clock) -> Result<u64, EmuError> {
+    memory.write(clock, 0xFF40, 0x00)?;
+    memory.write(clock, 0xFF40, 0x93)?;
+    let start = clock.m_cycles;
+    while memory.read(clock, 0xFF41)? & 0x3 != 3 {}
+    while memory.read(clock, 0xFF41)? & 0x3 != 0 {}
+    Ok(clock.m_cycles - start)
+}
+
+#[test]
+fn fifo_mode3_length() -> Result<(), EmuError> {
+    let (mut memory, mut clock) = get_mock_map(Renderer::Fifo)?;
+    // 80 dots of OAM scan and 172 of drawing
+    assert_eq!(drawing_length(&mut memory, &mut clock)?, 63);
+    // Fine scroll drops pixels at the start of the line
+    memory.write(&mut clock, 0xFF43, 7)?;
+    assert_eq!(drawing_length(&mut memory, &mut clock)?, 65);
+    memory.write(&mut clock, 0xFF43, 0)?;
+    // Each sprite pauses the pixel output, 11 dots when aligned to a BG tile
+    memory.write(&mut clock, 0xFE00, 16)?;
+    memory.write(&mut clock, 0xFE01, 40)?;
+    assert_eq!(drawing_length(&mut memory, &mut clock)?, 66);
+    memory.write(&mut clock, 0xFE04, 16)?;
+    memory.write(&mut clock, 0xFE05, 80)?;
+    assert_eq!(drawing_length(&mut memory, &mut clock)?, 69);
+    Ok(())
+}
+
+#[test]
+fn fifo_mid_line_palette() -> Result<(), EmuError> {
+    for (renderer, left) in [(Renderer::Scanline, 0xFFFFFF), (Renderer::Fifo, 0x000000)] {
+        let (mut memory, mut clock) = get_mock_map(renderer)?;
+        write_solid_tile(&mut memory, &mut clock, 0, 3)?;
+        memory.write(&mut clock, 0xFF47, 0xE4)?;
+        memory.write(&mut clock, 0xFF40, 0x91)?;
+        while memory.read(&mut clock, 0xFF41)? & 0x3 != 3 {}
+        for _ in 0..20 {
+            memory.tick(&mut clock);
+        }
+        memory.write(&mut clock, 0xFF47, 0x00)?;
+        run_frame(&mut memory, &mut clock);
+        let frame = memory.framebuffer();
+        assert_eq!(frame[0], left);
+        assert_eq!(frame[159], 0xFFFFFF);
+        assert_eq!(frame[SCREEN_WIDTH], 0xFFFFFF);
+    }
+    Ok(())
+}
+
+fn get_cgb_map(renderer: Renderer) -> Result<(MemoryMap, Clock), EmuError> {
+    let mut clock = Clock::default();
+    let mut memory =
+        MemoryMap::with_renderer(vec![0; 0x8000], ROMInfo::default(), Modes::CGB, renderer);
+    memory.write(&mut clock, 0xFF40, 0x00)?;
+    Ok((memory, clock))
+}
+
+/// Writes RGB555 colors to palette RAM starting at `index` through BCPS/BCPD or OCPS/OCPD
+fn write_palette(
+    memory: &mut MemoryMap,
+    clock: &mut Clock,
+    index_reg: u16,
+    index: u8,
+    colors: &[u16],
+) -> Result<(), EmuError> {
+    memory.write(clock, index_reg, 0x80 | index)?;
+    for color in colors {
+        for byte in color.to_le_bytes() {
+            memory.write(clock, index_reg + 1, byte)?;
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn cgb_palette_ram() -> Result<(), EmuError> {
+    let (mut memory, mut clock) = get_cgb_map(Renderer::Scanline)?;
+    write_palette(&mut memory, &mut clock, 0xFF68, 0x3E, &[0x1234, 0x5678])?;
+    // The index wraps around and bit 6 reads as 1
+    assert_eq!(memory.read(&mut clock, 0xFF68)?, 0xC2);
+    memory.write(&mut clock, 0xFF68, 0x3F)?;
+    assert_eq!(memory.read(&mut clock, 0xFF69)?, 0x12);
+    // Without auto-increment the index stays put
+    memory.write(&mut clock, 0xFF69, 0xAB)?;
+    assert_eq!(memory.read(&mut clock, 0xFF68)?, 0x7F);
+    assert_eq!(memory.read(&mut clock, 0xFF69)?, 0xAB);
+    memory.write(&mut clock, 0xFF68, 0x01)?;
+    assert_eq!(memory.read(&mut clock, 0xFF69)?, 0x56);
+    // OBJ palettes are separate
+    assert_eq!(memory.read(&mut clock, 0xFF6A)?, 0x40);
+    assert_eq!(memory.read(&mut clock, 0xFF6B)?, 0xFF);
+
+    // Locked while drawing, writes still increment the index
+    memory.write(&mut clock, 0xFF68, 0x80)?;
+    memory.write(&mut clock, 0xFF40, 0x91)?;
+    while memory.read(&mut clock, 0xFF41)? & 0x3 != 3 {}
+    assert_eq!(memory.read(&mut clock, 0xFF69)?, 0xFF);
+    memory.write(&mut clock, 0xFF69, 0x00)?;
+    assert_eq!(memory.read(&mut clock, 0xFF68)?, 0xC1);
+
+    let (mut memory, mut clock) = get_mock_map(Renderer::Scanline)?;
+    assert_eq!(memory.read(&mut clock, 0xFF68)?, 0xFF);
+    assert_eq!(memory.read(&mut clock, 0xFF69)?, 0xFF);
+    Ok(())
+}
+
+#[test]
+fn cgb_bg_attributes() -> Result<(), EmuError> {
+    for renderer in [Renderer::Scanline, Renderer::Fifo] {
+        let (mut memory, mut clock) = get_cgb_map(renderer)?;
+        // Tile 0 of bank 1 has color 3 on its left half and 1 on the right
+        memory.write(&mut clock, 0xFF4F, 1)?;
+        for row in 0..8 {
+            memory.write(&mut clock, 0x8000 + row * 2, 0xFF)?;
+            memory.write(&mut clock, 0x8000 + row * 2 + 1, 0xF0)?;
+        }
+        // Palette 2, bank 1, then the same flipped horizontally
+        memory.write(&mut clock, 0x9800, 0x0A)?;
+        memory.write(&mut clock, 0x9801, 0x2A)?;
+        memory.write(&mut clock, 0xFF4F, 0)?;
+        write_palette(
+            &mut memory,
+            &mut clock,
+            0xFF68,
+            0x10,
+            &[0, 0x7C00, 0, 0x001F],
+        )?;
+        memory.write(&mut clock, 0xFF40, 0x91)?;
+        run_frame(&mut memory, &mut clock);
+        let frame = memory.framebuffer();
+        assert_eq!(frame[0], 0xFF0000);
+        assert_eq!(frame[4], 0x0000FF);
+        assert_eq!(frame[8], 0x0000FF);
+        assert_eq!(frame[12], 0xFF0000);
+        // Bank 0 tile 0 is empty, palette 0 is white
+        assert_eq!(frame[16], 0xFFFFFF);
+    }
+    Ok(())
+}
+
+#[test]
+fn cgb_sprite_priority() -> Result<(), EmuError> {
+    for renderer in [Renderer::Scanline, Renderer::Fifo] {
+        for (opri, color) in [(0, 0x00FF00), (1, 0x0000FF)] {
+            let (mut memory, mut clock) = get_cgb_map(renderer)?;
+            write_solid_tile(&mut memory, &mut clock, 1, 1)?;
+            // BG tile 2 has color 1 and priority over sprites in the second map column
+            write_solid_tile(&mut memory, &mut clock, 2, 1)?;
+            memory.write(&mut clock, 0x9801, 2)?;
+            memory.write(&mut clock, 0xFF4F, 1)?;
+            memory.write(&mut clock, 0x9801, 0x80)?;
+            memory.write(&mut clock, 0xFF4F, 0)?;
+            // Sprite 0 (palette 1) is right of sprite 1 (palette 2)
+            for (i, value) in [16, 12, 1, 0x01, 16, 8, 1, 0x02].iter().enumerate() {
+                memory.write(&mut clock, 0xFE00 + i as u16, *value)?;
+            }
+            write_palette(&mut memory, &mut clock, 0xFF6A, 0x08, &[0, 0x03E0])?;
+            write_palette(&mut memory, &mut clock, 0xFF6A, 0x10, &[0, 0x7C00])?;
+            write_palette(&mut memory, &mut clock, 0xFF68, 0x00, &[0x7FFF, 0x0000])?;
+            memory.write(&mut clock, 0xFF6C, opri)?;
+            memory.write(&mut clock, 0xFF40, 0x93)?;
+            run_frame(&mut memory, &mut clock);
+            let frame = memory.framebuffer();
+            assert_eq!(frame[0], 0x0000FF);
+            // Where they overlap OAM order wins, unless OPRI asks for DMG ordering
+            assert_eq!(frame[4], color);
+            assert_eq!(frame[7], color);
+            // BG attribute priority
+            assert_eq!(frame[8], 0x000000);
+
+            // LCDC bit 0 takes the BG priority away
+            memory.write(&mut clock, 0xFF40, 0x92)?;
+            run_frame(&mut memory, &mut clock);
+            assert_eq!(memory.framebuffer()[8], 0x00FF00);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,224 @@
+use redgb::{
+    error::EmuError,
+    rom::{
+        cartridge_type::CartridgeType,
+        licensee::Licensee,
+        rom_error::RomError,
+        rom_info::{CGBMode, Destination},
+        rom_parser::{self, ParseMode},
+        rom_validation::{RomIssue, RomValidation},
+    },
+};
+
+// https://gbdev.io/pandocs/The_Cartridge_Header.html#0104-0133--nintendo-logo
+const NINTENDO: [u8; 48] = [
+    0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D,
+    0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E, 0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99,
+    0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC, 0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E,
+];
+
+/// 32 KiB ROM with a valid logo and header checksum
+fn get_mock_rom() -> Vec<u8> {
+    let mut rom = vec![0; 0x8000];
+    rom[0x104..=0x133].copy_from_slice(&NINTENDO);
+    rom[0x134..0x138].copy_from_slice(b"TEST");
+    rom[0x143] = 0x80;
+    fix_header_checksum(&mut rom);
+    rom
+}
+
+fn fix_header_checksum(rom: &mut [u8]) {
+    rom[0x14D] = rom[0x134..=0x14C].iter().fold(0u8, |checksum, byte| {
+        checksum.wrapping_sub(*byte).wrapping_sub(1)
+    });
+}
+
+#[test]
+fn valid_header() -> Result<(), EmuError> {
+    let info = rom_parser::parse_rom_header(&get_mock_rom())?;
+    assert!(info.title.starts_with("TEST"));
+    assert_eq!(info.cgb, CGBMode::Color { exclusive: false });
+    assert_eq!(info.rom_banks, 2);
+    assert!(info.warnings.is_empty());
+    Ok(())
+}
+
+#[test]
+fn too_short() {
+    let rom = get_mock_rom();
+    assert_eq!(
+        rom_parser::parse_rom_header_with(&rom[..0x100], ParseMode::Lenient).unwrap_err(),
+        RomError::TooShort { len: 0x100 }
+    );
+}
+
+#[test]
+fn strict_errors() {
+    let mut rom = get_mock_rom();
+    rom[0x104] = 0;
+    assert_eq!(
+        rom_parser::parse_rom_header(&rom).unwrap_err(),
+        RomError::MissingLogo
+    );
+
+    let mut rom = get_mock_rom();
+    rom[0x14D] = rom[0x14D].wrapping_add(1);
+    assert!(matches!(
+        rom_parser::parse_rom_header(&rom),
+        Err(RomError::HeaderChecksum { .. })
+    ));
+}
+
+#[test]
+fn lenient_warnings() -> Result<(), EmuError> {
+    let mut rom = get_mock_rom();
+    rom[0x104] = 0;
+    let expected = rom[0x14D];
+    rom[0x14D] = expected.wrapping_add(1);
+    let info = rom_parser::parse_rom_header_with(&rom, ParseMode::Lenient)?;
+    assert_eq!(
+        info.warnings,
+        vec![
+            RomError::MissingLogo,
+            RomError::HeaderChecksum {
+                expected: expected.wrapping_add(1),
+                calculated: expected
+            }
+        ]
+    );
+    Ok(())
+}
+
+fn fix_global_checksum(rom: &mut [u8]) {
+    let checksum = rom
+        .iter()
+        .enumerate()
+        .filter(|(addr, _)| *addr != 0x14E && *addr != 0x14F)
+        .fold(0u16, |checksum, (_, byte)| {
+            checksum.wrapping_add(*byte as u16)
+        });
+    rom[0x14E..=0x14F].copy_from_slice(&checksum.to_be_bytes());
+}
+
+#[test]
+fn global_checksum() -> Result<(), EmuError> {
+    let mut rom = get_mock_rom();
+    rom[0x4000] = 0xAB;
+    fix_global_checksum(&mut rom);
+    let info = rom_parser::parse_rom_header(&rom)?;
+    let validation = RomValidation::new(&rom, &info);
+    assert!(validation.is_ok(), "{:?}", validation.issues);
+
+    rom[0x7FFF] = 0x01;
+    let validation = RomValidation::new(&rom, &info);
+    assert_eq!(
+        validation.issues,
+        vec![RomIssue::GlobalChecksum {
+            expected: info.rom_checksum,
+            calculated: info.rom_checksum.wrapping_add(1)
+        }]
+    );
+    Ok(())
+}
+
+#[test]
+fn rom_size() -> Result<(), EmuError> {
+    let mut rom = get_mock_rom();
+    // 64 KiB declared, 32 KiB in the file
+    rom[0x148] = 0x1;
+    fix_header_checksum(&mut rom);
+    fix_global_checksum(&mut rom);
+    let info = rom_parser::parse_rom_header(&rom)?;
+    assert_eq!(
+        RomValidation::new(&rom, &info).issues,
+        vec![RomIssue::Truncated {
+            declared: 0x10000,
+            actual: 0x8000
+        }]
+    );
+
+    // 3 and a half banks
+    rom.resize(0xE000, 0);
+    let validation = RomValidation::new(&rom, &info);
+    assert_eq!(validation.file_banks, 4);
+    assert!(
+        validation
+            .issues
+            .contains(&RomIssue::PartialBank { len: 0xE000 })
+    );
+
+    rom.resize(0x14000, 0);
+    fix_global_checksum(&mut rom);
+    assert_eq!(
+        RomValidation::new(&rom, &info).issues,
+        vec![
+            RomIssue::Oversized {
+                declared: 0x10000,
+                actual: 0x14000
+            },
+            RomIssue::BankCount { banks: 5 }
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn unknown_rom_size() -> Result<(), EmuError> {
+    let mut rom = get_mock_rom();
+    rom[0x148] = 0x20;
+    fix_header_checksum(&mut rom);
+    assert_eq!(
+        rom_parser::parse_rom_header(&rom).unwrap_err(),
+        RomError::UnknownRomSize { code: 0x20 }
+    );
+    let info = rom_parser::parse_rom_header_with(&rom, ParseMode::Lenient)?;
+    assert_eq!(info.rom_banks, 2);
+    Ok(())
+}
+
+#[test]
+fn cartridge_type() -> Result<(), EmuError> {
+    let mut rom = get_mock_rom();
+    rom[0x147] = 0x1B;
+    rom[0x149] = 0x2;
+    fix_header_checksum(&mut rom);
+    let info = rom_parser::parse_rom_header(&rom)?;
+    assert_eq!(
+        info.cartridge_type,
+        CartridgeType::Mbc5 {
+            ram: true,
+            battery: true,
+            rumble: false
+        }
+    );
+    assert_eq!(info.mem_banks, 1);
+    assert_eq!(info.cartridge_type.to_string(), "MBC5+RAM+BATTERY");
+    assert!(CartridgeType::from_code(0x10).has_timer());
+    assert!(CartridgeType::from_code(0x06).has_ram());
+    assert_eq!(CartridgeType::from_code(0x04), CartridgeType::Unknown(0x04));
+    Ok(())
+}
+
+#[test]
+fn licensee_and_destination() -> Result<(), EmuError> {
+    let mut rom = get_mock_rom();
+    rom[0x14A] = 0x1;
+    rom[0x14B] = 0x01;
+    rom[0x14C] = 0x2;
+    fix_header_checksum(&mut rom);
+    let info = rom_parser::parse_rom_header(&rom)?;
+    assert_eq!(info.licensee, Licensee::Old(0x01));
+    assert_eq!(info.licensee.name(), Some("Nintendo"));
+    assert_eq!(info.destination, Destination::Overseas);
+    assert_eq!(info.version, 2);
+
+    rom[0x144..=0x145].copy_from_slice(b"A4");
+    rom[0x14A] = 0x0;
+    rom[0x14B] = 0x33;
+    fix_header_checksum(&mut rom);
+    let info = rom_parser::parse_rom_header(&rom)?;
+    assert_eq!(info.licensee, Licensee::New(*b"A4"));
+    assert_eq!(info.licensee.to_string(), "Konami (Yu-Gi-Oh!)");
+    assert_eq!(info.destination, Destination::Japan);
+    Ok(())
+}
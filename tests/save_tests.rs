@@ -0,0 +1,149 @@
+use std::{fs, path::PathBuf};
+
+use redgb::{
+    cpu::clock::Clock,
+    error::EmuError,
+    mem::{map::MemoryMap, save::SaveFile},
+    rom::{cartridge_type::CartridgeType, rom_info::ROMInfo},
+};
+
+fn get_mock_map(cartridge_type: u8, mem_banks: u16) -> MemoryMap {
+    let info = ROMInfo {
+        cartridge_type: CartridgeType::from_code(cartridge_type),
+        rom_banks: 4,
+        mem_banks,
+        ..ROMInfo::default()
+    };
+    MemoryMap::init_rom(vec![0; 4 * 0x4000], info)
+}
+
+fn temp_save(name: &str) -> SaveFile {
+    let path: PathBuf =
+        std::env::temp_dir().join(format!("redgb_{name}_{}.sav", std::process::id()));
+    let _ = fs::remove_file(&path);
+    SaveFile::new(path)
+}
+
+#[test]
+fn ram_roundtrip() -> Result<(), EmuError> {
+    let mut clock = Clock::default();
+    let save = temp_save("ram_roundtrip");
+    let mut memory = get_mock_map(0x03, 4);
+    // A missing save is a fresh game
+    save.load(&mut memory)?;
+    memory.write(&mut clock, 0x0000, 0x0A)?;
+    memory.write(&mut clock, 0x6000, 0x01)?;
+    memory.write(&mut clock, 0x4000, 0x02)?;
+    memory.write(&mut clock, 0xA123, 0x77)?;
+    assert!(memory.take_save_dirty(&clock));
+    assert!(!memory.take_save_dirty(&clock));
+    save.flush(&memory)?;
+
+    let data = fs::read(save.path()).expect("the save was just written");
+    assert_eq!(data.len(), 4 * 0x2000);
+    assert_eq!(data[2 * 0x2000 + 0x123], 0x77);
+
+    let mut memory = get_mock_map(0x03, 4);
+    save.load(&mut memory)?;
+    memory.write(&mut clock, 0x0000, 0x0A)?;
+    memory.write(&mut clock, 0x6000, 0x01)?;
+    memory.write(&mut clock, 0x4000, 0x02)?;
+    assert_eq!(memory.read(&mut clock, 0xA123)?, 0x77);
+    let _ = fs::remove_file(save.path());
+    Ok(())
+}
+
+#[test]
+fn rtc_footer() -> Result<(), EmuError> {
+    let mut clock = Clock::default();
+    let save = temp_save("rtc_footer");
+    let mut memory = get_mock_map(0x10, 1);
+    memory.write(&mut clock, 0x0000, 0x0A)?;
+    memory.write(&mut clock, 0xA000, 0x12)?;
+    memory.write(&mut clock, 0x4000, 0x09)?;
+    memory.write(&mut clock, 0xA000, 42)?;
+    save.flush(&memory)?;
+    let data = fs::read(save.path()).expect("the save was just written");
+    assert_eq!(data.len(), 0x2000 + 48);
+    // Current minutes, then latched minutes
+    assert_eq!(data[0x2000 + 4], 42);
+    assert_eq!(data[0x2000 + 24], 42);
+
+    let mut memory = get_mock_map(0x10, 1);
+    save.load(&mut memory)?;
+    let rtc = memory.rtc_mut().expect("MBC3+TIMER has an RTC");
+    assert_eq!(rtc.registers().minutes, 42);
+    memory.write(&mut clock, 0x0000, 0x0A)?;
+    assert_eq!(memory.read(&mut clock, 0xA000)?, 0x12);
+
+    // A 44 byte footer with a 32-bit timestamp is accepted too
+    fs::write(save.path(), &data[..0x2000 + 44]).expect("temp dir is writable");
+    save.load(&mut get_mock_map(0x10, 1))?;
+    fs::write(save.path(), &data[..0x2000 + 10]).expect("temp dir is writable");
+    assert!(matches!(
+        save.load(&mut get_mock_map(0x10, 1)),
+        Err(EmuError::SaveFile(_))
+    ));
+    let _ = fs::remove_file(save.path());
+    Ok(())
+}
+
+#[test]
+fn dirty_tracking() -> Result<(), EmuError> {
+    let mut clock = Clock::default();
+    // Writes with RAM disabled or missing don't change anything
+    let mut memory = get_mock_map(0x03, 1);
+    memory.write(&mut clock, 0xA000, 0x12)?;
+    assert!(!memory.take_save_dirty(&clock));
+    memory.write(&mut clock, 0x0000, 0x0A)?;
+    memory.write(&mut clock, 0xA000, 0x00)?;
+    assert!(!memory.take_save_dirty(&clock));
+    let mut memory = get_mock_map(0x01, 0);
+    memory.write(&mut clock, 0x0000, 0x0A)?;
+    memory.write(&mut clock, 0xA000, 0x12)?;
+    assert!(!memory.take_save_dirty(&clock));
+
+    // RTC writes, latches and the clock counting on its own do
+    let mut memory = get_mock_map(0x10, 1);
+    memory.write(&mut clock, 0x0000, 0x0A)?;
+    memory.write(&mut clock, 0x4000, 0x09)?;
+    memory.write(&mut clock, 0xA000, 42)?;
+    assert!(memory.take_save_dirty(&clock));
+    clock.t_cycles += 4_194_304;
+    assert!(memory.take_save_dirty(&clock));
+    assert!(!memory.take_save_dirty(&clock));
+    memory.write(&mut clock, 0x6000, 0x00)?;
+    memory.write(&mut clock, 0x6000, 0x01)?;
+    assert!(memory.take_save_dirty(&clock));
+    Ok(())
+}
+
+#[test]
+fn rtc_wall_clock_catch_up() -> Result<(), EmuError> {
+    let mut clock = Clock::default();
+    let save = temp_save("rtc_wall_clock");
+    let mut memory = get_mock_map(0x10, 1);
+    memory.write(&mut clock, 0x0000, 0x0A)?;
+    memory.write(&mut clock, 0x4000, 0x0A)?;
+    memory.write(&mut clock, 0xA000, 5)?;
+    save.flush(&memory)?;
+    // Saved an hour ago
+    let mut data = fs::read(save.path()).expect("the save was just written");
+    let timestamp = u64::from_le_bytes(data[0x2000 + 40..].try_into().unwrap()) - 3600;
+    data[0x2000 + 40..].copy_from_slice(&timestamp.to_le_bytes());
+    fs::write(save.path(), &data).expect("temp dir is writable");
+
+    // Emulated time only, the hour is lost
+    let mut memory = get_mock_map(0x10, 1);
+    save.load(&mut memory)?;
+    assert_eq!(memory.rtc_mut().map(|rtc| rtc.registers().hours), Some(5));
+
+    let mut memory = get_mock_map(0x10, 1);
+    let rtc = memory.rtc_mut().expect("MBC3+TIMER has an RTC");
+    rtc.set_wall_clock(true);
+    save.load(&mut memory)?;
+    assert_eq!(memory.rtc_mut().map(|rtc| rtc.registers().hours), Some(6));
+    assert!(memory.take_save_dirty(&clock));
+    let _ = fs::remove_file(save.path());
+    Ok(())
+}
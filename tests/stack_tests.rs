@@ -0,0 +1,120 @@
+use redgb::{
+    cpu::{
+        alu,
+        clock::Clock,
+        cpu_context::CpuContext,
+        reg_file::{Flag, Modes, RegFile},
+    },
+    error::EmuError,
+    mem::map::MemoryMap,
+    rom::rom_info::ROMInfo,
+};
+
+fn get_mock_context(rom: Vec<u8>) -> CpuContext {
+    let mut context = CpuContext::init(
+        RegFile::new(Modes::CGBDMG),
+        MemoryMap::init_rom(rom, ROMInfo::default()),
+        Clock::default(),
+    );
+    context.registers.pc = 0;
+    context
+}
+
+#[test]
+fn push_bc_pop_de() -> Result<(), EmuError> {
+    let mut context = get_mock_context(vec![0xC5, 0xD1, 0xDD]);
+    alu::write_u16(&mut context.registers.c, &mut context.registers.b, 0x1234);
+    let _ = context.start_exec_cycle();
+    assert_eq!(
+        alu::read_u16(&context.registers.e, &context.registers.d),
+        0x1234
+    );
+    assert_eq!(context.registers.sp, 0xFFFE);
+    assert_eq!(context.clock.m_cycles, 8);
+    Ok(())
+}
+
+#[test]
+fn pop_af() -> Result<(), EmuError> {
+    let mut context = get_mock_context(vec![0xF1, 0xDD]);
+    context.registers.sp = 0xC000;
+    context.memory.write(&mut context.clock, 0xC000, 0xFF)?;
+    context.memory.write(&mut context.clock, 0xC001, 0x12)?;
+    let _ = context.start_exec_cycle();
+    assert_eq!(context.registers.a, 0x12);
+    assert_eq!(context.registers.f, 0xF0);
+    assert_eq!(context.registers.sp, 0xC002);
+    assert_eq!(context.clock.m_cycles, 6);
+    Ok(())
+}
+
+#[test]
+fn call_ret() -> Result<(), EmuError> {
+    let mut context = get_mock_context(vec![0xCD, 0x05, 0x00, 0xDD, 0xDD, 0xC9]);
+    let _ = context.start_exec_cycle();
+    assert_eq!(context.registers.pc, 4);
+    assert_eq!(context.registers.sp, 0xFFFE);
+    assert_eq!(context.clock.m_cycles, 11);
+    Ok(())
+}
+
+#[test]
+fn call_nz() -> Result<(), EmuError> {
+    // Doesn't call
+    let mut context = get_mock_context(vec![0xC4, 0x05, 0x00, 0xDD, 0xDD, 0xDD]);
+    let _ = context.registers.set_flag(Flag::Zero, Some(true));
+    let _ = context.start_exec_cycle();
+    assert_eq!(context.registers.pc, 4);
+    assert_eq!(context.registers.sp, 0xFFFE);
+    assert_eq!(context.clock.m_cycles, 4);
+
+    // Calls
+    context.registers.pc = 0;
+    context.clock.m_cycles = 0;
+    let _ = context.registers.set_flag(Flag::Zero, Some(false));
+    let _ = context.start_exec_cycle();
+    assert_eq!(context.registers.pc, 6);
+    assert_eq!(context.registers.sp, 0xFFFC);
+    assert_eq!(context.clock.m_cycles, 7);
+    Ok(())
+}
+
+#[test]
+fn ret_c() -> Result<(), EmuError> {
+    // Returns
+    let mut context = get_mock_context(vec![0xD8, 0xDD, 0xDD, 0xDD]);
+    context.registers.sp = 0xC000;
+    context.memory.write(&mut context.clock, 0xC000, 0x03)?;
+    context.memory.write(&mut context.clock, 0xC001, 0x00)?;
+    context.clock.m_cycles = 0;
+    let _ = context.registers.set_flag(Flag::Carry, Some(true));
+    let _ = context.start_exec_cycle();
+    assert_eq!(context.registers.pc, 4);
+    assert_eq!(context.registers.sp, 0xC002);
+    assert_eq!(context.clock.m_cycles, 6);
+
+    // Doesn't return
+    context.registers.pc = 0;
+    context.registers.sp = 0xC000;
+    context.clock.m_cycles = 0;
+    let _ = context.registers.set_flag(Flag::Carry, Some(false));
+    let _ = context.start_exec_cycle();
+    assert_eq!(context.registers.pc, 2);
+    assert_eq!(context.registers.sp, 0xC000);
+    assert_eq!(context.clock.m_cycles, 3);
+    Ok(())
+}
+
+#[test]
+fn rst_38() -> Result<(), EmuError> {
+    let mut rom = vec![0xDD; 0x40];
+    rom[0] = 0xFF;
+    let mut context = get_mock_context(rom);
+    let _ = context.start_exec_cycle();
+    assert_eq!(context.registers.pc, 0x39);
+    assert_eq!(context.registers.sp, 0xFFFC);
+    assert_eq!(context.memory.read(&mut context.clock, 0xFFFC)?, 0x01);
+    assert_eq!(context.memory.read(&mut context.clock, 0xFFFD)?, 0x00);
+    assert_eq!(context.clock.m_cycles, 7);
+    Ok(())
+}
@@ -0,0 +1,189 @@
+use redgb::{
+    cpu::{
+        clock::Clock,
+        cpu_context::{CpuContext, RunState},
+        interrupts::Interrupt,
+        reg_file::{Modes, RegFile},
+    },
+    error::EmuError,
+    mem::map::MemoryMap,
+    rom::rom_info::ROMInfo,
+};
+
+fn get_mock_map() -> MemoryMap {
+    MemoryMap::new(vec![0; 0x8000], ROMInfo::default(), Modes::DMG)
+}
+
+/// Resets DIV and starts the timer with `tac`, the divider is 4 T-C past the reset afterwards
+fn start_timer(memory: &mut MemoryMap, clock: &mut Clock, tac: u8) -> Result<(), EmuError> {
+    memory.write(clock, 0xFF0F, 0x00)?;
+    memory.write(clock, 0xFF04, 0x00)?;
+    memory.write(clock, 0xFF07, tac)
+}
+
+fn idle(memory: &mut MemoryMap, clock: &mut Clock, m_cycles: u32) {
+    for _ in 0..m_cycles {
+        memory.tick(clock);
+    }
+}
+
+#[test]
+fn div() -> Result<(), EmuError> {
+    let mut clock = Clock::default();
+    let mut memory = get_mock_map();
+    memory.write(&mut clock, 0xFF04, 0x12)?;
+    idle(&mut memory, &mut clock, 63);
+    // The read is the 64th M-C
+    assert_eq!(memory.read(&mut clock, 0xFF04)?, 0x01);
+    idle(&mut memory, &mut clock, 63);
+    assert_eq!(memory.read(&mut clock, 0xFF04)?, 0x02);
+    Ok(())
+}
+
+#[test]
+fn tima_frequencies() -> Result<(), EmuError> {
+    for (tac, period) in [(0x04, 256), (0x05, 4), (0x06, 16), (0x07, 64)] {
+        let mut clock = Clock::default();
+        let mut memory = get_mock_map();
+        start_timer(&mut memory, &mut clock, tac)?;
+        // 1 M-C already passed since the reset
+        idle(&mut memory, &mut clock, period - 1);
+        assert_eq!(memory.read(&mut clock, 0xFF05)?, 0x01, "TAC {tac:#X}");
+        idle(&mut memory, &mut clock, 2 * period - 1);
+        assert_eq!(memory.read(&mut clock, 0xFF05)?, 0x03, "TAC {tac:#X}");
+    }
+    Ok(())
+}
+
+#[test]
+fn disabled() -> Result<(), EmuError> {
+    let mut clock = Clock::default();
+    let mut memory = get_mock_map();
+    start_timer(&mut memory, &mut clock, 0x01)?;
+    idle(&mut memory, &mut clock, 100);
+    assert_eq!(memory.read(&mut clock, 0xFF05)?, 0x00);
+    Ok(())
+}
+
+#[test]
+fn delayed_reload() -> Result<(), EmuError> {
+    let mut clock = Clock::default();
+    let mut memory = get_mock_map();
+    memory.write(&mut clock, 0xFF06, 0x10)?;
+    memory.write(&mut clock, 0xFF05, 0xFF)?;
+    start_timer(&mut memory, &mut clock, 0x05)?;
+    idle(&mut memory, &mut clock, 2);
+    // TIMA overflows on this read and reads 0 for one M-C
+    assert_eq!(memory.read(&mut clock, 0xFF05)?, 0x00);
+    assert!(!memory.interrupt_requested(Interrupt::Timer));
+    assert_eq!(memory.read(&mut clock, 0xFF05)?, 0x10);
+    assert!(memory.interrupt_requested(Interrupt::Timer));
+    Ok(())
+}
+
+#[test]
+fn tima_write_cancels_reload() -> Result<(), EmuError> {
+    let mut clock = Clock::default();
+    let mut memory = get_mock_map();
+    memory.write(&mut clock, 0xFF06, 0x10)?;
+    memory.write(&mut clock, 0xFF05, 0xFF)?;
+    start_timer(&mut memory, &mut clock, 0x05)?;
+    idle(&mut memory, &mut clock, 2);
+    // Same M-C as the overflow
+    memory.write(&mut clock, 0xFF05, 0x42)?;
+    idle(&mut memory, &mut clock, 1);
+    assert_eq!(memory.read(&mut clock, 0xFF05)?, 0x42);
+    assert!(!memory.interrupt_requested(Interrupt::Timer));
+    Ok(())
+}
+
+#[test]
+fn write_during_reload() -> Result<(), EmuError> {
+    let mut clock = Clock::default();
+    let mut memory = get_mock_map();
+    memory.write(&mut clock, 0xFF06, 0x10)?;
+    memory.write(&mut clock, 0xFF05, 0xFF)?;
+    start_timer(&mut memory, &mut clock, 0x05)?;
+    idle(&mut memory, &mut clock, 3);
+    // TMA is loaded this M-C, the TIMA write is ignored and the new TMA goes through
+    memory.write(&mut clock, 0xFF05, 0x42)?;
+    assert_eq!(memory.read(&mut clock, 0xFF05)?, 0x10);
+    assert!(memory.interrupt_requested(Interrupt::Timer));
+
+    let mut memory = get_mock_map();
+    memory.write(&mut clock, 0xFF06, 0x10)?;
+    memory.write(&mut clock, 0xFF05, 0xFF)?;
+    start_timer(&mut memory, &mut clock, 0x05)?;
+    idle(&mut memory, &mut clock, 3);
+    memory.write(&mut clock, 0xFF06, 0x20)?;
+    assert_eq!(memory.read(&mut clock, 0xFF05)?, 0x20);
+    Ok(())
+}
+
+#[test]
+fn div_write_glitch() -> Result<(), EmuError> {
+    let mut clock = Clock::default();
+    let mut memory = get_mock_map();
+    start_timer(&mut memory, &mut clock, 0x05)?;
+    // Bit 3 of the divider is set, resetting it is a falling edge
+    idle(&mut memory, &mut clock, 1);
+    memory.write(&mut clock, 0xFF04, 0x00)?;
+    assert_eq!(memory.read(&mut clock, 0xFF05)?, 0x01);
+    Ok(())
+}
+
+#[test]
+fn tac_write_glitch() -> Result<(), EmuError> {
+    let mut clock = Clock::default();
+    let mut memory = get_mock_map();
+    start_timer(&mut memory, &mut clock, 0x05)?;
+    idle(&mut memory, &mut clock, 1);
+    // Disabling the timer while bit 3 is set
+    memory.write(&mut clock, 0xFF07, 0x01)?;
+    assert_eq!(memory.read(&mut clock, 0xFF05)?, 0x01);
+    Ok(())
+}
+
+#[test]
+fn timer_interrupt_wakes_halt() -> Result<(), EmuError> {
+    // halt, nop
+    let mut rom = vec![0xDD; 0x100];
+    rom[..2].copy_from_slice(&[0x76, 0x00]);
+    let mut context = CpuContext::init(
+        RegFile::new(Modes::DMG),
+        MemoryMap::new(rom, ROMInfo::default(), Modes::DMG),
+        Clock::default(),
+    );
+    context.registers.pc = 0;
+    context.memory.write(&mut context.clock, 0xFFFF, 0x04)?;
+    start_timer(&mut context.memory, &mut context.clock, 0x05)?;
+    context.step()?;
+    assert_eq!(context.state, RunState::Halted);
+    // 256 increments until the overflow
+    for _ in 0..4 * 256 {
+        context.step()?;
+    }
+    assert_eq!(context.state, RunState::Running);
+    Ok(())
+}
+
+#[test]
+fn double_speed() -> Result<(), EmuError> {
+    let mut clock = Clock::default();
+    let mut memory = MemoryMap::new(vec![0; 0x8000], ROMInfo::default(), Modes::CGB);
+    memory.write(&mut clock, 0xFF4D, 0x01)?;
+    memory.switch_speed();
+    memory.write(&mut clock, 0xFF04, 0x00)?;
+    let start = clock.t_cycles;
+    // DIV still counts M-Cs, which now take half the time
+    idle(&mut memory, &mut clock, 63);
+    assert_eq!(memory.read(&mut clock, 0xFF04)?, 0x01);
+    assert_eq!(clock.t_cycles - start, 128);
+
+    // The PPU runs in real time, a line takes twice as many M-Cs
+    memory.write(&mut clock, 0xFF40, 0x00)?;
+    memory.write(&mut clock, 0xFF40, 0x91)?;
+    idle(&mut memory, &mut clock, 227);
+    assert_eq!(memory.read(&mut clock, 0xFF44)?, 1);
+    Ok(())
+}